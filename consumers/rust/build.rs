@@ -3,7 +3,7 @@ fn main() {
         .out_dir("src/pb")
         .compile(
             &["sf/substreams/v1/substreams.proto", "tokens.proto"],
-            &["../../../substreams/proto", "../../eth-token/proto/"],
+            &["../../../substreams/proto", "../../modules/token-common/proto/"],
         )
         .unwrap();
 }
@@ -0,0 +1,280 @@
+use num_bigint::BigUint;
+use substreams::hex;
+use substreams_ethereum::pb::eth as ethpb;
+use token_common::abi;
+
+use crate::pb::nfts::Standard;
+
+/// `Transfer(address,address,uint256)`, an ERC20 emits the same signature
+/// without indexing the value.
+const TRANSFER_EVENT: [u8; 32] =
+    hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+/// `TransferSingle(address,address,address,uint256,uint256)`
+const TRANSFER_SINGLE_EVENT: [u8; 32] =
+    hex!("c3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62");
+
+/// `TransferBatch(address,address,address,uint256[],uint256[])`
+const TRANSFER_BATCH_EVENT: [u8; 32] =
+    hex!("4a39dc06d4c0dbc64b70af90fd698a233a518aa5d07e595d983b8c0526c8f7fb");
+
+pub struct Transfer {
+    pub standard: Standard,
+    /// empty for an ERC721
+    pub operator: Vec<u8>,
+    pub from: Vec<u8>,
+    pub to: Vec<u8>,
+    pub token_id: BigUint,
+    pub value: BigUint,
+}
+
+/// Tokens moved by the log, in the order of the event, nothing for any other
+/// event or one that cannot be decoded.
+pub fn decode_transfers(log: &ethpb::v2::Log) -> Vec<Transfer> {
+    if log.topics.len() != 4 {
+        return vec![];
+    }
+
+    let decoded = match log.topics[0].as_slice() {
+        topic if topic == TRANSFER_EVENT => decode_transfer(log).map(|transfer| vec![transfer]),
+        topic if topic == TRANSFER_SINGLE_EVENT => {
+            decode_transfer_single(log).map(|transfer| vec![transfer])
+        }
+        topic if topic == TRANSFER_BATCH_EVENT => decode_transfer_batch(log),
+        _ => Ok(vec![]),
+    };
+
+    decoded.unwrap_or_else(|error| {
+        substreams::log::info!("skipping invalid transfer log: {}", error);
+        vec![]
+    })
+}
+
+fn decode_transfer(log: &ethpb::v2::Log) -> Result<Transfer, String> {
+    Ok(Transfer {
+        standard: Standard::Erc721,
+        operator: vec![],
+        from: abi::read_address(&log.topics[1])?,
+        to: abi::read_address(&log.topics[2])?,
+        token_id: abi::read_uint256(&log.topics[3])?,
+        value: BigUint::from(1u32),
+    })
+}
+
+fn decode_transfer_single(log: &ethpb::v2::Log) -> Result<Transfer, String> {
+    if log.data.len() != 64 {
+        return Err(format!(
+            "TransferSingle invalid data length: {}",
+            log.data.len()
+        ));
+    }
+
+    Ok(Transfer {
+        standard: Standard::Erc1155,
+        operator: abi::read_address(&log.topics[1])?,
+        from: abi::read_address(&log.topics[2])?,
+        to: abi::read_address(&log.topics[3])?,
+        token_id: abi::read_uint256(&log.data[0..32])?,
+        value: abi::read_uint256(&log.data[32..64])?,
+    })
+}
+
+fn decode_transfer_batch(log: &ethpb::v2::Log) -> Result<Vec<Transfer>, String> {
+    if log.data.len() < 64 {
+        return Err(format!(
+            "TransferBatch invalid data length: {}",
+            log.data.len()
+        ));
+    }
+
+    let ids = read_uint256_array(&log.data, &log.data[0..32])?;
+    let values = read_uint256_array(&log.data, &log.data[32..64])?;
+    if ids.len() != values.len() {
+        return Err(format!(
+            "TransferBatch has {} ids and {} values",
+            ids.len(),
+            values.len()
+        ));
+    }
+
+    let operator = abi::read_address(&log.topics[1])?;
+    let from = abi::read_address(&log.topics[2])?;
+    let to = abi::read_address(&log.topics[3])?;
+
+    Ok(ids
+        .into_iter()
+        .zip(values)
+        .map(|(token_id, value)| Transfer {
+            standard: Standard::Erc1155,
+            operator: operator.clone(),
+            from: from.clone(),
+            to: to.clone(),
+            token_id,
+            value,
+        })
+        .collect())
+}
+
+/// Decodes the ABI encoded `uint256[]` found at `offset` in `data`, its length
+/// word followed by its items.
+fn read_uint256_array(data: &[u8], offset: &[u8]) -> Result<Vec<BigUint>, String> {
+    let start = abi::read_uint32(offset)? as usize;
+    if data.len() < 32 || start > data.len() - 32 {
+        return Err(format!(
+            "array offset {} out of {} bytes",
+            start,
+            data.len()
+        ));
+    }
+
+    let len = abi::read_uint32(&data[start..start + 32])? as usize;
+    let items = &data[start + 32..];
+    if len > items.len() / 32 {
+        return Err(format!(
+            "array of {} items out of {} bytes",
+            len,
+            items.len()
+        ));
+    }
+
+    items.chunks(32).take(len).map(abi::read_uint256).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATOR: [u8; 20] = hex!("1e0049783f008a0085193e00003d00cd54003c71");
+    const FROM: [u8; 20] = hex!("8ba1f109551bd432803012645ac136ddd64dba72");
+    const TO: [u8; 20] = hex!("ab5801a7d398351b8be11c439e05c5b3259aec9b");
+
+    fn topic(address: [u8; 20]) -> Vec<u8> {
+        [vec![0; 12], address.to_vec()].concat()
+    }
+
+    fn word(value: u64) -> Vec<u8> {
+        [vec![0; 24], value.to_be_bytes().to_vec()].concat()
+    }
+
+    fn log(event: [u8; 32], topics: Vec<Vec<u8>>, data: Vec<Vec<u8>>) -> ethpb::v2::Log {
+        ethpb::v2::Log {
+            topics: [vec![event.to_vec()], topics].concat(),
+            data: data.concat(),
+            ..Default::default()
+        }
+    }
+
+    fn batch(data: Vec<Vec<u8>>) -> ethpb::v2::Log {
+        log(
+            TRANSFER_BATCH_EVENT,
+            vec![topic(OPERATOR), topic(FROM), topic(TO)],
+            data,
+        )
+    }
+
+    fn moved(transfers: &[Transfer]) -> Vec<(BigUint, BigUint)> {
+        transfers
+            .iter()
+            .map(|transfer| (transfer.token_id.clone(), transfer.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn erc721_transfers_index_the_token_id() {
+        let transfers = decode_transfers(&log(
+            TRANSFER_EVENT,
+            vec![topic(FROM), topic(TO), word(7804)],
+            vec![],
+        ));
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].standard, Standard::Erc721);
+        assert!(transfers[0].operator.is_empty());
+        assert_eq!((transfers[0].from.as_slice(), transfers[0].to.as_slice()), (&FROM[..], &TO[..]));
+        assert_eq!(moved(&transfers), vec![(BigUint::from(7804u32), BigUint::from(1u32))]);
+
+        // an ERC20 transfer keeps its value in the data
+        let erc20 = log(TRANSFER_EVENT, vec![topic(FROM), topic(TO)], vec![word(7804)]);
+        assert!(decode_transfers(&erc20).is_empty());
+    }
+
+    #[test]
+    fn transfer_single() {
+        let single = log(
+            TRANSFER_SINGLE_EVENT,
+            vec![topic(OPERATOR), topic(FROM), topic(TO)],
+            vec![word(10), word(3)],
+        );
+
+        let transfers = decode_transfers(&single);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].standard, Standard::Erc1155);
+        assert_eq!(transfers[0].operator, OPERATOR.to_vec());
+        assert_eq!((transfers[0].from.as_slice(), transfers[0].to.as_slice()), (&FROM[..], &TO[..]));
+        assert_eq!(moved(&transfers), vec![(BigUint::from(10u32), BigUint::from(3u32))]);
+
+        let mut truncated = single;
+        truncated.data.truncate(63);
+        assert!(decode_transfers(&truncated).is_empty());
+    }
+
+    #[test]
+    fn transfer_batch() {
+        // ids at 0x40, values at 0xa0, like solidity encodes them
+        let transfers = decode_transfers(&batch(vec![
+            word(0x40),
+            word(0xa0),
+            word(2),
+            word(1),
+            word(2),
+            word(2),
+            word(5),
+            word(8),
+        ]));
+
+        assert!(transfers.iter().all(|transfer| transfer.operator == OPERATOR.to_vec()));
+        assert_eq!(
+            moved(&transfers),
+            vec![
+                (BigUint::from(1u32), BigUint::from(5u32)),
+                (BigUint::from(2u32), BigUint::from(8u32)),
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_batch_follows_the_array_offsets() {
+        // values encoded before the ids, the offsets say where each array is
+        let reordered = decode_transfers(&batch(vec![
+            word(0x80),
+            word(0x40),
+            word(1),
+            word(9),
+            word(1),
+            word(42),
+        ]));
+        assert_eq!(moved(&reordered), vec![(BigUint::from(42u32), BigUint::from(9u32))]);
+
+        // both offsets pointing to the same array
+        let shared = decode_transfers(&batch(vec![word(0x40), word(0x40), word(1), word(3)]));
+        assert_eq!(moved(&shared), vec![(BigUint::from(3u32), BigUint::from(3u32))]);
+
+        // offset past the data
+        assert!(decode_transfers(&batch(vec![word(0x40), word(0x1000), word(1), word(1)])).is_empty());
+
+        // length larger than the items left
+        assert!(decode_transfers(&batch(vec![word(0x40), word(0x40), word(3), word(1)])).is_empty());
+
+        // ids and values of different lengths
+        assert!(decode_transfers(&batch(vec![
+            word(0x40),
+            word(0xa0),
+            word(2),
+            word(1),
+            word(2),
+            word(1),
+            word(5),
+        ]))
+        .is_empty());
+    }
+}
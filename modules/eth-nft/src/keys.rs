@@ -0,0 +1,14 @@
+//! Keys of the eth-nft stores, addresses are lowercase hex without `0x` and
+//! token ids decimal.
+
+pub fn contract(address: &str) -> String {
+    format!("contract:{}", address)
+}
+
+pub fn owner(contract: &str, token_id: &str) -> String {
+    format!("owner:{}:{}", contract, token_id)
+}
+
+pub fn balance(contract: &str, token_id: &str, holder: &str) -> String {
+    format!("balance:{}:{}:{}", contract, token_id, holder)
+}
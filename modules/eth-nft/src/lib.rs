@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use substreams::errors::Error;
+use substreams::{log, proto, store, Hex};
+use substreams_ethereum::pb::eth as ethpb;
+use token_common::bytecode::Standard;
+use token_common::contracts::{self, Candidate};
+use token_common::rpc::{self, Method, MethodResponses, RpcRequest};
+
+use pb::nfts;
+
+mod events;
+mod keys;
+mod pb;
+
+const ZERO_ADDRESS: &str = "0000000000000000000000000000000000000000";
+
+const NFT_METHODS: &[Method] = &[Method::SupportsErc721, Method::SupportsErc1155];
+
+#[substreams::handlers::map]
+fn map_nft_contracts(blk: ethpb::v2::Block) -> Result<nfts::NftContracts, Error> {
+    let block_number = blk.number;
+    let candidates = contracts::candidates(blk, &[Standard::Erc721, Standard::Erc1155]);
+    if candidates.is_empty() {
+        return Ok(nfts::NftContracts { contracts: vec![] });
+    }
+
+    // a single round-trip for all the candidates of the block
+    let mut request = RpcRequest::default();
+    for candidate in &candidates {
+        request.add(&candidate.address, NFT_METHODS);
+    }
+    let responses = substreams_ethereum::rpc::eth_call(request.calls()).responses;
+    let responses = request.responses(responses);
+
+    let contracts = candidates
+        .iter()
+        .filter_map(|candidate| {
+            nft_contract(candidate, block_number, &responses[&candidate.address])
+        })
+        .collect();
+
+    Ok(nfts::NftContracts { contracts })
+}
+
+/// Contract of a candidate whose `supportsInterface` confirms the standard
+/// its bytecode implements. A proxy's code has no selectors to scan, the
+/// interface it supports is its standard.
+fn nft_contract(
+    candidate: &Candidate,
+    block_number: u64,
+    responses: &MethodResponses,
+) -> Option<nfts::NftContract> {
+    let erc721 = rpc::decode_supports_interface(responses.get(Method::SupportsErc721));
+    let erc1155 = rpc::decode_supports_interface(responses.get(Method::SupportsErc1155));
+
+    let standard = match candidate.standard {
+        Standard::Erc721 if erc721 => nfts::Standard::Erc721,
+        Standard::Erc1155 if erc1155 => nfts::Standard::Erc1155,
+        Standard::Unknown if erc1155 => nfts::Standard::Erc1155,
+        Standard::Unknown if erc721 => nfts::Standard::Erc721,
+        _ => {
+            log::debug!(
+                "{} does not support the ERC721 or ERC1155 interface",
+                Hex(&candidate.address)
+            );
+            return None;
+        }
+    };
+
+    log::debug!("{} is an {:?} contract", Hex(&candidate.address), standard);
+    Some(nfts::NftContract {
+        address: Hex(&candidate.address).to_string(),
+        standard: standard as i32,
+        creator: Hex(&candidate.creator).to_string(),
+        created_at_block: block_number,
+        created_at_tx: Hex(&candidate.transaction).to_string(),
+        is_proxy: candidate.is_proxy,
+        implementation: Hex(&candidate.implementation).to_string(),
+    })
+}
+
+#[substreams::handlers::store]
+fn store_nft_contracts(contracts: nfts::NftContracts, store: store::StoreSet) {
+    for contract in contracts.contracts {
+        let key = keys::contract(&contract.address);
+        store.set(1, key, &proto::encode(&contract).unwrap());
+    }
+}
+
+#[substreams::handlers::map]
+fn map_nft_transfers(
+    blk: ethpb::v2::Block,
+    contracts_store: store::StoreGet,
+) -> Result<nfts::NftTransfers, Error> {
+    let mut transfers = nfts::NftTransfers { transfers: vec![] };
+
+    for trx in blk.transaction_traces {
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            let decoded = events::decode_transfers(&log);
+            if decoded.is_empty() {
+                continue;
+            }
+
+            let contract = Hex(&log.address).to_string();
+            if contracts_store
+                .get_last(&keys::contract(&contract))
+                .is_none()
+            {
+                continue; // not a contract known to store_nft_contracts
+            }
+
+            for transfer in decoded {
+                transfers.transfers.push(nfts::NftTransfer {
+                    contract: contract.clone(),
+                    standard: transfer.standard as i32,
+                    token_id: transfer.token_id.to_string(),
+                    from: Hex(&transfer.from).to_string(),
+                    to: Hex(&transfer.to).to_string(),
+                    value: transfer.value.to_string(),
+                    operator: Hex(&transfer.operator).to_string(),
+                    transaction_id: Hex(&trx.hash).to_string(),
+                    log_ordinal: log.block_index as u64,
+                });
+            }
+        }
+    }
+
+    Ok(transfers)
+}
+
+/// Owner of every ERC721 token under `owner:{contract}:{token_id}`, the zero
+/// address once burned.
+#[substreams::handlers::store]
+fn store_owners(transfers: nfts::NftTransfers, output: store::StoreSet) {
+    for transfer in transfers.transfers {
+        if transfer.standard != nfts::Standard::Erc721 as i32 {
+            continue;
+        }
+
+        output.set(
+            transfer.log_ordinal,
+            keys::owner(&transfer.contract, &transfer.token_id),
+            &Vec::from(transfer.to),
+        );
+    }
+}
+
+/// Balance of every holder of an ERC1155 token id, an id can have many
+/// holders. Transfers from the zero address mint, transfers to it burn.
+#[substreams::handlers::store]
+fn store_nft_balances(transfers: nfts::NftTransfers, output: store::StoreAddBigInt) {
+    for transfer in transfers.transfers {
+        if transfer.standard != nfts::Standard::Erc1155 as i32 {
+            continue;
+        }
+
+        let value = BigInt::from_str(&transfer.value).unwrap();
+        let ordinal = transfer.log_ordinal;
+        let contract = transfer.contract;
+        let token_id = transfer.token_id;
+
+        if transfer.from != ZERO_ADDRESS {
+            output.add(
+                ordinal,
+                keys::balance(&contract, &token_id, &transfer.from),
+                &-value.clone(),
+            );
+        }
+
+        if transfer.to != ZERO_ADDRESS {
+            output.add(
+                ordinal,
+                keys::balance(&contract, &token_id, &transfer.to),
+                &value,
+            );
+        }
+    }
+}
@@ -0,0 +1,2 @@
+#[path = "./sf.ethereum.nfts.v1.rs"]
+pub mod nfts;
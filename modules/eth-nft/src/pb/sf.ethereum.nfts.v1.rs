@@ -0,0 +1,62 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NftContracts {
+    #[prost(message, repeated, tag="1")]
+    pub contracts: ::prost::alloc::vec::Vec<NftContract>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NftContract {
+    #[prost(string, tag="1")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(enumeration="Standard", tag="2")]
+    pub standard: i32,
+    /// caller of the contract creation, or of initialize for a proxy
+    #[prost(string, tag="3")]
+    pub creator: ::prost::alloc::string::String,
+    #[prost(uint64, tag="4")]
+    pub created_at_block: u64,
+    #[prost(string, tag="5")]
+    pub created_at_tx: ::prost::alloc::string::String,
+    /// found through an OpenZeppelin proxy initialize call
+    #[prost(bool, tag="6")]
+    pub is_proxy: bool,
+    /// contract the proxy delegated initialize to, empty when not a proxy
+    #[prost(string, tag="7")]
+    pub implementation: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NftTransfers {
+    #[prost(message, repeated, tag="1")]
+    pub transfers: ::prost::alloc::vec::Vec<NftTransfer>,
+}
+/// A token moved by an ERC721 Transfer, an ERC1155 TransferSingle or one of the
+/// ids of an ERC1155 TransferBatch, which all share the log's ordinal. Mints are
+/// transfers from the zero address and burns transfers to it
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NftTransfer {
+    #[prost(string, tag="1")]
+    pub contract: ::prost::alloc::string::String,
+    #[prost(enumeration="Standard", tag="2")]
+    pub standard: i32,
+    #[prost(string, tag="3")]
+    pub token_id: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub to: ::prost::alloc::string::String,
+    /// always 1 for an ERC721
+    #[prost(string, tag="6")]
+    pub value: ::prost::alloc::string::String,
+    /// sender of an ERC1155 transfer, empty for an ERC721
+    #[prost(string, tag="7")]
+    pub operator: ::prost::alloc::string::String,
+    #[prost(string, tag="8")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="9")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Standard {
+    Erc721 = 0,
+    Erc1155 = 1,
+}
@@ -0,0 +1,114 @@
+use num_bigint::BigUint;
+use substreams::hex;
+use substreams_ethereum::pb::eth as ethpb;
+use token_common::abi;
+
+/// `Transfer(address,address,uint256)`, ERC721 uses the same signature with
+/// the token id indexed as a third topic.
+const TRANSFER_EVENT: [u8; 32] =
+    hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+/// `Approval(address,address,uint256)`, like `Transfer` ERC721 indexes the
+/// token id as a third topic.
+const APPROVAL_EVENT: [u8; 32] =
+    hex!("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925");
+
+pub struct Transfer {
+    pub from: Vec<u8>,
+    pub to: Vec<u8>,
+    pub value: BigUint,
+}
+
+/// ERC20 transfer of the log, `None` for any other event, ERC721 transfers
+/// included.
+pub fn decode_transfer(log: &ethpb::v2::Log) -> Option<Transfer> {
+    if log.topics.len() != 3 || log.topics[0] != TRANSFER_EVENT {
+        return None;
+    }
+
+    Some(Transfer {
+        from: abi::read_address(&log.topics[1]).ok()?,
+        to: abi::read_address(&log.topics[2]).ok()?,
+        value: abi::read_uint256(&log.data).ok()?,
+    })
+}
+
+pub struct Approval {
+    pub owner: Vec<u8>,
+    pub spender: Vec<u8>,
+    pub value: BigUint,
+}
+
+/// ERC20 approval of the log, `None` for any other event, ERC721 approvals
+/// included.
+pub fn decode_approval(log: &ethpb::v2::Log) -> Option<Approval> {
+    if log.topics.len() != 3 || log.topics[0] != APPROVAL_EVENT {
+        return None;
+    }
+
+    Some(Approval {
+        owner: abi::read_address(&log.topics[1]).ok()?,
+        spender: abi::read_address(&log.topics[2]).ok()?,
+        value: abi::read_uint256(&log.data).ok()?,
+    })
+}
+
+/// Whether an approved value is `2^256-1`, the allowance wallets ask for
+/// when a spender should never need approving again.
+pub fn is_unlimited(value: &BigUint) -> bool {
+    value.bits() == 256 && value.count_ones() == 256
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic(address: [u8; 20]) -> Vec<u8> {
+        [vec![0; 12], address.to_vec()].concat()
+    }
+
+    // approval of the Uniswap V2 router
+    fn approval_log(data: Vec<u8>) -> ethpb::v2::Log {
+        ethpb::v2::Log {
+            topics: vec![
+                APPROVAL_EVENT.to_vec(),
+                topic(hex!("8ba1f109551bd432803012645ac136ddd64dba72")),
+                topic(hex!("7a250d5630b4cf539739df2c5dacb4c659f2488d")),
+            ],
+            data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unlimited_approvals() {
+        let max = BigUint::from_bytes_be(&[0xff; 32]);
+        assert!(is_unlimited(&max));
+        assert!(!is_unlimited(&(max - 1u32)));
+        assert!(!is_unlimited(&BigUint::from(1_000_000u32)));
+    }
+
+    #[test]
+    fn approvals_are_decoded() {
+        let approval = decode_approval(&approval_log(vec![0xff; 32])).expect("approval");
+        assert_eq!(approval.owner, hex!("8ba1f109551bd432803012645ac136ddd64dba72").to_vec());
+        assert_eq!(approval.spender, hex!("7a250d5630b4cf539739df2c5dacb4c659f2488d").to_vec());
+        assert!(is_unlimited(&approval.value));
+    }
+
+    #[test]
+    fn malformed_approvals_are_skipped() {
+        // value cut short
+        assert!(decode_approval(&approval_log(vec![0xff; 31])).is_none());
+
+        // ERC721 approval, the token id is a fourth topic
+        let mut log = approval_log(vec![]);
+        log.topics.push(vec![0; 32]);
+        assert!(decode_approval(&log).is_none());
+
+        // a Transfer is not an Approval
+        let mut log = approval_log(vec![0xff; 32]);
+        log.topics[0] = TRANSFER_EVENT.to_vec();
+        assert!(decode_approval(&log).is_none());
+    }
+}
@@ -0,0 +1,26 @@
+//! Keys of the eth-token stores, addresses are lowercase hex without `0x`.
+
+pub fn token(address: &str) -> String {
+    format!("token:{}", address)
+}
+
+pub fn balance(token: &str, holder: &str) -> String {
+    format!("balance:{}:{}", token, holder)
+}
+
+/// Token of a `balance` key.
+pub fn balance_token(key: &str) -> Option<&str> {
+    key.strip_prefix("balance:")?.split(':').next()
+}
+
+pub fn allowance(token: &str, owner: &str, spender: &str) -> String {
+    format!("allowance:{}:{}:{}", token, owner, spender)
+}
+
+pub fn total_supply(token: &str) -> String {
+    format!("total_supply:{}", token)
+}
+
+pub fn holders(token: &str) -> String {
+    format!("holders:{}", token)
+}
@@ -1,162 +1,375 @@
-mod pb;
-mod eth;
-mod rpc;
+use std::str::FromStr;
 
+use num_bigint::BigInt;
 use substreams::errors::Error;
-use substreams::{log, proto, store, Hex, hex};
+use substreams::{log, proto, Hex};
 use substreams_ethereum::pb::eth as ethpb;
-use crate::rpc::create_rpc_calls;
+use token_common::bytecode::Standard;
+use token_common::contracts::{self, Candidate};
+use token_common::pb::tokens::MetadataSource;
+use token_common::rpc::{Method, MethodResponses, RpcRequest};
+use token_common::{pb, rpc};
 
-const INITIALIZE_METHOD_HASH: [u8; 4] = hex!("1459457a");
+mod events;
+mod keys;
+mod store;
+#[cfg(test)]
+mod testing;
 
-#[substreams::handlers::map]
-fn map_tokens(blk: ethpb::v2::Block) -> Result<pb::tokens::Tokens, Error> {
-    let mut tokens = vec![];
+const ZERO_ADDRESS: &str = "0000000000000000000000000000000000000000";
+
+/// `rpc::TOKEN_METHODS` and `totalSupply()`.
+const ERC20_METHODS: &[Method] = &[
+    Method::Decimals,
+    Method::Name,
+    Method::Symbol,
+    Method::TotalSupply,
+];
+
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_tokens(blk: ethpb::v2::Block) -> Result<pb::tokens::Tokens, Error> {
+    let block_number = blk.number;
+    let candidates = contracts::candidates(blk, &[Standard::Erc20]);
+    if candidates.is_empty() {
+        return Ok(pb::tokens::Tokens { tokens: vec![] });
+    }
+
+    // a single round-trip for all the candidates of the block
+    let mut request = RpcRequest::default();
+    for candidate in &candidates {
+        request.add(&candidate.address, ERC20_METHODS);
+    }
+    let responses = substreams_ethereum::rpc::eth_call(request.calls()).responses;
+    let responses = request.responses(responses);
+
+    let tokens = candidates
+        .iter()
+        .filter_map(|candidate| {
+            decode_token(candidate, block_number, &responses[&candidate.address])
+        })
+        .collect();
+
+    Ok(pb::tokens::Tokens { tokens })
+}
+
+/// Token from the responses to the `ERC20_METHODS` calls of a candidate, `None`
+/// when the contract does not behave like an ERC20.
+fn decode_token(
+    candidate: &Candidate,
+    block_number: u64,
+    responses: &MethodResponses,
+) -> Option<pb::tokens::Token> {
+    let address = &candidate.address;
+    let mut token = match rpc::decode_token(Hex(address).to_string(), responses) {
+        Ok(token) => token,
+        Err(error) => {
+            log::debug!("{} is not an ERC20 token contract: {}", Hex(address), error);
+            return None;
+        }
+    };
+
+    // a token reverting on totalSupply() is still a token, its supply is left empty
+    token.total_supply = match rpc::decode_total_supply(responses.get(Method::TotalSupply)) {
+        Ok(total_supply) => total_supply,
+        Err(error) => {
+            log::debug!("{} has no total supply: {}", Hex(address), error);
+            String::new()
+        }
+    };
+
+    // tokens predating ERC20 return a bytes32, only skip the contract when neither decodes
+    let source = token.metadata_source.as_ref().unwrap();
+    if source.name == MetadataSource::Fallback as i32
+        && source.symbol == MetadataSource::Fallback as i32
+    {
+        log::debug!(
+            "{} is not an ERC20 token contract, neither name nor symbol could be decoded [name: {}, symbol: {}]",
+            Hex(address),
+            String::from_utf8_lossy(responses.get(Method::Name).raw.as_ref()),
+            String::from_utf8_lossy(responses.get(Method::Symbol).raw.as_ref()),
+        );
+        return None;
+    }
+
+    log::debug!(
+        "{} is an ERC20 token contract with name {}",
+        Hex(address),
+        token.name
+    );
+    token.creator = Hex(&candidate.creator).to_string();
+    token.created_at_block = block_number;
+    token.created_at_tx = Hex(&candidate.transaction).to_string();
+    token.is_proxy = candidate.is_proxy;
+    token.implementation = Hex(&candidate.implementation).to_string();
+    Some(token)
+}
+
+/// Keeps the whole `Token` under `token:{address}`, so downstream stores can
+/// skip proxies or tokens from a known creator without another `eth_call`.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_tokens(tokens: pb::tokens::Tokens, store: store::StoreSet) {
+    for token in tokens.tokens {
+        let key = keys::token(&token.address);
+        store.set(1, key, &proto::encode(&token).unwrap());
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_transfers(
+    blk: ethpb::v2::Block,
+    tokens_store: store::StoreGet,
+) -> Result<pb::tokens::Transfers, Error> {
+    let mut transfers = pb::tokens::Transfers { transfers: vec![] };
 
     for trx in blk.transaction_traces {
-        for call in trx.calls {
-            if call.state_reverted {
-                continue;
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            let transfer = match events::decode_transfer(&log) {
+                None => continue,
+                Some(transfer) => transfer,
+            };
+
+            let token = Hex(&log.address).to_string();
+            if tokens_store.get_last(&keys::token(&token)).is_none() {
+                continue; // not an ERC20 known to store_tokens
             }
-            if call.call_type == ethpb::v2::CallType::Create as i32
-                || call.call_type == ethpb::v2::CallType::Call as i32
-            // proxy contract creation
-            {
-                let call_input_len = call.input.len();
-                if call.call_type == ethpb::v2::CallType::Call as i32
-                    && (call_input_len < 4 || call.input[0..4] != INITIALIZE_METHOD_HASH)
-                {
-                    // this will check if a proxy contract has been called to create a ERC20 contract.
-                    // if that is the case the Proxy contract will call the initialize function on the ERC20 contract
-                    // this is part of the OpenZeppelin Proxy contract standard
-                    continue;
-                }
-
-                if call.call_type == ethpb::v2::CallType::Create as i32 {
-                    let mut code_change_len = 0;
-                    for code_change in &call.code_changes {
-                        code_change_len += code_change.new_code.len()
-                    }
-
-                    log::debug!(
-                        "found contract creation: {}, caller {}, code change {}, input {}",
-                        Hex(&call.address),
-                        Hex(&call.caller),
-                        code_change_len,
-                        call_input_len,
-                    );
-
-                    if code_change_len <= 150 {
-                        // optimization to skip none viable SC
-                        log::info!(
-                            "skipping too small code to be a token contract: {}",
-                            Hex(&call.address)
-                        );
-                        continue;
-                    }
-                } else {
-                    log::debug!(
-                        "found proxy initialization: contract {}, caller {}",
-                        Hex(&call.address),
-                        Hex(&call.caller)
-                    );
-                }
-
-                if call.caller == hex!("0000000000004946c0e9f43f4dee607b0ef1fa1c")
-                    || call.caller == hex!("00000000687f5b66638856396bee28c1db0178d1")
-                {
-                    log::debug!("skipping known caller address");
-                    continue;
-                }
-
-                let rpc_call_decimal = create_rpc_calls(&call.address, vec![rpc::DECIMALS]);
-                let rpc_responses_unmarshalled_decimal: ethpb::rpc::RpcResponses =
-                    substreams_ethereum::rpc::eth_call(&rpc_call_decimal);
-                let response_decimal = rpc_responses_unmarshalled_decimal.responses;
-                if response_decimal[0].failed {
-                    let decimals_error = String::from_utf8_lossy(response_decimal[0].raw.as_ref());
-                    log::debug!(
-                        "{} is not an ERC20 token contract because of 'eth_call' failures [decimals: {}]",
-                        Hex(&call.address),
-                        decimals_error,
-                    );
-                    continue;
-                }
-
-                let decoded_decimals = eth::read_uint32(response_decimal[0].raw.as_ref());
-                if decoded_decimals.is_err() {
-                    log::debug!(
-                        "{} is not an ERC20 token contract decimal `eth_call` failed: {}",
-                        Hex(&call.address),
-                        decoded_decimals.err().unwrap(),
-                    );
-                    continue;
-                }
-
-                let rpc_call_name_symbol = create_rpc_calls(&call.address, vec![rpc::NAME, rpc::SYMBOL]);
-                let rpc_responses_unmarshalled: ethpb::rpc::RpcResponses =
-                    substreams_ethereum::rpc::eth_call(&rpc_call_name_symbol);
-                let responses = rpc_responses_unmarshalled.responses;
-                if responses[0].failed || responses[1].failed {
-                    let name_error = String::from_utf8_lossy(responses[0].raw.as_ref());
-                    let symbol_error = String::from_utf8_lossy(responses[1].raw.as_ref());
-
-                    log::debug!(
-                        "{} is not an ERC20 token contract because of 'eth_call' failures [name: {}, symbol: {}]",
-                        Hex(&call.address),
-                        name_error,
-                        symbol_error,
-                    );
-                    continue;
-                };
-
-                let decoded_name = eth::read_string(responses[1].raw.as_ref());
-                if decoded_name.is_err() {
-                    log::debug!(
-                        "{} is not an ERC20 token contract name `eth_call` failed: {}",
-                        Hex(&call.address),
-                        decoded_name.err().unwrap(),
-                    );
-                    continue;
-                }
-
-                let decoded_symbol = eth::read_string(responses[2].raw.as_ref());
-                if decoded_symbol.is_err() {
-                    log::debug!(
-                        "{} is not an ERC20 token contract symbol `eth_call` failed: {}",
-                        Hex(&call.address),
-                        decoded_symbol.err().unwrap(),
-                    );
-                    continue;
-                }
-
-                let decimals = decoded_decimals.unwrap() as u64;
-                let symbol = decoded_symbol.unwrap();
-                let name = decoded_name.unwrap();
-                log::debug!(
-                    "{} is an ERC20 token contract with name {}",
-                    Hex(&call.address),
-                    name,
-                );
-                let token = pb::tokens::Token {
-                    address: Hex(&call.address).to_string(),
-                    name,
-                    symbol,
-                    decimals,
-                };
-
-                tokens.push(token);
+
+            transfers.transfers.push(pb::tokens::Transfer {
+                token,
+                from: Hex(&transfer.from).to_string(),
+                to: Hex(&transfer.to).to_string(),
+                value: transfer.value.to_string(),
+                transaction_id: Hex(&trx.hash).to_string(),
+                log_ordinal: log.block_index as u64,
+            });
+        }
+    }
+
+    Ok(transfers)
+}
+
+/// Balance of every holder of a token, and its total supply: transfers from
+/// the zero address mint, transfers to it burn.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_balances(transfers: pb::tokens::Transfers, output: store::StoreAddBigInt) {
+    for transfer in transfers.transfers {
+        let value = BigInt::from_str(&transfer.value).unwrap();
+        let ordinal = transfer.log_ordinal;
+        let token = transfer.token;
+
+        if transfer.from == ZERO_ADDRESS {
+            output.add(ordinal, keys::total_supply(&token), &value);
+        } else {
+            output.add(
+                ordinal,
+                keys::balance(&token, &transfer.from),
+                &-value.clone(),
+            );
+        }
+
+        if transfer.to == ZERO_ADDRESS {
+            output.add(ordinal, keys::total_supply(&token), &-value);
+        } else {
+            output.add(ordinal, keys::balance(&token, &transfer.to), &value);
+        }
+    }
+}
+
+/// Number of accounts holding a token, counted when a balance leaves or
+/// comes back to zero.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_holders(balances_deltas: store::Deltas, output: store::StoreAddInt64) {
+    for delta in balances_deltas {
+        let token = match keys::balance_token(&delta.key) {
+            None => continue, // a total supply
+            Some(token) => token,
+        };
+
+        // a created balance has no old value
+        let held_before = is_positive(&delta.old_value);
+        let held_after = is_positive(&delta.new_value);
+
+        if !held_before && held_after {
+            output.add(delta.ordinal, keys::holders(token), 1);
+        } else if held_before && !held_after {
+            output.add(delta.ordinal, keys::holders(token), -1);
+        }
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_approvals(
+    blk: ethpb::v2::Block,
+    tokens_store: store::StoreGet,
+) -> Result<pb::tokens::Approvals, Error> {
+    let mut approvals = pb::tokens::Approvals { approvals: vec![] };
+
+    for trx in blk.transaction_traces {
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            let approval = match events::decode_approval(&log) {
+                None => continue,
+                Some(approval) => approval,
+            };
+
+            let token = Hex(&log.address).to_string();
+            if tokens_store.get_last(&keys::token(&token)).is_none() {
+                continue; // not an ERC20 known to store_tokens
             }
+
+            approvals.approvals.push(pb::tokens::Approval {
+                token,
+                owner: Hex(&approval.owner).to_string(),
+                spender: Hex(&approval.spender).to_string(),
+                unlimited: events::is_unlimited(&approval.value),
+                value: approval.value.to_string(),
+                transaction_id: Hex(&trx.hash).to_string(),
+                log_ordinal: log.block_index as u64,
+            });
         }
     }
 
-    Ok(pb::tokens::Tokens { tokens })
+    Ok(approvals)
 }
 
-#[substreams::handlers::store]
-fn store_tokens(tokens: pb::tokens::Tokens, store: store::StoreSet) {
-    for token in tokens.tokens {
-        let key = format!("token:{}", token.address);
-        store.set(1, key, &proto::encode(&token).unwrap());
+/// Last `Approval` of every (token, owner, spender), under
+/// `allowance:{token}:{owner}:{spender}`. Most tokens spend an allowance in
+/// `transferFrom` without emitting an `Approval`, so the value is what was
+/// approved rather than what is left.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_allowances(approvals: pb::tokens::Approvals, output: store::StoreSet) {
+    for approval in approvals.approvals {
+        output.set(
+            approval.log_ordinal,
+            keys::allowance(&approval.token, &approval.owner, &approval.spender),
+            &proto::encode(&approval).unwrap(),
+        );
+    }
+}
+
+/// Whether a `StoreAddBigInt` value is above zero, an empty value is not.
+fn is_positive(value: &[u8]) -> bool {
+    match std::str::from_utf8(value).map(BigInt::from_str) {
+        Ok(Ok(value)) => value > BigInt::from(0),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    const TOKEN: &str = "00000000000000000000000000000000000000a1";
+    const ALICE: &str = "00000000000000000000000000000000000000aa";
+    const BOB: &str = "00000000000000000000000000000000000000bb";
+
+    #[derive(Default)]
+    struct Stores {
+        balances: MemoryStoreRef,
+        holders: MemoryStoreRef,
+    }
+
+    impl Stores {
+        /// Runs `store_balances` and `store_holders` over a block of
+        /// `(from, to, value)` transfers of `TOKEN`.
+        fn process_block(&self, transfers: &[(&str, &str, u64)]) {
+            let transfers = transfers
+                .iter()
+                .enumerate()
+                .map(|(index, (from, to, value))| pb::tokens::Transfer {
+                    token: TOKEN.to_string(),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    value: value.to_string(),
+                    log_ordinal: index as u64,
+                    ..Default::default()
+                })
+                .collect();
+
+            store_balances(
+                pb::tokens::Transfers { transfers },
+                StoreAddBigInt::new(&self.balances),
+            );
+            let balances_deltas = self.balances.borrow_mut().flush();
+            store_holders(balances_deltas, StoreAddInt64::new(&self.holders));
+            self.holders.borrow_mut().flush();
+        }
+
+        fn balance(&self, holder: &str) -> Option<String> {
+            self.balances
+                .borrow()
+                .get_last(&keys::balance(TOKEN, holder))
+        }
+
+        fn total_supply(&self) -> Option<String> {
+            self.balances.borrow().get_last(&keys::total_supply(TOKEN))
+        }
+
+        fn holders(&self) -> Option<String> {
+            self.holders.borrow().get_last(&keys::holders(TOKEN))
+        }
+    }
+
+    #[test]
+    fn mints_and_burns_move_the_total_supply() {
+        let stores = Stores::default();
+
+        stores.process_block(&[(ZERO_ADDRESS, ALICE, 100)]);
+        assert_eq!(stores.total_supply().as_deref(), Some("100"));
+        assert_eq!(stores.balance(ALICE).as_deref(), Some("100"));
+        assert_eq!(stores.balance(ZERO_ADDRESS), None);
+        assert_eq!(stores.holders().as_deref(), Some("1"));
+
+        stores.process_block(&[(ALICE, ZERO_ADDRESS, 100)]);
+        assert_eq!(stores.total_supply().as_deref(), Some("0"));
+        assert_eq!(stores.balance(ALICE).as_deref(), Some("0"));
+        assert_eq!(stores.balance(ZERO_ADDRESS), None);
+        assert_eq!(stores.holders().as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn self_transfers_keep_the_holder() {
+        let stores = Stores::default();
+        stores.process_block(&[(ZERO_ADDRESS, ALICE, 100)]);
+
+        // the whole balance leaves before coming back
+        stores.process_block(&[(ALICE, ALICE, 40), (ALICE, ALICE, 100)]);
+        assert_eq!(stores.balance(ALICE).as_deref(), Some("100"));
+        assert_eq!(stores.total_supply().as_deref(), Some("100"));
+        assert_eq!(stores.holders().as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn holders_leave_at_zero_and_come_back() {
+        let stores = Stores::default();
+        stores.process_block(&[(ZERO_ADDRESS, ALICE, 100)]);
+
+        stores.process_block(&[(ALICE, BOB, 100)]);
+        assert_eq!(stores.balance(ALICE).as_deref(), Some("0"));
+        assert_eq!(stores.balance(BOB).as_deref(), Some("100"));
+        assert_eq!(stores.holders().as_deref(), Some("1"));
+
+        stores.process_block(&[(BOB, ALICE, 30)]);
+        assert_eq!(stores.balance(ALICE).as_deref(), Some("30"));
+        assert_eq!(stores.balance(BOB).as_deref(), Some("70"));
+        assert_eq!(stores.holders().as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn only_balances_above_zero_are_positive() {
+        assert!(is_positive(b"12"));
+        assert!(!is_positive(b"0"));
+        assert!(!is_positive(b"-5"));
+        assert!(!is_positive(b""));
+        assert!(!is_positive(b"not a number"));
     }
 }
@@ -1,3 +0,0 @@
-#[path = "./sf.ethereum.tokens.v1.rs"]
-#[allow(dead_code)]
-pub mod tokens;
\ No newline at end of file
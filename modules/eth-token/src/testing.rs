@@ -0,0 +1,129 @@
+//! In-memory stores standing in for the runtime ones in the tests.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use substreams::pb::substreams::{store_delta, StoreDelta};
+
+pub type Deltas = Vec<StoreDelta>;
+
+/// Key/value store keeping the deltas of the block being processed.
+#[derive(Default)]
+pub struct MemoryStore {
+    kv: BTreeMap<String, Vec<u8>>,
+    deltas: Vec<StoreDelta>,
+}
+
+pub type MemoryStoreRef = Rc<RefCell<MemoryStore>>;
+
+impl MemoryStore {
+    pub fn new_ref() -> MemoryStoreRef {
+        Rc::new(RefCell::new(MemoryStore::default()))
+    }
+
+    /// Starts a new block and returns the deltas of the previous one.
+    pub fn flush(&mut self) -> Deltas {
+        std::mem::take(&mut self.deltas)
+    }
+
+    pub fn get_last(&self, key: &str) -> Option<String> {
+        self.kv
+            .get(key)
+            .map(|value| String::from_utf8_lossy(value).to_string())
+    }
+
+    fn add<T: FromStr + Default + std::ops::Add<Output = T> + ToString>(
+        &mut self,
+        ord: u64,
+        key: String,
+        value: T,
+    ) {
+        let current = match self.get_last(&key) {
+            None => T::default(),
+            Some(current) => T::from_str(&current).ok().unwrap(),
+        };
+        self.set(ord, key, (current + value).to_string().into_bytes());
+    }
+
+    fn set(&mut self, ord: u64, key: String, new_value: Vec<u8>) {
+        let old_value = self.kv.insert(key.clone(), new_value.clone());
+        let operation = match old_value {
+            Some(_) => store_delta::Operation::Update,
+            None => store_delta::Operation::Create,
+        };
+        self.deltas.push(StoreDelta {
+            operation: operation as i32,
+            ordinal: ord,
+            key,
+            old_value: old_value.unwrap_or_default(),
+            new_value,
+        });
+    }
+}
+
+pub struct StoreGet {
+    store: MemoryStoreRef,
+}
+
+impl StoreGet {
+    pub fn new(store: &MemoryStoreRef) -> StoreGet {
+        StoreGet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn get_last(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().kv.get(key).cloned()
+    }
+}
+
+pub struct StoreSet {
+    store: MemoryStoreRef,
+}
+
+impl StoreSet {
+    pub fn new(store: &MemoryStoreRef) -> StoreSet {
+        StoreSet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn set(&self, ord: u64, key: String, value: &[u8]) {
+        self.store.borrow_mut().set(ord, key, value.to_vec());
+    }
+}
+
+pub struct StoreAddBigInt {
+    store: MemoryStoreRef,
+}
+
+impl StoreAddBigInt {
+    pub fn new(store: &MemoryStoreRef) -> StoreAddBigInt {
+        StoreAddBigInt {
+            store: store.clone(),
+        }
+    }
+
+    pub fn add(&self, ord: u64, key: String, value: &BigInt) {
+        self.store.borrow_mut().add(ord, key, value.clone());
+    }
+}
+
+pub struct StoreAddInt64 {
+    store: MemoryStoreRef,
+}
+
+impl StoreAddInt64 {
+    pub fn new(store: &MemoryStoreRef) -> StoreAddInt64 {
+        StoreAddInt64 {
+            store: store.clone(),
+        }
+    }
+
+    pub fn add(&self, ord: u64, key: String, value: i64) {
+        self.store.borrow_mut().add(ord, key, value);
+    }
+}
@@ -0,0 +1,17 @@
+use anyhow::{Ok, Result};
+use substreams_ethereum::Abigen;
+
+fn main() -> Result<(), anyhow::Error> {
+    let out_dir = std::env::var("OUT_DIR")?;
+
+    for (contract, abi) in [("Factory", "factory"), ("Pair", "pair")] {
+        let abi_path = format!("abis/{}.json", abi);
+        println!("cargo:rerun-if-changed={}", abi_path);
+
+        Abigen::new(contract, &abi_path)?
+            .generate()?
+            .write_to_file(format!("{}/{}.rs", out_dir, abi))?;
+    }
+
+    Ok(())
+}
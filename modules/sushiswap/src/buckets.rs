@@ -0,0 +1,170 @@
+use std::str;
+use std::str::FromStr;
+
+use substreams::pb::substreams::{store_delta, Clock};
+
+use crate::profile::PROFILE;
+use crate::store;
+
+/// Day, hour and minute buckets of the `pair_day`, `pair_hour`, `token_day` and
+/// `global_day` keys and of the candles. A store keeps the last `retention`
+/// buckets of each, the current one included, and deletes the older ones. The
+/// retentions come from the profile's `bucket_retention`.
+pub struct Bucket {
+    pub name: &'static str,
+    pub seconds: i64,
+    pub retention: i64,
+}
+
+pub const DAY: Bucket = Bucket {
+    name: "day",
+    seconds: 86400,
+    retention: PROFILE.bucket_retention.day,
+};
+
+pub const HOUR: Bucket = Bucket {
+    name: "hour",
+    seconds: 3600,
+    retention: PROFILE.bucket_retention.hour,
+};
+
+pub const MINUTE: Bucket = Bucket {
+    name: "minute",
+    seconds: 60,
+    retention: PROFILE.bucket_retention.minute,
+};
+
+pub const BUCKETS: &[&Bucket] = &[&DAY, &HOUR, &MINUTE];
+
+impl Bucket {
+    pub fn id(&self, clock: &Clock) -> i64 {
+        clock.timestamp.as_ref().unwrap().seconds / self.seconds
+    }
+
+    /// Key of `store_buckets` holding the current bucket id.
+    pub fn key(&self) -> String {
+        format!("bucket:{}", self.name)
+    }
+
+    /// Buckets leaving retention when the clock moves from `previous` to
+    /// `current`.
+    pub fn expired(&self, previous: i64, current: i64) -> std::ops::RangeInclusive<i64> {
+        expired_range(previous, current, self.retention)
+    }
+}
+
+/// Ids leaving a retention of `retention` buckets when the id moves from
+/// `previous` to `current`. Buckets skipped over by a gap in the chain never
+/// held keys, only the retained ones up to `previous` can need a delete.
+pub fn expired_range(previous: i64, current: i64, retention: i64) -> std::ops::RangeInclusive<i64> {
+    (previous - retention + 1)..=previous.min(current - retention)
+}
+
+/// Previous and current ids of the `key` bucket of `store_buckets` when it
+/// changed in this block. Nothing changes on the first block, which creates
+/// the keys.
+pub fn rolled(buckets_deltas: &store::Deltas, key: &str) -> Option<(i64, i64)> {
+    buckets_deltas
+        .iter()
+        .filter(|delta| delta.operation == store_delta::Operation::Update as i32 && delta.key == key)
+        .map(|delta| (parse_id(&delta.old_value), parse_id(&delta.new_value)))
+        .find(|(previous, current)| previous != current)
+}
+
+/// Buckets of `bucket` to delete in this block, found in the deltas of
+/// `store_buckets`: a bucket closes when its id changes.
+pub fn expired(buckets_deltas: &store::Deltas, bucket: &Bucket) -> Vec<i64> {
+    match rolled(buckets_deltas, &bucket.key()) {
+        Some((previous, current)) => bucket.expired(previous, current).collect(),
+        None => vec![],
+    }
+}
+
+fn parse_id(value: &[u8]) -> i64 {
+    i64::from_str(str::from_utf8(value).unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+    use crate::pb::database::DatabaseChanges;
+    use crate::keys::{Field, StoreKey};
+    use crate::testing::block::{mint_log, sync_log, transfer_log, BlockBuilder};
+    use crate::testing::store::StoreGet;
+    use crate::testing::{decimal, table_changes, Pipeline, ONE, PAIR, USER};
+
+    #[test]
+    fn expired_buckets_cover_gaps() {
+        assert_eq!(DAY.expired(10, 11).collect::<Vec<_>>(), vec![10]);
+        assert_eq!(DAY.expired(10, 14).collect::<Vec<_>>(), vec![10]);
+
+        let week = Bucket {
+            name: "week",
+            seconds: 7 * 86400,
+            retention: 3,
+        };
+        assert_eq!(week.expired(10, 11).collect::<Vec<_>>(), vec![8]);
+        assert_eq!(week.expired(10, 12).collect::<Vec<_>>(), vec![8, 9]);
+        assert_eq!(week.expired(10, 20).collect::<Vec<_>>(), vec![8, 9, 10]);
+        assert!(week.expired(10, 10).next().is_none());
+    }
+
+    #[test]
+    fn day_buckets_close_after_a_gap() {
+        let pipeline = Pipeline::with_pair();
+
+        let opened = pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(USER, PAIR, vec![(PAIR, vec![sync_log(2 * ONE, 4 * ONE)])])
+                .build(),
+        );
+        let day = format!("{}-18518", PAIR);
+        let day_field = |changes: &DatabaseChanges, name: &str| {
+            let value = table_changes(changes, "pair_day_data")
+                .filter(|change| change.pk == day)
+                .flat_map(|change| change.fields.iter())
+                .find(|field| field.name == name)
+                .map(|field| field.new_value.clone());
+            decimal(value.expect(name))
+        };
+        assert_eq!(day_field(&opened, "open_reserve_0"), BigDecimal::from(2));
+
+        // three days later, without any Sync
+        let closed = pipeline.process_block(BlockBuilder::new(3, 1_600_259_200).build());
+        assert_eq!(day_field(&closed, "close_reserve_1"), BigDecimal::from(4));
+    }
+
+    #[test]
+    fn global_day_totals_are_deleted_with_their_day() {
+        let pipeline = Pipeline::with_pair();
+
+        pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(
+                    USER,
+                    PAIR,
+                    vec![(
+                        PAIR,
+                        vec![
+                            transfer_log("0x0000000000000000000000000000000000000000", USER, ONE),
+                            sync_log(2 * ONE, 4 * ONE),
+                            mint_log(USER, 2 * ONE, 4 * ONE),
+                        ],
+                    )],
+                )
+                .build(),
+        );
+        let key = StoreKey::GlobalDay {
+            day: 18518,
+            field: Field::TransactionCount,
+        }
+        .encode();
+        let totals = StoreGet::new(&pipeline.totals);
+        assert_eq!(totals.get_last(&key), Some(b"1".to_vec()));
+
+        pipeline.process_block(BlockBuilder::new(3, 1_600_086_400).build());
+        assert_eq!(totals.get_last(&key), None);
+    }
+}
@@ -0,0 +1,266 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use substreams::log;
+use substreams::pb::substreams::{store_delta, Clock};
+
+use crate::buckets::{self, Bucket};
+use crate::keys::{Denomination, StoreKey};
+use crate::pb::pcs;
+use crate::pcs::event::Type;
+use crate::{store, utils};
+
+/// Candle stores keep a single bucket per interval: the one the clock is in.
+pub struct Interval {
+    pub name: &'static str,
+    pub bucket: &'static Bucket,
+}
+
+pub const INTERVALS: &[Interval] = &[
+    Interval {
+        name: "minute",
+        bucket: &buckets::MINUTE,
+    },
+    Interval {
+        name: "hour",
+        bucket: &buckets::HOUR,
+    },
+    Interval {
+        name: "day",
+        bucket: &buckets::DAY,
+    },
+];
+
+pub const PAIR: &str = "pair";
+pub const TOKEN: &str = "token";
+
+impl Interval {
+    pub fn find(name: &str) -> Option<&'static Interval> {
+        INTERVALS.iter().find(|interval| interval.name == name)
+    }
+
+    pub fn bucket(&self, clock: &Clock) -> i64 {
+        self.bucket.id(clock)
+    }
+
+    pub fn prefix(&self, bucket: i64) -> String {
+        format!("{}:{}:", self.name, bucket)
+    }
+
+    /// `{interval}:{bucket}:{pair|token}:{address}`
+    pub fn key(&self, bucket: i64, kind: &str, address: &str) -> String {
+        format!("{}{}:{}", self.prefix(bucket), kind, address)
+    }
+}
+
+/// Splits a candle key, volume keys have an extra `volume`/`volume_usd` part.
+pub fn parse_key(key: &str) -> Option<(&'static Interval, i64, &str, &str)> {
+    let parts: Vec<&str> = key.split(':').collect();
+    if parts.len() < 4 {
+        return None;
+    }
+    let interval = Interval::find(parts[0])?;
+    let bucket = i64::from_str(parts[1]).ok()?;
+    Some((interval, bucket, parts[2], parts[3]))
+}
+
+/// Drops the buckets of every interval closed in this block, found in the
+/// deltas of `store_buckets`, their candles are complete.
+pub fn delete_expired_buckets<F: Fn(&String)>(buckets_deltas: &store::Deltas, delete_prefix: F) {
+    for interval in INTERVALS {
+        for bucket in buckets::expired(buckets_deltas, interval.bucket) {
+            delete_prefix(&interval.prefix(bucket));
+        }
+    }
+}
+
+/// Prices the candles are built from, keyed by candle: the `token0_price` of
+/// every pair with a new reserve, and the USD price of every token repriced by
+/// `store_prices`.
+pub fn price_samples(
+    clock: &Clock,
+    reserves: &pcs::Reserves,
+    prices_deltas: &store::Deltas,
+) -> Vec<(u64, String, BigDecimal)> {
+    let mut samples = vec![];
+
+    for reserve in &reserves.reserves {
+        let price = match utils::event_decimal(&reserve.pair_address, "token0_price", &reserve.token0_price) {
+            Some(price) => price,
+            None => continue,
+        };
+        for interval in INTERVALS {
+            samples.push((
+                reserve.log_ordinal,
+                interval.key(interval.bucket(clock), PAIR, &reserve.pair_address),
+                price.clone(),
+            ));
+        }
+    }
+
+    for delta in prices_deltas {
+        if delta.operation == store_delta::Operation::Delete as i32 {
+            continue;
+        }
+
+        let token = match StoreKey::parse(&delta.key) {
+            Some(StoreKey::DerivedPrice {
+                token,
+                denomination: Denomination::Usd,
+            }) => token,
+            _ => continue,
+        };
+
+        let price = match utils::decode_big_decimal(&delta.key, &delta.new_value) {
+            Ok(price) => price,
+            Err(error) => {
+                log::info!("skipping candles of token {}: {}", token, error);
+                continue;
+            }
+        };
+        for interval in INTERVALS {
+            samples.push((
+                delta.ordinal,
+                interval.key(interval.bucket(clock), TOKEN, &token),
+                price.clone(),
+            ));
+        }
+    }
+
+    samples
+}
+
+/// Volumes of every swap, keyed by candle volume key. Pair candles count the
+/// volume in token0, token candles in the token itself.
+pub fn volume_samples(clock: &Clock, events: &pcs::Events) -> Vec<(u64, String, BigDecimal)> {
+    let mut samples = vec![];
+
+    for event in &events.events {
+        let swap = match event.r#type.as_ref() {
+            Some(Type::Swap(swap)) => swap,
+            _ => continue,
+        };
+
+        // unpriced swaps have no USD volume, their volume still counts
+        let volume_usd = BigDecimal::from_str(swap.volume_usd.as_str()).ok();
+        let volume_token0 = utils::event_decimal(&event.pair_address, "volume_token0", &swap.volume_token0);
+        let volume_token1 = utils::event_decimal(&event.pair_address, "volume_token1", &swap.volume_token1);
+
+        for interval in INTERVALS {
+            let bucket = interval.bucket(clock);
+            for (kind, address, volume) in [
+                (PAIR, &event.pair_address, &volume_token0),
+                (TOKEN, &event.token0, &volume_token0),
+                (TOKEN, &event.token1, &volume_token1),
+            ] {
+                let key = interval.key(bucket, kind, address);
+                if let Some(volume) = volume {
+                    samples.push((event.log_ordinal, format!("{}:volume", key), volume.clone()));
+                }
+                if let Some(volume_usd) = &volume_usd {
+                    samples.push((
+                        event.log_ordinal,
+                        format!("{}:volume_usd", key),
+                        volume_usd.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+    use crate::pb;
+    use crate::testing::block::{sync_log, BlockBuilder};
+    use crate::testing::store::StoreGet;
+    use crate::testing::{clock, decimal, field_value, table_changes, Pipeline, ONE, PAIR, TOKEN_A, TOKEN_B, USER};
+
+    #[test]
+    fn pair_candles_follow_reserves() {
+        let pipeline = Pipeline::with_pair();
+
+        let sync = |number: u64, timestamp: i64, reserve0: u128, reserve1: u128| {
+            BlockBuilder::new(number, timestamp)
+                .transaction(USER, PAIR, vec![(PAIR, vec![sync_log(reserve0, reserve1)])])
+                .build()
+        };
+
+        let opened = pipeline.process_block(sync(2, 1_600_000_002, 2 * ONE, 4 * ONE));
+        let candle = table_changes(&opened, "pair_candle")
+            .find(|change| field_value(change, "interval") == "minute")
+            .expect("minute candle");
+        assert_eq!(candle.operation, pb::database::table_change::Operation::Create as i32);
+        assert_eq!(field_value(candle, "pair"), PAIR);
+
+        pipeline.process_block(sync(3, 1_600_000_004, 4 * ONE, 2 * ONE));
+        let updated = pipeline.process_block(sync(4, 1_600_000_006, 3 * ONE, 4 * ONE));
+        let candle = table_changes(&updated, "pair_candle")
+            .find(|change| field_value(change, "interval") == "minute")
+            .expect("minute candle");
+        assert_eq!(candle.operation, pb::database::table_change::Operation::Update as i32);
+        assert_eq!(candle.pk, format!("{}-minute-1599999960", PAIR));
+        assert_eq!(decimal(field_value(candle, "open")), decimal("0.5".to_string()));
+        assert_eq!(decimal(field_value(candle, "high")), BigDecimal::from(2));
+        assert_eq!(decimal(field_value(candle, "low")), decimal("0.5".to_string()));
+        assert_eq!(decimal(field_value(candle, "close")), decimal("0.75".to_string()));
+    }
+
+    #[test]
+    fn candles_are_dropped_after_a_gap() {
+        let pipeline = Pipeline::with_pair();
+
+        pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(USER, PAIR, vec![(PAIR, vec![sync_log(2 * ONE, 4 * ONE)])])
+                .build(),
+        );
+        let minute = format!("minute:26666666:pair:{}", PAIR);
+        assert!(StoreGet::new(&pipeline.candle_open).get_last(&minute).is_some());
+
+        // five minutes later, without any Sync
+        pipeline.process_block(BlockBuilder::new(3, 1_600_000_302).build());
+        assert!(StoreGet::new(&pipeline.candle_open).get_last(&minute).is_none());
+        assert!(StoreGet::new(&pipeline.candle_close).get_last(&minute).is_none());
+    }
+
+    #[test]
+    fn unpriced_swaps_keep_their_volume() {
+        let swap = |volume_usd: &str, volume_token1: &str| pcs::Event {
+            r#type: Some(Type::Swap(pcs::Swap {
+                volume_usd: volume_usd.to_string(),
+                volume_token0: "2".to_string(),
+                volume_token1: volume_token1.to_string(),
+                ..Default::default()
+            })),
+            log_ordinal: 1,
+            pair_address: PAIR.to_string(),
+            token0: TOKEN_A.to_string(),
+            token1: TOKEN_B.to_string(),
+            ..Default::default()
+        };
+        let events = pcs::Events {
+            events: vec![swap("", "4"), swap("10", "not a decimal")],
+            ..Default::default()
+        };
+
+        let samples = volume_samples(&clock(&BlockBuilder::new(1, 1_600_000_000).build()), &events);
+        let minute = |kind: &str, address: &str, volume: &str| {
+            let key = format!("minute:26666666:{}:{}:{}", kind, address, volume);
+            samples
+                .iter()
+                .filter(|(_, sample_key, _)| sample_key == &key)
+                .map(|(_, _, volume)| volume.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(minute(super::PAIR, PAIR, "volume"), vec!["2", "2"]);
+        assert_eq!(minute(super::PAIR, PAIR, "volume_usd"), vec!["10"]);
+        assert_eq!(minute(TOKEN, TOKEN_B, "volume"), vec!["4"]);
+    }
+}
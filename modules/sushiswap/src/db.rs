@@ -1,14 +1,18 @@
+use std::ops::{Div, Mul};
 use std::string::String;
 
+use bigdecimal::{BigDecimal, Zero};
 use substreams::pb::substreams::{store_delta, StoreDelta, Clock};
-use substreams::{log, proto, store};
+use substreams::log;
 
+use crate::errors::PcsError;
+use crate::keys::{self, StoreKey};
 use crate::pb::database::table_change::Operation;
 use crate::pb::database::{DatabaseChanges, Field, TableChange};
-use crate::pcs::{Burn, Event, Events, Mint, Swap};
-use crate::{field, field_create_string, field_from_strings, pb, pcs, utils, Type};
-
-const PANCAKE_FACTORY: &str = "ca143ce32fe78f1f7019d7d551a6402fc5350c73";
+use crate::pcs::{Burn, Candle, Candles, Event, Events, Mint, Reserve, Reserves, Swap};
+use crate::profile::PROFILE;
+use crate::store;
+use crate::{fees, field, field_create_string, field_from_strings, pb, pcs, utils, Type};
 
 #[derive(Clone, Debug)]
 enum Item {
@@ -17,7 +21,11 @@ enum Item {
     TotalDelta(StoreDelta),
     VolumeDelta(StoreDelta),
     ReserveDelta(StoreDelta),
+    LpBalanceDelta(StoreDelta),
     Event(Event),
+    PairCandle(Candle),
+    TokenCandle(Candle),
+    ReserveSnapshot(Reserve),
 }
 
 pub fn process(
@@ -28,15 +36,25 @@ pub fn process(
     volumes_deltas: store::Deltas,
     reserves_deltas: store::Deltas,
     events: Events,
+    candles: Candles,
     pcs_tokens_store: &store::StoreGet,
-) -> DatabaseChanges {
+    pairs_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    volumes_store: &store::StoreGet,
+    lp_balances_deltas: store::Deltas,
+    lp_balances_store: &store::StoreGet,
+    reserves: Reserves,
+) -> Result<DatabaseChanges, PcsError> {
     let items = join_sort_deltas(
         pair_deltas,
         pcs_token_deltas,
         total_deltas,
         volumes_deltas,
         reserves_deltas,
+        lp_balances_deltas,
         events,
+        candles,
+        reserves,
     );
 
     log::info!("about! to process db_out items: {}", items.len());
@@ -48,17 +66,41 @@ pub fn process(
     for item in items {
         match item {
             Item::PairDelta(delta) => {
-                handle_pair_delta(delta, &block, &mut database_changes, pcs_tokens_store)
+                handle_pair_delta(delta, &block, &mut database_changes, pcs_tokens_store)?
             }
-            Item::PcsTokenDelta(delta) => handle_token_delta(delta, &mut database_changes, block),
+            Item::PcsTokenDelta(delta) => handle_token_delta(delta, &mut database_changes, block)?,
             Item::TotalDelta(delta) => handle_total_delta(delta, &mut database_changes, block),
-            Item::VolumeDelta(delta) => handle_volume_delta(delta, &mut database_changes, block),
+            Item::VolumeDelta(delta) => handle_volume_delta(
+                delta,
+                &mut database_changes,
+                block,
+                pairs_store,
+                prices_store,
+                volumes_store,
+            ),
             Item::ReserveDelta(delta) => handle_reserves_delta(delta, &mut database_changes, block),
+            Item::LpBalanceDelta(delta) => handle_lp_balance_delta(
+                delta,
+                &mut database_changes,
+                block,
+                pairs_store,
+                prices_store,
+                lp_balances_store,
+            ),
             Item::Event(event) => handle_events(event, &mut database_changes, block),
+            Item::PairCandle(candle) => {
+                handle_candle("pair_candle", candle, &mut database_changes, block)
+            }
+            Item::TokenCandle(candle) => {
+                handle_candle("token_candle", candle, &mut database_changes, block)
+            }
+            Item::ReserveSnapshot(reserve) => {
+                handle_reserve_snapshot(reserve, &mut database_changes, block)
+            }
         }
     }
 
-    return database_changes;
+    return Ok(database_changes);
 }
 
 fn handle_pair_delta(
@@ -66,15 +108,21 @@ fn handle_pair_delta(
     block: &Clock,
     changes: &mut DatabaseChanges,
     pcs_tokens_store: &store::StoreGet,
-) {
+) -> Result<(), PcsError> {
     if delta.operation != store_delta::Operation::Create as i32 {
-        return;
+        return Ok(());
     }
 
-    let pair: pcs::Pair = proto::decode(&delta.new_value).unwrap();
+    let pair: pcs::Pair = utils::decode_proto(&delta.key, &delta.new_value)?;
 
-    let token0 = utils::get_last_token(&pcs_tokens_store, pair.token0_address.as_str());
-    let token1 = utils::get_last_token(&pcs_tokens_store, pair.token1_address.as_str());
+    // store_pcs_tokens skips tokens it could not fetch, name those pairs after the addresses
+    let name = match utils::get_last_pair_tokens(&pcs_tokens_store, &pair) {
+        Ok((token0, token1)) => format!("{}-{}", token0.symbol, token1.symbol),
+        Err(error) => {
+            log::info!("naming pair {} after its tokens' addresses: {}", pair.address, error);
+            format!("{}-{}", pair.token0_address, pair.token1_address)
+        }
+    };
 
     changes.table_changes.push(TableChange {
         table: "pair".to_string(),
@@ -84,21 +132,23 @@ fn handle_pair_delta(
         operation: delta.operation,
         fields: vec![
             field!("id", pair.address.clone(), ""),
-            field!("name", format!("{}-{}", token0.symbol, token1.symbol), ""),
+            field!("name", name, ""),
             field!("token_0", pair.token0_address, ""),
             field!("token_1", pair.token1_address, ""),
             field!("block", block.number, ""),
             field!("timestamp", block.timestamp.as_ref().unwrap().seconds, ""),
         ],
     });
+
+    Ok(())
 }
 
-fn handle_token_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) {
+fn handle_token_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) -> Result<(), PcsError> {
     if delta.operation != store_delta::Operation::Create as i32 {
-        return;
+        return Ok(());
     }
 
-    let token: pb::tokens::Token = proto::decode(&delta.new_value).unwrap();
+    let token: pb::tokens::Token = utils::decode_proto(&delta.key, &delta.new_value)?;
 
     changes.table_changes.push(TableChange {
         table: "token".to_string(),
@@ -113,66 +163,57 @@ fn handle_token_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &
             field!("decimals", token.decimals, ""),
         ],
     });
+
+    Ok(())
 }
 
 fn handle_total_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) {
-    let parts: Vec<&str> = delta.key.split(":").collect();
-    let prefix = parts[0];
     let mut operation = delta.operation;
-    let (table, pk, fields) = match prefix {
-        "pair" => {
-            let pair_address = parts[1];
-            let field_name = parts[2];
-
-            let field = match field_name {
-                "transaction_count" => field_from_strings!("total_transactions", delta),
-                "swap_count" => return, // todo: what does here ? up the colum of pancake_factory.swap[] ?
-                "mint_count" => return, // todo: what does here ? up the colum of pancake_factory.mint[] ?
-                "burn_count" => return, // todo: what does here ? up the colum of pancake_factory.burn[] ?
+    let (table, pk, fields) = match StoreKey::parse(&delta.key) {
+        Some(StoreKey::PairTotal { pair, field }) => {
+            let field = match field {
+                keys::Field::TransactionCount => field_from_strings!("total_transactions", delta),
+                keys::Field::SwapCount => return, // todo: what does here ? up the colum of pancake_factory.swap[] ?
+                keys::Field::MintCount => return, // todo: what does here ? up the colum of pancake_factory.mint[] ?
+                keys::Field::BurnCount => return, // todo: what does here ? up the colum of pancake_factory.burn[] ?
                 _ => return,
             };
 
-            ("pair", pair_address, vec![field])
+            ("pair", pair, vec![field])
         }
-        "token" => {
-            let token_addr = parts[1];
-            let field_name = parts[2]; // will take in account token0 addr and token1 addr
-
-            let field = match field_name {
-                "transaction_count" => field_from_strings!("total_transactions", delta),
+        Some(StoreKey::TokenTotal { token, field }) => {
+            // will take in account token0 addr and token1 addr
+            let field = match field {
+                keys::Field::TransactionCount => field_from_strings!("total_transactions", delta),
                 _ => return,
             };
 
-            ("token", token_addr, vec![field])
+            ("token", token, vec![field])
         }
-        "global" => {
-            let field_name = parts[1];
-
-            let field = match field_name {
-                "transaction_count" => {
+        Some(StoreKey::Global { field }) => {
+            let field = match field {
+                keys::Field::TransactionCount => {
                     operation = Operation::Update as i32;
                     field_from_strings!("total_transactions", delta)
                 }
-                "pair_count" => field_from_strings!("total_pairs", delta),
+                keys::Field::PairCount => field_from_strings!("total_pairs", delta),
                 _ => return,
             };
 
-            ("pancake_factory", PANCAKE_FACTORY, vec![field])
+            ("pancake_factory", PROFILE.factory_address_hex(), vec![field])
         }
-        "global_day" => {
+        Some(StoreKey::GlobalDay { day, field }) => {
             if delta.operation == Operation::Delete as i32 {
                 return;
             }
 
-            let day = parts[1];
-            let field_name = parts[2];
             operation = Operation::Update as i32;
-            let field = match field_name {
-                "transaction_count" => field_from_strings!("total_transactions", delta),
+            let field = match field {
+                keys::Field::TransactionCount => field_from_strings!("total_transactions", delta),
                 _ => return,
             };
 
-            ("pancake_day_data", day, vec![field])
+            ("pancake_day_data", day.to_string(), vec![field])
         }
         _ => return,
     };
@@ -182,136 +223,126 @@ fn handle_total_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &
         ordinal: delta.ordinal,
         operation,
         table: table.to_string(),
-        pk: pk.to_string(),
+        pk,
         fields,
     })
 }
 
-fn handle_volume_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) {
-    let parts: Vec<&str> = delta.key.split(":").collect();
-    let prefix = parts[0];
-
+fn handle_volume_delta(
+    delta: StoreDelta,
+    changes: &mut DatabaseChanges,
+    block: &Clock,
+    pairs_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    volumes_store: &store::StoreGet,
+) {
     let mut operation = delta.operation;
-    let (table, pk, fields) = match prefix {
-        "pair_day" => {
+    let (table, pk, fields) = match StoreKey::parse(&delta.key) {
+        Some(StoreKey::PairDay { day, pair, field }) => {
             if delta.operation == Operation::Delete as i32 {
                 return;
             }
 
-            let day = parts[1];
-            let pair_address = parts[2];
-            let key = parts[3];
-
-            let field = match key {
-                "usd" => field_from_strings!("daily_volume_usd", delta),
-                "token0" => field_from_strings!("daily_volume_token_0", delta),
-                "token1" => field_from_strings!("daily_volume_token_1", delta),
+            let mut fields = match field {
+                keys::Field::Usd => vec![field_from_strings!("daily_volume_usd", delta)],
+                keys::Field::Token0 => vec![field_from_strings!("daily_volume_token_0", delta)],
+                keys::Field::Token1 => vec![field_from_strings!("daily_volume_token_1", delta)],
+                keys::Field::FeesUsd => vec![field_from_strings!("daily_fees_usd", delta)],
+                keys::Field::ProtocolFeesUsd => {
+                    vec![field_from_strings!("daily_protocol_fees_usd", delta)]
+                }
                 _ => return,
             };
+            let apr = daily_fee_apr(
+                &delta,
+                day,
+                &pair,
+                &field,
+                pairs_store,
+                prices_store,
+                volumes_store,
+            );
+            if let Some(apr) = apr {
+                fields.push(field_create_string!("daily_fee_apr", apr));
+            }
             operation = Operation::Update as i32;
-            (
-                "pair_day_data",
-                format!("{}-{}", pair_address, day),
-                vec![field],
-            )
+            ("pair_day_data", format!("{}-{}", pair, day), fields)
         }
-        "pair_hour" => {
+        Some(StoreKey::PairHour { hour, pair, field }) => {
             if delta.operation == Operation::Delete as i32 {
                 return;
             }
 
-            let hour = parts[1];
-            let pair_address = parts[2];
-            let key = parts[3];
-
-            let field = match key {
-                "usd" => field_from_strings!("hourly_volume_usd", delta),
-                "token0" => field_from_strings!("hourly_volume_token_0", delta),
-                "token1" => field_from_strings!("hourly_volume_token_1", delta),
+            let field = match field {
+                keys::Field::Usd => field_from_strings!("hourly_volume_usd", delta),
+                keys::Field::Token0 => field_from_strings!("hourly_volume_token_0", delta),
+                keys::Field::Token1 => field_from_strings!("hourly_volume_token_1", delta),
                 _ => return,
             };
             operation = Operation::Update as i32;
-            (
-                "pair_hour_data",
-                format!("{}-{}", pair_address, hour),
-                vec![field],
-            )
+            ("pair_hour_data", format!("{}-{}", pair, hour), vec![field])
         }
-        "pair" => {
-            let pair_address = parts[1];
-            let field_name = parts[2];
-
-            let field = match field_name {
-                "usd" => field_from_strings!("volume_usd", delta),
-
-                "token0" => field_from_strings!("volume_token0", delta),
-                "token1" => field_from_strings!("volume_token1", delta),
-                "total_supply" => field_from_strings!("total_supply", delta),
+        Some(StoreKey::PairTotal { pair, field }) => {
+            let field = match field {
+                keys::Field::Usd => field_from_strings!("volume_usd", delta),
+                keys::Field::UntrackedUsd => field_from_strings!("untracked_volume_usd", delta),
+
+                keys::Field::Token0 => field_from_strings!("volume_token0", delta),
+                keys::Field::Token1 => field_from_strings!("volume_token1", delta),
+                keys::Field::TotalSupply => field_from_strings!("total_supply", delta),
+                keys::Field::FeesUsd => field_from_strings!("fees_usd", delta),
+                keys::Field::ProtocolFeesUsd => field_from_strings!("protocol_fees_usd", delta),
                 _ => return,
             };
 
-            ("pair", pair_address.to_string(), vec![field])
+            ("pair", pair, vec![field])
         }
-        "token_day" => {
+        Some(StoreKey::TokenDay { day, token, field }) => {
             if delta.operation == Operation::Delete as i32 {
                 return;
             }
 
-            let day = parts[1];
-            let token_address = parts[2];
-            let key = parts[3];
-
-            let field = match key {
-                "usd" => field_from_strings!("daily_volume_usd", delta),
+            let field = match field {
+                keys::Field::Usd => field_from_strings!("daily_volume_usd", delta),
                 _ => return,
             };
 
-            (
-                "token_day_data",
-                format!("{}-{}", token_address, day),
-                vec![field],
-            )
+            ("token_day_data", format!("{}-{}", token, day), vec![field])
         }
-        "token" => {
-            let token_address = parts[1];
-            let key = parts[2];
-
-            let field = match key {
-                "trade" => field_from_strings!("trade_volume", delta),
-                "trade_usd" => field_from_strings!("trade_volume_usd", delta),
-                "liquidity" => field_from_strings!("liquidity", delta),
+        Some(StoreKey::TokenTotal { token, field }) => {
+            let field = match field {
+                keys::Field::Trade => field_from_strings!("trade_volume", delta),
+                keys::Field::TradeUsd => field_from_strings!("trade_volume_usd", delta),
+                keys::Field::UntrackedUsd => field_from_strings!("untracked_volume_usd", delta),
                 _ => return,
             };
 
-            ("token", token_address.to_string(), vec![field])
+            ("token", token, vec![field])
         }
-        "global" => {
-            let key = parts[1];
+        Some(StoreKey::Global { field }) => {
             operation = Operation::Update as i32;
-            let field = match key {
-                "usd" => field_from_strings!("total_volume_usd", delta),
-                "native" => field_from_strings!("total_volume_native", delta),
-                "liquidity_usd" => field_from_strings!("total_liquidity_usd", delta),
+            let field = match field {
+                keys::Field::Usd => field_from_strings!("total_volume_usd", delta),
+                keys::Field::Native => field_from_strings!("total_volume_native", delta),
+                keys::Field::UntrackedUsd => field_from_strings!("untracked_volume_usd", delta),
+                keys::Field::LiquidityUsd => field_from_strings!("total_liquidity_usd", delta),
                 _ => return,
             };
 
-            ("pancake_factory", PANCAKE_FACTORY.to_string(), vec![field])
+            ("pancake_factory", PROFILE.factory_address_hex(), vec![field])
         }
-        "global_day" => {
+        Some(StoreKey::GlobalDay { day, field }) => {
             if delta.operation == Operation::Delete as i32 {
                 return;
             }
 
-            let day = parts[1];
-            let key = parts[2];
-
             operation = Operation::Update as i32;
-            let field = match key {
-                "usd" => {
+            let field = match field {
+                keys::Field::Usd => {
                     operation = delta.operation;
                     field_from_strings!("daily_volume_usd", delta)
                 }
-                "native" => field_from_strings!("daily_volume_native", delta),
+                keys::Field::Native => field_from_strings!("daily_volume_native", delta),
                 _ => return,
             };
 
@@ -325,96 +356,93 @@ fn handle_volume_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block:
         ordinal: delta.ordinal,
         operation,
         table: table.to_string(),
-        pk: pk.to_string(),
+        pk,
         fields,
     })
 }
 
-fn handle_reserves_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) {
-    let parts: Vec<&str> = delta.key.split(":").collect();
-    let prefix = parts[0];
+/// APR of the fees left to the liquidity providers so far in the day of a
+/// `delta` of the day's fees or protocol fees, against the pair's reserves at
+/// the end of the block. The other one of the two is read at the ordinal of
+/// `delta`.
+fn daily_fee_apr(
+    delta: &StoreDelta,
+    day: i64,
+    pair_address: &str,
+    field: &keys::Field,
+    pairs_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    volumes_store: &store::StoreGet,
+) -> Option<String> {
+    let value = utils::decode_big_decimal(&delta.key, &delta.new_value).ok()?;
+    let day_value = |field: keys::Field| {
+        let key = StoreKey::PairDay {
+            day,
+            pair: pair_address.to_string(),
+            field,
+        }
+        .encode();
+        match volumes_store.get_at(delta.ordinal, &key) {
+            None => Some(BigDecimal::zero()),
+            Some(value) => utils::decode_big_decimal(&key, &value).ok(),
+        }
+    };
 
-    let mut operation = delta.operation;
+    let (swap_fees_usd, protocol_fees_usd) = match field {
+        keys::Field::FeesUsd => (value, day_value(keys::Field::ProtocolFeesUsd)?),
+        keys::Field::ProtocolFeesUsd => (day_value(keys::Field::FeesUsd)?, value),
+        _ => return None,
+    };
+    let reserve_usd = utils::get_last_reserve_usd(pairs_store, prices_store, pair_address)?;
 
-    let (table, pk, fields) = match prefix {
-        "pair_day" => {
-            if delta.operation == Operation::Delete as i32 {
-                return;
-            }
+    fees::daily_fee_apr(&fees::lp_fees_usd(&swap_fees_usd, &protocol_fees_usd), &reserve_usd)
+        .map(|apr| apr.to_string())
+}
 
-            let day = parts[1];
-            let pair_address = parts[2];
-            let key = parts[3];
+fn handle_reserves_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block: &Clock) {
+    let mut operation = delta.operation;
 
-            let field = match key {
-                "reserve0" => field_from_strings!("reserve_0", delta),
-                "reserve1" => {
-                    operation = Operation::Update as i32;
-                    field_from_strings!("reserve_1", delta)
-                }
-                _ => return,
+    let (table, pk, fields) = match StoreKey::parse(&delta.key) {
+        Some(StoreKey::PairDay { day, pair, field }) => {
+            let fields = match bucket_reserve_fields(field, &delta, &mut operation) {
+                Some(fields) => fields,
+                None => return,
             };
 
-            (
-                "pair_day_data",
-                format!("{}-{}", pair_address, day),
-                vec![field],
-            )
+            ("pair_day_data", format!("{}-{}", pair, day), fields)
         }
-        "pair_hour" => {
-            if delta.operation == Operation::Delete as i32 {
-                return;
-            }
-
-            let hour = parts[1];
-            let pair_address = parts[2];
-            let key = parts[3];
-
-            let field = match key {
-                "reserve0" => field_from_strings!("reserve_0", delta),
-                "reserve1" => {
-                    operation = Operation::Update as i32;
-                    field_from_strings!("reserve_1", delta)
-                }
-                _ => return,
+        Some(StoreKey::PairHour { hour, pair, field }) => {
+            let fields = match bucket_reserve_fields(field, &delta, &mut operation) {
+                Some(fields) => fields,
+                None => return,
             };
 
-            (
-                "pair_hour_data",
-                format!("{}-{}", pair_address, hour),
-                vec![field],
-            )
+            ("pair_hour_data", format!("{}-{}", pair, hour), fields)
         }
-        "price" => {
-            let key = parts[3];
-
-            let field = match key {
-                "token0" => field_from_strings!("token_0_price", delta),
-                "token1" => field_from_strings!("token_1_price", delta),
+        Some(StoreKey::Price { pair, field, .. }) => {
+            let field = match field {
+                keys::Field::Token0 => field_from_strings!("token_0_price", delta),
+                keys::Field::Token1 => field_from_strings!("token_1_price", delta),
                 _ => return,
             };
 
-            let pair_address = parts[1];
-            ("pair", pair_address.to_string(), vec![field])
+            ("pair", pair, vec![field])
         }
-        "reserve" => {
-            let key = parts[3];
-
-            let field = match key {
-                "reserve0" => field_from_strings!("reserve_0", delta),
-                "reserve1" => field_from_strings!("reserve_1", delta),
+        Some(StoreKey::Reserve { pair, field, .. }) => {
+            let field = match field {
+                keys::Field::Reserve0 => field_from_strings!("reserve_0", delta),
+                keys::Field::Reserve1 => field_from_strings!("reserve_1", delta),
                 _ => return,
             };
 
-            let pair_address = parts[1];
-            ("pair", pair_address.to_string(), vec![field])
+            ("pair", pair, vec![field])
         }
         _ => return,
     };
 
     changes.table_changes.push(TableChange {
         table: table.to_string(),
-        pk: pk.to_string(),
+        pk,
         block_num: block.number,
         ordinal: delta.ordinal,
         operation,
@@ -422,6 +450,95 @@ fn handle_reserves_delta(delta: StoreDelta, changes: &mut DatabaseChanges, block
     })
 }
 
+/// Reserves of a pair's day or hour: the first one set in the bucket opens it,
+/// the last one closes it when `store_reserves` deletes the bucket.
+fn bucket_reserve_fields(
+    field: keys::Field,
+    delta: &StoreDelta,
+    operation: &mut i32,
+) -> Option<Vec<Field>> {
+    let name = match field {
+        keys::Field::Reserve0 => "reserve_0",
+        keys::Field::Reserve1 => "reserve_1",
+        _ => return None,
+    };
+
+    if delta.operation == Operation::Delete as i32 {
+        *operation = Operation::Update as i32;
+        let close = String::from_utf8_lossy(delta.old_value.as_slice()).to_string();
+        return Some(vec![field_create_string!(format!("close_{}", name), close)]);
+    }
+
+    let mut fields = vec![field_from_strings!(name, delta)];
+    if delta.operation == Operation::Create as i32 {
+        let open = String::from_utf8_lossy(delta.new_value.as_slice()).to_string();
+        fields.push(field_create_string!(format!("open_{}", name), open));
+    }
+    // reserve0 is set first, its first delta of the bucket creates the row
+    if field == keys::Field::Reserve1 {
+        *operation = Operation::Update as i32;
+    }
+    Some(fields)
+}
+
+/// Position of an account in a pair: its liquidity token balance, its share of
+/// the pair's supply and the USD value of that share of the reserves, as of the
+/// end of the block.
+fn handle_lp_balance_delta(
+    delta: StoreDelta,
+    changes: &mut DatabaseChanges,
+    block: &Clock,
+    pairs_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    lp_balances_store: &store::StoreGet,
+) {
+    let (pair, account) = match StoreKey::parse(&delta.key) {
+        Some(StoreKey::LpBalance { pair, account }) => (pair, account),
+        _ => return,
+    };
+
+    let mut fields = vec![field_from_strings!("liquidity_token_balance", delta)];
+    if delta.operation == store_delta::Operation::Create as i32 {
+        fields.push(field_create_string!("pair", pair));
+        fields.push(field_create_string!("user", account));
+    }
+
+    if let Some(share) = position_share(&delta, &pair, lp_balances_store) {
+        if let Some(reserve_usd) = utils::get_last_reserve_usd(pairs_store, prices_store, &pair) {
+            fields.push(field_create_string!("value_usd", share.clone().mul(reserve_usd).with_prec(100)));
+        }
+        fields.push(field_create_string!("share", share));
+    }
+
+    changes.table_changes.push(TableChange {
+        table: "liquidity_position".to_string(),
+        pk: format!("{}-{}", pair, account),
+        block_num: block.number,
+        ordinal: delta.ordinal,
+        operation: delta.operation,
+        fields,
+    })
+}
+
+fn position_share(
+    delta: &StoreDelta,
+    pair_address: &str,
+    lp_balances_store: &store::StoreGet,
+) -> Option<BigDecimal> {
+    let balance = utils::decode_big_decimal(&delta.key, &delta.new_value).ok()?;
+
+    let supply_key = StoreKey::LpTotalSupply {
+        pair: pair_address.to_string(),
+    }
+    .encode();
+    let supply = utils::decode_big_decimal(&supply_key, &lp_balances_store.get_last(&supply_key)?).ok()?;
+    if supply.is_zero() {
+        return None;
+    }
+
+    Some(balance.div(supply).with_prec(100))
+}
+
 fn handle_events(event: Event, changes: &mut DatabaseChanges, block: &Clock) {
     match event.r#type.as_ref().unwrap() {
         Type::Swap(swap) => handle_swap_event(&swap, &event, changes, block),
@@ -507,13 +624,70 @@ fn handle_mint_event(mint: &Mint, event: &Event, changes: &mut DatabaseChanges,
     });
 }
 
+fn handle_candle(table: &str, candle: Candle, changes: &mut DatabaseChanges, block: &Clock) {
+    let id = format!("{}-{}-{}", candle.address, candle.interval, candle.timestamp);
+    let operation = match candle.opened {
+        true => Operation::Create,
+        false => Operation::Update,
+    };
+
+    changes.table_changes.push(TableChange {
+        table: table.to_string(),
+        pk: id.clone(),
+        block_num: block.number,
+        ordinal: candle.log_ordinal,
+        operation: operation as i32,
+        fields: vec![
+            field_create_string!("id", id),
+            field_create_string!(table.trim_end_matches("_candle"), candle.address),
+            field_create_string!("interval", candle.interval),
+            field_create_string!("timestamp", candle.timestamp),
+            field_create_string!("open", candle.open),
+            field_create_string!("high", candle.high),
+            field_create_string!("low", candle.low),
+            field_create_string!("close", candle.close),
+            field_create_string!("volume", candle.volume),
+            field_create_string!("volume_usd", candle.volume_usd),
+        ],
+    });
+}
+
+/// Every Sync gets its own immutable row, unlike the pair and its day and hour
+/// data which only keep the latest reserves.
+fn handle_reserve_snapshot(reserve: Reserve, changes: &mut DatabaseChanges, block: &Clock) {
+    let id = format!("{}-{}", reserve.transaction_id, reserve.log_ordinal);
+
+    changes.table_changes.push(TableChange {
+        table: "reserve_snapshot".to_string(),
+        pk: id.clone(),
+        block_num: block.number,
+        ordinal: reserve.log_ordinal,
+        operation: Operation::Create as i32,
+        fields: vec![
+            field_create_string!("id", id),
+            field_create_string!("pair", reserve.pair_address),
+            field_create_string!("transaction", reserve.transaction_id),
+            field_create_string!("block", reserve.block_num),
+            field_create_string!("timestamp", block.timestamp.as_ref().unwrap().seconds),
+            field_create_string!("log_ordinal", reserve.log_ordinal),
+            field_create_string!("reserve_0", reserve.reserve0),
+            field_create_string!("reserve_1", reserve.reserve1),
+            field_create_string!("token_0_price", reserve.token0_price),
+            field_create_string!("token_1_price", reserve.token1_price),
+        ],
+    });
+}
+
 fn join_sort_deltas(
     pair_deltas: store::Deltas,
     pcs_token_deltas: store::Deltas,
     total_deltas: store::Deltas,
     volumes_deltas: store::Deltas,
     reserves_delta: store::Deltas,
+    lp_balances_deltas: store::Deltas,
     events: Events,
+    candles: Candles,
+    reserves: Reserves,
 ) -> Vec<Item> {
     struct SortableItem {
         ordinal: u64,
@@ -557,6 +731,13 @@ fn join_sort_deltas(
         })
     }
 
+    for delta in lp_balances_deltas {
+        items.push(SortableItem {
+            ordinal: delta.ordinal,
+            item: Item::LpBalanceDelta(delta),
+        })
+    }
+
     for event in events.events {
         items.push(SortableItem {
             ordinal: event.log_ordinal,
@@ -564,6 +745,123 @@ fn join_sort_deltas(
         })
     }
 
+    for candle in candles.pair_candles {
+        items.push(SortableItem {
+            ordinal: candle.log_ordinal,
+            item: Item::PairCandle(candle),
+        })
+    }
+
+    for candle in candles.token_candles {
+        items.push(SortableItem {
+            ordinal: candle.log_ordinal,
+            item: Item::TokenCandle(candle),
+        })
+    }
+
+    for reserve in reserves.reserves {
+        items.push(SortableItem {
+            ordinal: reserve.log_ordinal,
+            item: Item::ReserveSnapshot(reserve),
+        })
+    }
+
     items.sort_by(|a, b| a.ordinal.cmp(&b.ordinal));
     return items.iter().map(|item| item.item.clone()).collect();
 }
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+    use crate::testing::block::{mint_log, sync_log, transfer_log, BlockBuilder};
+    use crate::testing::{clock, decimal, field_value, table_changes, Pipeline, ONE, PAIR, USER};
+
+    #[test]
+    fn every_sync_gets_a_reserve_snapshot() {
+        let pipeline = Pipeline::with_pair();
+
+        let synced = pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(
+                    USER,
+                    PAIR,
+                    vec![(PAIR, vec![sync_log(2 * ONE, 4 * ONE), sync_log(3 * ONE, 4 * ONE)])],
+                )
+                .build(),
+        );
+
+        let snapshots: Vec<_> = table_changes(&synced, "reserve_snapshot").collect();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[1].pk, "0x00000000000000020000000000000000-1");
+        assert_eq!(field_value(snapshots[1], "pair"), PAIR);
+        assert_eq!(field_value(snapshots[1], "block"), "2");
+        assert_eq!(decimal(field_value(snapshots[0], "reserve_0")), BigDecimal::from(2));
+        assert_eq!(decimal(field_value(snapshots[1], "reserve_0")), BigDecimal::from(3));
+        assert_eq!(
+            decimal(field_value(snapshots[1], "token_0_price")),
+            decimal("0.75".to_string())
+        );
+    }
+
+    #[test]
+    fn liquidity_positions_follow_lp_transfers() {
+        const OTHER: &str = "0x00000000000000000000000000000000000000bb";
+
+        let pipeline = Pipeline::with_pair();
+
+        pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(
+                    USER,
+                    PAIR,
+                    vec![(
+                        PAIR,
+                        vec![
+                            transfer_log("0x0000000000000000000000000000000000000000", USER, 2 * ONE),
+                            sync_log(2 * ONE, 4 * ONE),
+                            mint_log(USER, 2 * ONE, 4 * ONE),
+                        ],
+                    )],
+                )
+                .build(),
+        );
+
+        let transferred = pipeline.process_block(
+            BlockBuilder::new(3, 1_600_000_004)
+                .transaction(USER, PAIR, vec![(PAIR, vec![transfer_log(USER, OTHER, ONE / 2)])])
+                .build(),
+        );
+
+        let position = |account: &str| {
+            table_changes(&transferred, "liquidity_position")
+                .find(|change| change.pk == format!("{}-{}", PAIR, account))
+                .unwrap_or_else(|| panic!("position of {}", account))
+        };
+
+        let sender = position(USER);
+        assert_eq!(decimal(field_value(sender, "liquidity_token_balance")), decimal("1.5".to_string()));
+        assert_eq!(decimal(field_value(sender, "share")), decimal("0.75".to_string()));
+
+        let recipient = position(OTHER);
+        assert_eq!(field_value(recipient, "user"), OTHER);
+        assert_eq!(decimal(field_value(recipient, "share")), decimal("0.25".to_string()));
+    }
+
+    #[test]
+    fn corrupted_token_deltas_are_errors() {
+        let delta = StoreDelta {
+            operation: store_delta::Operation::Create as i32,
+            ordinal: 1,
+            key: "token:0xabc".to_string(),
+            old_value: vec![],
+            new_value: vec![0xff, 0xff],
+        };
+        let mut changes = DatabaseChanges { table_changes: vec![] };
+
+        let error = handle_token_delta(delta, &mut changes, &clock(&BlockBuilder::new(1, 1_600_000_000).build()));
+        assert!(matches!(error, Err(PcsError::Decode { ref key, .. }) if key == "token:0xabc"));
+        assert!(changes.table_changes.is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use substreams::errors::Error;
+
+/// Errors of the sushiswap handlers.
+///
+/// Missing store entries are expected while the stream catches up: a token the
+/// imported tokens store does not know yet, a pair that was never synced, a
+/// price `store_prices` did not derive. Handlers skip or flag what depends on
+/// them and report a warning instead of aborting, see `is_recoverable`. The
+/// other variants point at corrupted store values and do abort.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PcsError {
+    MissingToken(String),
+    MissingPair(String),
+    MissingPrice(String),
+    /// Several prices, like those of both tokens of a swap.
+    MissingPrices(Vec<String>),
+    InvalidDecimal { key: String, value: String },
+    Decode { key: String, reason: String },
+}
+
+impl PcsError {
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            PcsError::MissingToken(_)
+            | PcsError::MissingPair(_)
+            | PcsError::MissingPrice(_)
+            | PcsError::MissingPrices(_) => true,
+            PcsError::InvalidDecimal { .. } | PcsError::Decode { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for PcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcsError::MissingToken(address) => write!(f, "token {} is not in the store", address),
+            PcsError::MissingPair(address) => write!(f, "pair {} is not in the store", address),
+            PcsError::MissingPrice(key) => write!(f, "price {} is not in the store", key),
+            PcsError::MissingPrices(keys) => {
+                write!(f, "prices {} are not in the store", keys.join(", "))
+            }
+            PcsError::InvalidDecimal { key, value } => {
+                write!(f, "value {:?} of {} is not a decimal", value, key)
+            }
+            PcsError::Decode { key, reason } => write!(f, "unable to decode {}: {}", key, reason),
+        }
+    }
+}
+
+impl From<PcsError> for Error {
+    fn from(error: PcsError) -> Self {
+        Error::Unexpected(error.to_string())
+    }
+}
@@ -1,44 +1,16 @@
-use std::convert::TryInto;
+pub const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
 pub fn address_pretty(input: &[u8]) -> String {
     format!("0x{}", hex::encode(input))
 }
 
-pub fn address_decode(address_pretty: &String) -> Vec<u8> {
-    hex::decode(address_pretty.split("0x").collect::<Vec<&str>>()[1]).unwrap()
+/// 32 bytes big endian, like the words of the log data.
+pub fn uint_to_bytes(value: &ethabi::Uint) -> Vec<u8> {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes.to_vec()
 }
 
-pub fn read_uint32(input: &[u8]) -> Result<u32, String> {
-    if input.len() != 32 {
-        return Err(format!("uint32 invalid length: {}", input.len()));
-    }
-    let as_array: [u8; 4] = input[28..32].try_into().unwrap();
-    Ok(u32::from_be_bytes(as_array))
+pub fn address_decode(address_pretty: &String) -> Vec<u8> {
+    hex::decode(address_pretty.split("0x").collect::<Vec<&str>>()[1]).unwrap()
 }
-
-pub fn read_string(input: &[u8]) -> Result<String, String> {
-    if input.len() < 96 {
-        return Err(format!("string invalid length: {}", input.len()));
-    }
-
-    let next = read_uint32(&input[0..32])?;
-    if next != 32 {
-        return Err(format!("invalid string uint32 value: {}", next));
-    };
-
-    let size = read_uint32(&input[32..64])?;
-    let end: usize = (size as usize) + 64;
-
-    if end > input.len() {
-        return Err(format!(
-            "invalid input: end {:?}, length: {:?}, next: {:?}, size: {:?}, whole: {:?}",
-            end,
-            input.len(),
-            next,
-            size,
-            hex::encode(&input[32..64])
-        ));
-    }
-
-    Ok(String::from_utf8_lossy(&input[64..end]).to_string())
-}
\ No newline at end of file
@@ -1,96 +1,68 @@
 use std::ops::{Add, Div, Mul};
-use std::str;
-use std::str::FromStr;
 
-use bigdecimal::{BigDecimal, FromPrimitive};
+use bigdecimal::BigDecimal;
 use num_bigint::BigUint;
-use substreams::store;
 
+use crate::abi::{factory, pair};
+use crate::errors::PcsError;
+use crate::eth::uint_to_bytes;
 use crate::event::pcs_event::Event;
+use crate::keys::{Denomination, StoreKey};
 use crate::pcs::event::Type::{Burn, Mint, Swap};
-use crate::utils::{convert_token_to_decimal, zero_big_decimal};
+use crate::profile::PROFILE;
+use crate::store;
+use crate::utils::{convert_token_to_decimal, get_price_at, zero_big_decimal};
 use crate::{address_pretty, pb, pcs};
 
-pub fn is_pair_created_event(sig: &str) -> bool {
-    /* keccak value for PairCreated(address,address,address,uint256) */
-    return sig == "0d3648bd0f6ba80134a33ba9275ac585d9d315f0ad8355cddefde31afa28d0e9";
-}
-
-pub fn is_pair_approval_event(sig: &str) -> bool {
-    /* keccak value for Approval(address,address,uint256) */
-    return sig == "8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
-}
-
-pub fn is_pair_burn_event(sig: &str) -> bool {
-    /* keccak value for Burn(address,uint256,uint256,address) */
-    return sig == "dccd412f0b1252819cb1fd330b93224ca42612892bb3f4f789976e6d81936496";
-}
-
-pub fn is_pair_mint_event(sig: &str) -> bool {
-    /* keccak value for Mint(address,uint256,uint256) */
-    return sig == "4c209b5fc8ad50758f13e2e1088ba56a560dff690a1c6fef26394f4c03821c4f";
-}
-
-pub fn is_pair_swap_event(sig: &str) -> bool {
-    /* keccak value for Swap(address,uint256,uint256,uint256,uint256,address) */
-    return sig == "d78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822";
-}
-
-pub fn is_pair_sync_event(sig: &str) -> bool {
-    /* keccak value for Sync(uint112,uint112) */
-    return sig == "1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1";
-}
-
-pub fn is_pair_transfer_event(sig: &str) -> bool {
-    /* keccak value for Transfer(address,address,uint256) */
-    return sig == "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
-}
-
-pub fn decode_event(log: pb::eth::Log) -> PcsEvent {
-    let sig = hex::encode(&log.topics[0]);
-
-    if is_pair_created_event(&sig) {
-        return new_pair_created_event(log);
+/// Decodes the events a pair emits (and the factory's `PairCreated`). Logs no
+/// event of the ABIs matches give an empty `PcsEvent`, logs matching an event
+/// but not decoding an error.
+pub fn decode_event(log: &pb::eth::Log) -> Result<PcsEvent, String> {
+    if factory::events::PairCreated::match_log(log) {
+        return Ok(new_pair_created_event(log, factory::events::PairCreated::decode(log)?));
     }
 
-    if is_pair_approval_event(&sig) {
-        return new_pair_approval_event(log);
+    if pair::events::Approval::match_log(log) {
+        return Ok(new_pair_approval_event(log, pair::events::Approval::decode(log)?));
     }
 
-    if is_pair_burn_event(&sig) {
-        return new_pair_burn_event(log);
+    if pair::events::Burn::match_log(log) {
+        return Ok(new_pair_burn_event(log, pair::events::Burn::decode(log)?));
     }
 
-    if is_pair_mint_event(&sig) {
-        return new_pair_mint_event(log);
+    if pair::events::Mint::match_log(log) {
+        return Ok(new_pair_mint_event(log, pair::events::Mint::decode(log)?));
     }
 
-    if is_pair_swap_event(&sig) {
-        return new_pair_swap_event(log);
+    if pair::events::Swap::match_log(log) {
+        return Ok(new_pair_swap_event(log, pair::events::Swap::decode(log)?));
     }
 
-    if is_pair_sync_event(&sig) {
-        return new_pair_sync_event(log);
+    if pair::events::Sync::match_log(log) {
+        return Ok(new_pair_sync_event(log, pair::events::Sync::decode(log)?));
     }
 
-    if is_pair_transfer_event(&sig) {
-        return new_pair_transfer_event(log);
+    if pair::events::Transfer::match_log(log) {
+        return Ok(new_pair_transfer_event(log, pair::events::Transfer::decode(log)?));
     }
 
-    return PcsEvent { event: None };
+    return Ok(PcsEvent { event: None });
 }
 
+/// Fills `base_event` with the mint. When the native USD price is not known
+/// yet the event is still filled, without `amount_usd`, and the missing price is
+/// returned.
 pub fn process_mint(
     mint_id: &str,
     base_event: &mut pcs::Event,
     prices_store: &store::StoreGet,
     pair: &pcs::Pair,
     tr1: Option<&PairTransferEvent>,
-    tr2: Option<&PairTransferEvent>,
+    tr2: &PairTransferEvent,
     pair_mint_event: &PairMintEvent,
     token0_decimals: u64,
     token1_decimals: u64,
-) {
+) -> Result<(), PcsError> {
     let log_ordinal = pair_mint_event.log_index;
     let (amount0, amount1, amount_usd) = convert_prices(
         &prices_store,
@@ -107,39 +79,39 @@ pub fn process_mint(
     let mut mint = pcs::Mint {
         id: mint_id.to_string(),
         sender: address_pretty(pair_mint_event.sender.as_slice()),
-        to: address_pretty(tr2.unwrap().to.as_slice()),
+        to: address_pretty(tr2.to.as_slice()),
         fee_to: "".to_string(),
         amount0: amount0.to_string(),
         amount1: amount1.to_string(),
-        amount_usd: amount_usd.to_string(),
-        liquidity: convert_token_to_decimal(tr2.unwrap().value.as_slice(), &18).to_string(),
+        amount_usd: "".to_string(),
+        liquidity: convert_token_to_decimal(tr2.value.as_slice(), &18).to_string(),
         fee_liquidity: "".to_string(),
     };
 
-    if tr1.is_some() {
-        if BigUint::from_bytes_be(tr1.unwrap().value.as_slice())
-            .ne(&BigUint::from_i32(10000).unwrap())
-        {
-            mint.fee_to = address_pretty(tr1.unwrap().to.as_slice());
-            mint.fee_liquidity =
-                convert_token_to_decimal(tr1.unwrap().value.as_slice(), &18).to_string();
+    if let Some(tr1) = tr1 {
+        if BigUint::from_bytes_be(tr1.value.as_slice()).ne(&BigUint::from(10000u32)) {
+            mint.fee_to = address_pretty(tr1.to.as_slice());
+            mint.fee_liquidity = convert_token_to_decimal(tr1.value.as_slice(), &18).to_string();
         }
     }
 
+    let result = amount_usd.map(|amount_usd| mint.amount_usd = amount_usd.to_string());
     base_event.r#type = Some(Mint(mint));
+    result
 }
 
+/// Fills `base_event` with the burn, see `process_mint` for a missing price.
 pub fn process_burn(
     burn_id: &str,
     base_event: &mut pcs::Event,
     prices_store: &store::StoreGet,
     pair: &pcs::Pair,
     tr1: Option<&PairTransferEvent>,
-    tr2: Option<&PairTransferEvent>,
+    tr2: &PairTransferEvent,
     pair_burn_event: &PairBurnEvent,
     token0_decimals: u64,
     token1_decimals: u64,
-) {
+) -> Result<(), PcsError> {
     let log_ordinal = pair_burn_event.log_index;
     let (amount0, amount1, amount_usd) = convert_prices(
         &prices_store,
@@ -153,129 +125,136 @@ pub fn process_burn(
 
     let mut burn = pcs::Burn {
         id: burn_id.to_string(),
-        sender: address_pretty(tr2.unwrap().from.as_slice()),
-        to: address_pretty(tr2.unwrap().to.as_slice()),
+        sender: address_pretty(tr2.from.as_slice()),
+        to: address_pretty(tr2.to.as_slice()),
         fee_to: "".to_string(),
         amount0: amount0.to_string(),
         amount1: amount1.to_string(),
-        amount_usd: amount_usd.to_string(),
-        liquidity: convert_token_to_decimal(tr2.unwrap().value.as_slice(), &18).to_string(),
+        amount_usd: "".to_string(),
+        liquidity: convert_token_to_decimal(tr2.value.as_slice(), &18).to_string(),
         fee_liquidity: "".to_string(),
     };
 
-    if tr1.is_some() {
-        burn.fee_to = address_pretty(tr1.unwrap().to.as_slice());
-        burn.fee_liquidity =
-            convert_token_to_decimal(tr1.unwrap().value.as_slice(), &18).to_string();
+    if let Some(tr1) = tr1 {
+        burn.fee_to = address_pretty(tr1.to.as_slice());
+        burn.fee_liquidity = convert_token_to_decimal(tr1.value.as_slice(), &18).to_string();
     }
 
+    let result = amount_usd.map(|amount_usd| burn.amount_usd = amount_usd.to_string());
     base_event.r#type = Some(Burn(burn));
     base_event.log_ordinal = log_ordinal;
+    result
 }
 
+/// Fills `base_event` with the swap. When neither token has a USD price the
+/// USD amounts are left empty and the missing prices of both are returned, the
+/// swap is then not tracked by the volume stores.
 pub fn process_swap(
     swap_id: &str,
     base_event: &mut pcs::Event,
     prices_store: &store::StoreGet,
     pair: &pcs::Pair,
-    pair_swap_event: Option<&PairSwapEvent>,
+    swap_event: &PairSwapEvent,
     from_addr: String,
     token0_decimals: u64,
     token1_decimals: u64,
-) {
-    let log_ordinal = pair_swap_event.unwrap().log_index;
-    let swap_event = pair_swap_event.unwrap();
+) -> Result<(), PcsError> {
+    let log_ordinal = swap_event.log_index;
 
-    let amount0_in = convert_token_to_decimal(
-        pair_swap_event.unwrap().amount0_in.as_slice(),
-        &token0_decimals,
-    );
-    let amount1_in = convert_token_to_decimal(
-        pair_swap_event.unwrap().amount1_in.as_slice(),
-        &token1_decimals,
-    );
-    let amount0_out = convert_token_to_decimal(
-        pair_swap_event.unwrap().amount0_out.as_slice(),
-        &token0_decimals,
-    );
-    let amount1_out = convert_token_to_decimal(
-        pair_swap_event.unwrap().amount1_out.as_slice(),
-        &token1_decimals,
-    );
+    let amount0_in = convert_token_to_decimal(swap_event.amount0_in.as_slice(), &token0_decimals);
+    let amount1_in = convert_token_to_decimal(swap_event.amount1_in.as_slice(), &token1_decimals);
+    let amount0_out = convert_token_to_decimal(swap_event.amount0_out.as_slice(), &token0_decimals);
+    let amount1_out = convert_token_to_decimal(swap_event.amount1_out.as_slice(), &token1_decimals);
 
     let amount0_total = amount0_out.clone().add(amount0_in.clone());
     let amount1_total = amount1_out.clone().add(amount1_in.clone());
 
-    let mut big_decimals_native = Vec::new();
-    big_decimals_native.push(get_derived_price(
-        &swap_event.log_index,
+    let amount0_native = get_derived_price(
+        &log_ordinal,
         &prices_store,
-        "native".to_string(),
+        Denomination::Native,
         &amount0_total,
         &pair.token0_address,
-    ));
-    big_decimals_native.push(get_derived_price(
-        &swap_event.log_index,
+    )?;
+    let amount1_native = get_derived_price(
+        &log_ordinal,
         &prices_store,
-        "native".to_string(),
+        Denomination::Native,
         &amount1_total,
         &pair.token1_address,
-    ));
-
-    let mut big_decimals_usd = Vec::new();
-    big_decimals_usd.push(get_derived_price(
-        &swap_event.log_index,
+    )?;
+    let amount0_usd = get_derived_price(
+        &log_ordinal,
         &prices_store,
-        "usd".to_string(),
+        Denomination::Usd,
         &amount0_total,
         &pair.token0_address,
-    ));
-    big_decimals_usd.push(get_derived_price(
-        &swap_event.log_index,
+    )?;
+    let amount1_usd = get_derived_price(
+        &log_ordinal,
         &prices_store,
-        "usd".to_string(),
+        Denomination::Usd,
         &amount1_total,
         &pair.token1_address,
-    ));
+    )?;
 
-    let derived_amount_native = average_floats(&big_decimals_native);
-    let tracked_amount_usd = average_floats(&big_decimals_usd);
+    let priced = amount0_usd.is_some() || amount1_usd.is_some();
+    let derived_amount_native = tracked_amount(pair, &amount0_native, &amount1_native);
+    let tracked_amount_usd = tracked_amount(pair, &amount0_usd, &amount1_usd);
+    let untracked_amount_usd = untracked_amount(&amount0_usd, &amount1_usd);
 
     let token0_trade_volume: BigDecimal = amount1_in.clone().add(&amount0_out);
     let token1_trade_volume: BigDecimal = amount0_in.clone().add(&amount1_out);
 
-    let token0_trade_volume_usd: &BigDecimal = &tracked_amount_usd;
-    let token1_trade_volume_usd: &BigDecimal = &tracked_amount_usd;
-
-    let volume_usd = &tracked_amount_usd;
-    let token0_volume = &amount0_total;
-    let token1_volume = &amount1_total;
+    let usd = |amount: &BigDecimal| match priced {
+        true => amount.to_string(),
+        false => "".to_string(),
+    };
 
     let swap = pcs::Swap {
         id: swap_id.to_string(),
-        sender: address_pretty(pair_swap_event.unwrap().sender.as_slice()),
-        to: address_pretty(pair_swap_event.unwrap().to.as_slice()),
+        sender: address_pretty(swap_event.sender.as_slice()),
+        to: address_pretty(swap_event.to.as_slice()),
         from: from_addr,
         amount0_in: amount0_in.to_string(),
         amount1_in: amount1_in.to_string(),
         amount0_out: amount0_out.to_string(),
         amount1_out: amount1_out.to_string(),
         amount_native: derived_amount_native.to_string(),
-        amount_usd: tracked_amount_usd.to_string(),
+        amount_usd: usd(&tracked_amount_usd),
         trade_volume0: token0_trade_volume.to_string(),
         trade_volume1: token1_trade_volume.to_string(),
-        trade_volume_usd0: token0_trade_volume_usd.to_string(),
-        trade_volume_usd1: token1_trade_volume_usd.to_string(),
-        volume_usd: volume_usd.to_string(),
-        volume_token0: token0_volume.to_string(),
-        volume_token1: token1_volume.to_string(),
-        log_address: String::from_utf8(swap_event.clone().log_address).unwrap(),
+        trade_volume_usd0: usd(&tracked_amount_usd),
+        trade_volume_usd1: usd(&tracked_amount_usd),
+        volume_usd: usd(&tracked_amount_usd),
+        untracked_volume_usd: usd(&untracked_amount_usd),
+        volume_token0: amount0_total.to_string(),
+        volume_token1: amount1_total.to_string(),
+        log_address: address_pretty(swap_event.log_address.as_slice()),
     };
 
     base_event.r#type = Some(Swap(swap));
     base_event.log_ordinal = log_ordinal;
+
+    match priced {
+        true => Ok(()),
+        false => Err(PcsError::MissingPrices(
+            [&pair.token0_address, &pair.token1_address]
+                .iter()
+                .map(|token| {
+                    StoreKey::DerivedPrice {
+                        token: token.to_string(),
+                        denomination: Denomination::Usd,
+                    }
+                    .encode()
+                })
+                .collect(),
+        )),
+    }
 }
 
+/// Token amounts and their value in USD. Tokens without a native price count
+/// as zero, a missing native USD price leaves the value unknown.
 fn convert_prices(
     prices_store: &store::StoreGet,
     log_ordinal: &u64,
@@ -284,171 +263,194 @@ fn convert_prices(
     pair: &pcs::Pair,
     token0_decimals: u64,
     token1_decimals: u64,
-) -> (BigDecimal, BigDecimal, BigDecimal) {
+) -> (BigDecimal, BigDecimal, Result<BigDecimal, PcsError>) {
     let token0_amount = convert_token_to_decimal(amount0, &token0_decimals);
     let token1_amount = convert_token_to_decimal(amount1, &token1_decimals);
 
-    let derived_native0_big_decimal = match prices_store.get_at(
-        *log_ordinal,
-        &format!("dprice:{}:native", pair.token0_address),
-    ) {
-        None => zero_big_decimal(),
-        Some(derived_native0_bytes) => {
-            BigDecimal::from_str(str::from_utf8(derived_native0_bytes.as_slice()).unwrap()).unwrap()
-        }
-    };
-
-    let derived_native1_big_decimal = match prices_store.get_at(
+    let amount_total_usd = amount_usd(
+        prices_store,
         *log_ordinal,
-        &format!("dprice:{}:native", pair.token1_address),
-    ) {
-        None => zero_big_decimal(),
-        Some(derived_native1_bytes) => {
-            BigDecimal::from_str(str::from_utf8(derived_native1_bytes.as_slice()).unwrap()).unwrap()
-        }
-    };
+        pair,
+        &token0_amount,
+        &token1_amount,
+    );
 
-    let usd_price_big_decimal =
-        match prices_store.get_at(*log_ordinal, &format!("dprice:usd:native")) {
-            None => zero_big_decimal(),
-            Some(usd_price_bytes) => {
-                BigDecimal::from_str(str::from_utf8(usd_price_bytes.as_slice()).unwrap()).unwrap()
-            }
-        };
+    return (token0_amount, token1_amount, amount_total_usd);
+}
 
-    let derived_native0_mul_token0_amount = derived_native0_big_decimal.mul(&token0_amount);
-    let derived_native1_mul_token1_amount = derived_native1_big_decimal.mul(&token1_amount);
+fn amount_usd(
+    prices_store: &store::StoreGet,
+    log_ordinal: u64,
+    pair: &pcs::Pair,
+    token0_amount: &BigDecimal,
+    token1_amount: &BigDecimal,
+) -> Result<BigDecimal, PcsError> {
+    let derived_native0_big_decimal =
+        derived_native_price(prices_store, log_ordinal, &pair.token0_address)?;
+    let derived_native1_big_decimal =
+        derived_native_price(prices_store, log_ordinal, &pair.token1_address)?;
+    let usd_price_big_decimal = get_price_at(prices_store, log_ordinal, &StoreKey::NativeUsdPrice)?;
+
+    let derived_native0_mul_token0_amount = derived_native0_big_decimal.mul(token0_amount);
+    let derived_native1_mul_token1_amount = derived_native1_big_decimal.mul(token1_amount);
 
     let sum_derived_native = derived_native0_mul_token0_amount.add(derived_native1_mul_token1_amount);
 
-    let amount_total_usd = sum_derived_native.mul(usd_price_big_decimal);
+    Ok(sum_derived_native.mul(usd_price_big_decimal))
+}
 
-    return (token0_amount, token1_amount, amount_total_usd);
+fn derived_native_price(
+    prices_store: &store::StoreGet,
+    log_ordinal: u64,
+    token_addr: &str,
+) -> Result<BigDecimal, PcsError> {
+    let key = StoreKey::DerivedPrice {
+        token: token_addr.to_string(),
+        denomination: Denomination::Native,
+    };
+    match get_price_at(prices_store, log_ordinal, &key) {
+        Err(PcsError::MissingPrice(_)) => Ok(zero_big_decimal()),
+        result => result,
+    }
 }
 
+/// Value of `token_amount`, `None` when the token has no price.
 fn get_derived_price(
     ord: &u64,
     prices_store: &store::StoreGet,
-    derived_token: String,
+    denomination: Denomination,
     token_amount: &BigDecimal,
     token_addr: &String,
-) -> Option<BigDecimal> {
-    let usd_price_bytes = prices_store
-        .get_at(
-            *ord,
-            &format!("dprice:{}:{}", *token_addr, derived_token),
-        )
-        .unwrap();
-    let usd_price =
-        BigDecimal::from_str(str::from_utf8(usd_price_bytes.as_slice()).unwrap()).unwrap();
-    if usd_price.eq(&zero_big_decimal()) {
-        return None;
+) -> Result<Option<BigDecimal>, PcsError> {
+    let key = StoreKey::DerivedPrice {
+        token: token_addr.clone(),
+        denomination,
+    };
+    let price = match get_price_at(prices_store, *ord, &key) {
+        Err(PcsError::MissingPrice(_)) => return Ok(None),
+        result => result?,
+    };
+    if price.eq(&zero_big_decimal()) {
+        return Ok(None);
     }
 
-    return Some((token_amount).mul(usd_price));
+    return Ok(Some((token_amount).mul(price)));
 }
 
-fn average_floats(big_decimals: &Vec<Option<BigDecimal>>) -> BigDecimal {
-    let mut sum = zero_big_decimal();
-    let mut count: f64 = 0.0;
-    for big_decimal_option in big_decimals {
-        if (*big_decimal_option).is_none() {
-            continue;
-        }
-        sum = sum.add((*big_decimal_option).as_ref().unwrap());
-        count = count + 1.0;
+/// Volume of a swap counted like the subgraph does: only the sides whose token
+/// is whitelisted, averaged when both are, zero when neither is. Unpriced sides
+/// count as zero.
+fn tracked_amount(
+    pair: &pcs::Pair,
+    amount0: &Option<BigDecimal>,
+    amount1: &Option<BigDecimal>,
+) -> BigDecimal {
+    let amount0 = amount0.clone().unwrap_or_else(zero_big_decimal);
+    let amount1 = amount1.clone().unwrap_or_else(zero_big_decimal);
+
+    match (
+        PROFILE.is_whitelisted(&pair.token0_address),
+        PROFILE.is_whitelisted(&pair.token1_address),
+    ) {
+        (true, true) => amount0.add(amount1).div(BigDecimal::from(2)),
+        (true, false) => amount0,
+        (false, true) => amount1,
+        (false, false) => zero_big_decimal(),
     }
+}
 
-    if count.eq(&0.0) {
-        return sum;
-    }
+/// Average of both sides of a swap whitelisted or not, unpriced sides counting
+/// as zero.
+fn untracked_amount(amount0: &Option<BigDecimal>, amount1: &Option<BigDecimal>) -> BigDecimal {
+    let amount0 = amount0.clone().unwrap_or_else(zero_big_decimal);
+    let amount1 = amount1.clone().unwrap_or_else(zero_big_decimal);
 
-    return sum.div(BigDecimal::from_f64(count).unwrap());
+    amount0.add(amount1).div(BigDecimal::from(2))
 }
 
-fn new_pair_created_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_created_event(log: &pb::eth::Log, created: factory::events::PairCreated) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairCreatedEvent(PairCreatedEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            token0: Vec::from(&log.topics[1][12..]),
-            token1: Vec::from(&log.topics[2][12..]),
-            pair: Vec::from(&log.data[12..44]),
+            token0: created.token0,
+            token1: created.token1,
+            pair: created.pair,
         })),
     };
 }
 
-fn new_pair_approval_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_approval_event(log: &pb::eth::Log, approval: pair::events::Approval) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairApprovalEvent(PairApprovalEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            owner: Vec::from(&log.topics[1][12..]),
-            spender: Vec::from(&log.topics[2][12..]),
-            value: Vec::from(&log.data[0..32]),
+            owner: approval.owner,
+            spender: approval.spender,
+            value: uint_to_bytes(&approval.value),
         })),
     };
 }
 
-fn new_pair_burn_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_burn_event(log: &pb::eth::Log, burn: pair::events::Burn) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairBurnEvent(PairBurnEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            sender: Vec::from(&log.topics[1][12..]),
-            amount0: Vec::from(&log.data[0..32]),
-            amount1: Vec::from(&log.data[32..64]),
-            to: Vec::from(&log.topics[2][12..]),
+            sender: burn.sender,
+            amount0: uint_to_bytes(&burn.amount0),
+            amount1: uint_to_bytes(&burn.amount1),
+            to: burn.to,
         })),
     };
 }
 
-fn new_pair_mint_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_mint_event(log: &pb::eth::Log, mint: pair::events::Mint) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairMintEvent(PairMintEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            sender: Vec::from(&log.topics[1][12..]),
-            amount0: Vec::from(&log.data[0..32]),
-            amount1: Vec::from(&log.data[32..64]),
+            sender: mint.sender,
+            amount0: uint_to_bytes(&mint.amount0),
+            amount1: uint_to_bytes(&mint.amount1),
         })),
     };
 }
 
-fn new_pair_swap_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_swap_event(log: &pb::eth::Log, swap: pair::events::Swap) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairSwapEvent(PairSwapEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            sender: Vec::from(&log.topics[1][12..]),
-            amount0_in: Vec::from(&log.data[0..32]),
-            amount1_in: Vec::from(&log.data[32..64]),
-            amount0_out: Vec::from(&log.data[64..96]),
-            amount1_out: Vec::from(&log.data[96..128]),
-            to: Vec::from(&log.topics[2][12..]),
+            sender: swap.sender,
+            amount0_in: uint_to_bytes(&swap.amount0_in),
+            amount1_in: uint_to_bytes(&swap.amount1_in),
+            amount0_out: uint_to_bytes(&swap.amount0_out),
+            amount1_out: uint_to_bytes(&swap.amount1_out),
+            to: swap.to,
         })),
     };
 }
 
-fn new_pair_sync_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_sync_event(log: &pb::eth::Log, sync: pair::events::Sync) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairSyncEvent(PairSyncEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            reserve0: Vec::from(&log.data[0..32]),
-            reserve1: Vec::from(&log.data[32..64]),
+            reserve0: uint_to_bytes(&sync.reserve0),
+            reserve1: uint_to_bytes(&sync.reserve1),
         })),
     };
 }
 
-fn new_pair_transfer_event(log: pb::eth::Log) -> PcsEvent {
+fn new_pair_transfer_event(log: &pb::eth::Log, transfer: pair::events::Transfer) -> PcsEvent {
     return PcsEvent {
         event: Some(Event::PairTransferEvent(PairTransferEvent {
-            log_address: log.address,
+            log_address: log.address.clone(),
             log_index: log.block_index as u64,
-            from: Vec::from(&log.topics[1][12..]),
-            to: Vec::from(&log.topics[2][12..]),
-            value: Vec::from(&log.data[0..32]),
+            from: transfer.from,
+            to: transfer.to,
+            value: uint_to_bytes(&transfer.value),
         })),
     };
 }
@@ -537,3 +539,148 @@ pub struct PairTransferEvent {
     pub to: Vec<u8>,
     pub value: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::testing::block::{
+        mint_log, pair_created_log, swap_log, sync_log, transfer_log, BlockBuilder,
+    };
+    use crate::testing::store::StoreGet;
+    use crate::testing::{
+        synthetic_tokens, table_changes, Pipeline, ONE, PAIR, PAIR_AB, TOKEN_A, TOKEN_B, USER,
+    };
+
+    const WMATIC: &str = "0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270";
+    const USDC: &str = "0x2791bca1f2de4661ed88a30c99a7a9449aa84174";
+    const OTHER: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn pair(token0: &str, token1: &str) -> pcs::Pair {
+        pcs::Pair {
+            token0_address: token0.to_string(),
+            token1_address: token1.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn decimal(value: &str) -> Option<BigDecimal> {
+        Some(BigDecimal::from_str(value).unwrap())
+    }
+
+    #[test]
+    fn tracked_amount_only_counts_whitelisted_sides() {
+        let (amount0, amount1) = (decimal("100"), decimal("300"));
+
+        assert_eq!(
+            tracked_amount(&pair(WMATIC, USDC), &amount0, &amount1),
+            BigDecimal::from(200)
+        );
+        assert_eq!(
+            tracked_amount(&pair(WMATIC, OTHER), &amount0, &amount1),
+            BigDecimal::from(100)
+        );
+        assert_eq!(
+            tracked_amount(&pair(OTHER, USDC), &amount0, &amount1),
+            BigDecimal::from(300)
+        );
+        assert_eq!(
+            tracked_amount(&pair(OTHER, OTHER), &amount0, &amount1),
+            zero_big_decimal()
+        );
+        assert_eq!(untracked_amount(&amount0, &None), BigDecimal::from(50));
+    }
+
+    #[test]
+    fn actions_of_pairs_with_unknown_tokens_are_skipped() {
+        let pipeline = Pipeline::new();
+        let mut tokens = synthetic_tokens();
+        tokens.tokens.retain(|token| token.address != TOKEN_B);
+        pipeline.seed_tokens(tokens);
+
+        let factory = format!("0x{}", PROFILE.factory_address_hex());
+        pipeline.process_block(
+            BlockBuilder::new(1, 1_600_000_000)
+                .transaction(
+                    USER,
+                    &factory,
+                    vec![(
+                        &factory,
+                        vec![pair_created_log(PROFILE.native_address, TOKEN_B, PAIR_AB, 1)],
+                    )],
+                )
+                .build(),
+        );
+
+        let mint = BlockBuilder::new(2, 1_600_000_002)
+            .transaction(
+                USER,
+                PAIR_AB,
+                vec![(
+                    PAIR_AB,
+                    vec![
+                        transfer_log("0x0000000000000000000000000000000000000000", USER, ONE),
+                        sync_log(2 * ONE, 4 * ONE),
+                        mint_log(USER, 2 * ONE, 4 * ONE),
+                    ],
+                )],
+            )
+            .build();
+
+        let minted = pipeline.process_block(mint.clone());
+        assert_eq!(table_changes(&minted, "mint").count(), 0);
+
+        let events = crate::map_burn_swaps_events(
+            mint,
+            StoreGet::new(&pipeline.pairs),
+            StoreGet::new(&pipeline.prices),
+            StoreGet::new(&pipeline.pcs_tokens),
+        )
+        .unwrap();
+        assert!(events.events.is_empty());
+        assert_eq!(events.warnings.len(), 1);
+        assert_eq!(events.warnings[0].item, "mint");
+        assert!(events.warnings[0].skipped);
+    }
+
+    #[test]
+    fn unpriced_swaps_report_the_prices_of_both_tokens() {
+        let pipeline = Pipeline::with_pair();
+        let swap = BlockBuilder::new(2, 1_600_000_002)
+            .transaction(
+                USER,
+                PAIR,
+                vec![(
+                    PAIR,
+                    vec![sync_log(2 * ONE, 4 * ONE), swap_log(USER, USER, ONE, 0, 0, ONE)],
+                )],
+            )
+            .build();
+        pipeline.process_block(swap.clone());
+
+        let events = crate::map_burn_swaps_events(
+            swap,
+            StoreGet::new(&pipeline.pairs),
+            StoreGet::new(&pipeline.prices),
+            StoreGet::new(&pipeline.pcs_tokens),
+        )
+        .unwrap();
+        assert_eq!(events.events.len(), 1);
+        assert_eq!(events.warnings.len(), 1);
+        assert!(!events.warnings[0].skipped);
+
+        let price_key = |token: &str| {
+            StoreKey::DerivedPrice {
+                token: token.to_string(),
+                denomination: Denomination::Usd,
+            }
+            .encode()
+        };
+        assert_eq!(
+            events.warnings[0].reason,
+            PcsError::MissingPrices(vec![price_key(PROFILE.native_address), price_key(TOKEN_A)])
+                .to_string()
+        );
+    }
+}
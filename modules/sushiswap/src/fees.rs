@@ -0,0 +1,103 @@
+use std::ops::{Div, Mul};
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::profile::PROFILE;
+
+const DAYS_PER_YEAR: u32 = 365;
+
+/// Fees paid by a swap, in USD. The whole `swap_fee` of the profile stays in
+/// the pair, the protocol takes its share of it later by minting liquidity
+/// (see `protocol_fees_usd`), so these include the protocol fees.
+pub fn swap_fees_usd(amount_usd: &BigDecimal) -> BigDecimal {
+    amount_usd
+        .mul(BigDecimal::from_str(PROFILE.swap_fee).unwrap())
+        .with_prec(100)
+}
+
+/// Value of the liquidity minted to `fee_to` along with a mint or a burn, at
+/// the USD value per liquidity token of that mint or burn: the part of the
+/// `swap_fees_usd` accrued since the previous mint or burn that the protocol
+/// takes (a sixth of them for a Uniswap V2 pair). `None` when no fee liquidity
+/// was minted.
+pub fn protocol_fees_usd(
+    fee_liquidity: &str,
+    liquidity: &str,
+    amount_usd: &BigDecimal,
+) -> Option<BigDecimal> {
+    let fee_liquidity = BigDecimal::from_str(fee_liquidity).ok()?;
+    let liquidity = BigDecimal::from_str(liquidity).ok()?;
+    if fee_liquidity.is_zero() || liquidity.is_zero() {
+        return None;
+    }
+
+    Some(fee_liquidity.mul(amount_usd).div(liquidity).with_prec(100))
+}
+
+/// Part of the `swap_fees_usd` left to the liquidity providers once the
+/// protocol took its `protocol_fees_usd`. The protocol fees of a day can pay
+/// for swaps of the previous days, the share never goes below zero.
+pub fn lp_fees_usd(swap_fees_usd: &BigDecimal, protocol_fees_usd: &BigDecimal) -> BigDecimal {
+    let lp_fees_usd = swap_fees_usd - protocol_fees_usd;
+    match lp_fees_usd > BigDecimal::zero() {
+        true => lp_fees_usd,
+        false => BigDecimal::zero(),
+    }
+}
+
+/// Fees of a day over the USD value of the pair's reserves, annualized. `None`
+/// for a pair without USD reserves.
+pub fn daily_fee_apr(fees_usd: &BigDecimal, reserve_usd: &BigDecimal) -> Option<BigDecimal> {
+    if reserve_usd.is_zero() {
+        return None;
+    }
+
+    Some(
+        fees_usd
+            .mul(BigDecimal::from(DAYS_PER_YEAR))
+            .div(reserve_usd)
+            .with_prec(100),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn protocol_fees_are_a_part_of_the_swap_fees() {
+        // 0.3% of the volume, of which the protocol mints itself a sixth.
+        let swap_fees_usd = swap_fees_usd(&decimal("1000"));
+        assert_eq!(swap_fees_usd, decimal("3"));
+
+        let protocol_fees_usd = protocol_fees_usd("1", "200", &decimal("100")).unwrap();
+        assert_eq!(protocol_fees_usd, decimal("0.5"));
+
+        assert_eq!(lp_fees_usd(&swap_fees_usd, &protocol_fees_usd), decimal("2.5"));
+        assert_eq!(lp_fees_usd(&decimal("0.2"), &protocol_fees_usd), decimal("0"));
+    }
+
+    #[test]
+    fn protocol_fees_are_valued_like_the_minted_liquidity() {
+        assert_eq!(
+            protocol_fees_usd("0.5", "10", &decimal("2000")),
+            Some(decimal("100"))
+        );
+        assert_eq!(protocol_fees_usd("", "10", &decimal("2000")), None);
+        assert_eq!(protocol_fees_usd("0.5", "0", &decimal("2000")), None);
+    }
+
+    #[test]
+    fn daily_fee_apr_is_annualized() {
+        assert_eq!(
+            daily_fee_apr(&decimal("10"), &decimal("36500")),
+            Some(decimal("0.1"))
+        );
+        assert_eq!(daily_fee_apr(&decimal("10"), &decimal("0")), None);
+    }
+}
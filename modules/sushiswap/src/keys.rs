@@ -0,0 +1,447 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Keys of the `store_pairs`, `store_pair_graph`, `store_reserves`,
+/// `store_prices`, `store_totals`, `store_volumes` and `store_lp_balances` stores, along with the
+/// `token:` keys of the imported tokens store. Writers build them from this
+/// enum and `db.rs` parses deltas back into it, so both sides agree on the
+/// layout. The TWAP and candle stores have their own keys, see `twap.rs` and
+/// `candles.rs`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoreKey {
+    /// `token:{token}`
+    Token { token: String },
+    /// `pair:{pair}`
+    Pair { pair: String },
+    /// `tokens:{token0}:{token1}`, tokens sorted
+    PairTokens { token0: String, token1: String },
+    /// `neighbors:{token}`
+    Neighbors { token: String },
+    /// `reserve:{pair}:{token}:reserve0|reserve1`
+    Reserve { pair: String, token: String, field: Field },
+    /// `price:{pair}:{token}:token0|token1`
+    Price { pair: String, token: String, field: Field },
+    /// `dprice:{token}:native|usd`
+    DerivedPrice { token: String, denomination: Denomination },
+    /// `dprice:usd:native`
+    NativeUsdPrice,
+    /// `droute:{token}:native`
+    DerivedRoute { token: String },
+    /// `dreserve:{pair}:{token}:native|usd`
+    DerivedReserve { pair: String, token: String, denomination: Denomination },
+    /// `dreserves:{pair}:native`
+    DerivedReserves { pair: String },
+    /// `pair:{pair}:{field}`
+    PairTotal { pair: String, field: Field },
+    /// `token:{token}:{field}`
+    TokenTotal { token: String, field: Field },
+    /// `global:{field}`
+    Global { field: Field },
+    /// `global_day:{day}:{field}`
+    GlobalDay { day: i64, field: Field },
+    /// `pair_day:{day}:{pair}:{field}`
+    PairDay { day: i64, pair: String, field: Field },
+    /// `pair_hour:{hour}:{pair}:{field}`
+    PairHour { hour: i64, pair: String, field: Field },
+    /// `token_day:{day}:{token}:{field}`
+    TokenDay { day: i64, token: String, field: Field },
+    /// `pair_day:{day}:{pair}:dreserve:{token}:usd`
+    PairDayDerivedReserve { day: i64, pair: String, token: String },
+    /// `pair_hour:{hour}:{pair}:dreserve:{token}:usd`
+    PairHourDerivedReserve { hour: i64, pair: String, token: String },
+    /// `token_day:{day}:dprice:{token}:usd`
+    TokenDayDerivedPrice { day: i64, token: String },
+    /// `lp:{pair}:{account}`
+    LpBalance { pair: String, account: String },
+    /// `lp_supply:{pair}`
+    LpTotalSupply { pair: String },
+}
+
+/// Last part of the keys holding a counter, a volume or a reserve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    TransactionCount,
+    SwapCount,
+    MintCount,
+    BurnCount,
+    PairCount,
+    Usd,
+    Native,
+    Token0,
+    Token1,
+    Reserve0,
+    Reserve1,
+    TotalSupply,
+    LiquidityUsd,
+    Trade,
+    TradeUsd,
+    UntrackedUsd,
+    FeesUsd,
+    ProtocolFeesUsd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Denomination {
+    Native,
+    Usd,
+}
+
+const FIELDS: &[(Field, &str)] = &[
+    (Field::TransactionCount, "transaction_count"),
+    (Field::SwapCount, "swap_count"),
+    (Field::MintCount, "mint_count"),
+    (Field::BurnCount, "burn_count"),
+    (Field::PairCount, "pair_count"),
+    (Field::Usd, "usd"),
+    (Field::Native, "native"),
+    (Field::Token0, "token0"),
+    (Field::Token1, "token1"),
+    (Field::Reserve0, "reserve0"),
+    (Field::Reserve1, "reserve1"),
+    (Field::TotalSupply, "total_supply"),
+    (Field::LiquidityUsd, "liquidity_usd"),
+    (Field::Trade, "trade"),
+    (Field::TradeUsd, "trade_usd"),
+    (Field::UntrackedUsd, "untracked_usd"),
+    (Field::FeesUsd, "fees_usd"),
+    (Field::ProtocolFeesUsd, "protocol_fees_usd"),
+];
+
+impl Field {
+    pub fn as_str(&self) -> &'static str {
+        FIELDS.iter().find(|(field, _)| field == self).unwrap().1
+    }
+
+    pub fn parse(value: &str) -> Option<Field> {
+        FIELDS
+            .iter()
+            .find(|(_, name)| *name == value)
+            .map(|(field, _)| *field)
+    }
+}
+
+impl Denomination {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Denomination::Native => "native",
+            Denomination::Usd => "usd",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Denomination> {
+        match value {
+            "native" => Some(Denomination::Native),
+            "usd" => Some(Denomination::Usd),
+            _ => None,
+        }
+    }
+}
+
+impl StoreKey {
+    pub fn pair_tokens(token_a: &str, token_b: &str) -> StoreKey {
+        let (token0, token1) = match token_a > token_b {
+            true => (token_b, token_a),
+            false => (token_a, token_b),
+        };
+        StoreKey::PairTokens {
+            token0: token0.to_string(),
+            token1: token1.to_string(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            StoreKey::Token { token } => format!("token:{}", token),
+            StoreKey::Pair { pair } => format!("pair:{}", pair),
+            StoreKey::PairTokens { token0, token1 } => format!("tokens:{}:{}", token0, token1),
+            StoreKey::Neighbors { token } => format!("neighbors:{}", token),
+            StoreKey::Reserve { pair, token, field } => {
+                format!("reserve:{}:{}:{}", pair, token, field.as_str())
+            }
+            StoreKey::Price { pair, token, field } => {
+                format!("price:{}:{}:{}", pair, token, field.as_str())
+            }
+            StoreKey::DerivedPrice {
+                token,
+                denomination,
+            } => format!("dprice:{}:{}", token, denomination.as_str()),
+            StoreKey::NativeUsdPrice => "dprice:usd:native".to_string(),
+            StoreKey::DerivedRoute { token } => format!("droute:{}:native", token),
+            StoreKey::DerivedReserve {
+                pair,
+                token,
+                denomination,
+            } => format!("dreserve:{}:{}:{}", pair, token, denomination.as_str()),
+            StoreKey::DerivedReserves { pair } => format!("dreserves:{}:native", pair),
+            StoreKey::PairTotal { pair, field } => format!("pair:{}:{}", pair, field.as_str()),
+            StoreKey::TokenTotal { token, field } => {
+                format!("token:{}:{}", token, field.as_str())
+            }
+            StoreKey::Global { field } => format!("global:{}", field.as_str()),
+            StoreKey::GlobalDay { day, field } => {
+                format!("{}{}", StoreKey::global_day_prefix(*day), field.as_str())
+            }
+            StoreKey::PairDay { day, pair, field } => {
+                format!("{}{}:{}", StoreKey::pair_day_prefix(*day), pair, field.as_str())
+            }
+            StoreKey::PairHour { hour, pair, field } => {
+                format!("{}{}:{}", StoreKey::pair_hour_prefix(*hour), pair, field.as_str())
+            }
+            StoreKey::TokenDay { day, token, field } => {
+                format!("{}{}:{}", StoreKey::token_day_prefix(*day), token, field.as_str())
+            }
+            StoreKey::PairDayDerivedReserve { day, pair, token } => {
+                format!("{}{}:dreserve:{}:usd", StoreKey::pair_day_prefix(*day), pair, token)
+            }
+            StoreKey::PairHourDerivedReserve { hour, pair, token } => {
+                format!("{}{}:dreserve:{}:usd", StoreKey::pair_hour_prefix(*hour), pair, token)
+            }
+            StoreKey::TokenDayDerivedPrice { day, token } => {
+                format!("{}dprice:{}:usd", StoreKey::token_day_prefix(*day), token)
+            }
+            StoreKey::LpBalance { pair, account } => format!("lp:{}:{}", pair, account),
+            StoreKey::LpTotalSupply { pair } => format!("lp_supply:{}", pair),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<StoreKey> {
+        let parts: Vec<&str> = key.split(':').collect();
+        let owned = |index: usize| parts[index].to_string();
+
+        let store_key = match (parts[0], parts.len()) {
+            ("token", 2) => StoreKey::Token { token: owned(1) },
+            ("pair", 2) => StoreKey::Pair { pair: owned(1) },
+            ("tokens", 3) => StoreKey::PairTokens {
+                token0: owned(1),
+                token1: owned(2),
+            },
+            ("neighbors", 2) => StoreKey::Neighbors { token: owned(1) },
+            ("reserve", 4) => StoreKey::Reserve {
+                pair: owned(1),
+                token: owned(2),
+                field: Field::parse(parts[3])?,
+            },
+            ("price", 4) => StoreKey::Price {
+                pair: owned(1),
+                token: owned(2),
+                field: Field::parse(parts[3])?,
+            },
+            ("dprice", 3) if parts[1] == "usd" && parts[2] == "native" => StoreKey::NativeUsdPrice,
+            ("dprice", 3) => StoreKey::DerivedPrice {
+                token: owned(1),
+                denomination: Denomination::parse(parts[2])?,
+            },
+            ("droute", 3) if parts[2] == "native" => StoreKey::DerivedRoute { token: owned(1) },
+            ("dreserve", 4) => StoreKey::DerivedReserve {
+                pair: owned(1),
+                token: owned(2),
+                denomination: Denomination::parse(parts[3])?,
+            },
+            ("dreserves", 3) if parts[2] == "native" => StoreKey::DerivedReserves { pair: owned(1) },
+            ("pair", 3) => StoreKey::PairTotal {
+                pair: owned(1),
+                field: Field::parse(parts[2])?,
+            },
+            ("token", 3) => StoreKey::TokenTotal {
+                token: owned(1),
+                field: Field::parse(parts[2])?,
+            },
+            ("global", 2) => StoreKey::Global {
+                field: Field::parse(parts[1])?,
+            },
+            ("global_day", 3) => StoreKey::GlobalDay {
+                day: i64::from_str(parts[1]).ok()?,
+                field: Field::parse(parts[2])?,
+            },
+            ("pair_day", 4) => StoreKey::PairDay {
+                day: i64::from_str(parts[1]).ok()?,
+                pair: owned(2),
+                field: Field::parse(parts[3])?,
+            },
+            ("pair_hour", 4) => StoreKey::PairHour {
+                hour: i64::from_str(parts[1]).ok()?,
+                pair: owned(2),
+                field: Field::parse(parts[3])?,
+            },
+            ("token_day", 4) => StoreKey::TokenDay {
+                day: i64::from_str(parts[1]).ok()?,
+                token: owned(2),
+                field: Field::parse(parts[3])?,
+            },
+            ("pair_day", 6) if parts[3] == "dreserve" && parts[5] == "usd" => {
+                StoreKey::PairDayDerivedReserve {
+                    day: i64::from_str(parts[1]).ok()?,
+                    pair: owned(2),
+                    token: owned(4),
+                }
+            }
+            ("pair_hour", 6) if parts[3] == "dreserve" && parts[5] == "usd" => {
+                StoreKey::PairHourDerivedReserve {
+                    hour: i64::from_str(parts[1]).ok()?,
+                    pair: owned(2),
+                    token: owned(4),
+                }
+            }
+            ("token_day", 5) if parts[2] == "dprice" && parts[4] == "usd" => {
+                StoreKey::TokenDayDerivedPrice {
+                    day: i64::from_str(parts[1]).ok()?,
+                    token: owned(3),
+                }
+            }
+            ("lp", 3) => StoreKey::LpBalance {
+                pair: owned(1),
+                account: owned(2),
+            },
+            ("lp_supply", 2) => StoreKey::LpTotalSupply { pair: owned(1) },
+            _ => return None,
+        };
+
+        Some(store_key)
+    }
+
+    /// Prefixes of the keys bucketed by day or hour, for `delete_prefix`.
+    pub fn pair_day_prefix(day: i64) -> String {
+        format!("pair_day:{}:", day)
+    }
+
+    pub fn pair_hour_prefix(hour: i64) -> String {
+        format!("pair_hour:{}:", hour)
+    }
+
+    pub fn token_day_prefix(day: i64) -> String {
+        format!("token_day:{}:", day)
+    }
+
+    pub fn global_day_prefix(day: i64) -> String {
+        format!("global_day:{}:", day)
+    }
+}
+
+impl fmt::Display for StoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAIR: &str = "0x00000000000000000000000000000000000000b1";
+    const TOKEN: &str = "0x00000000000000000000000000000000000000a1";
+
+    #[test]
+    fn keys_round_trip() {
+        let keys = vec![
+            StoreKey::Token { token: TOKEN.to_string() },
+            StoreKey::Pair { pair: PAIR.to_string() },
+            StoreKey::pair_tokens(TOKEN, PAIR),
+            StoreKey::Neighbors { token: TOKEN.to_string() },
+            StoreKey::Reserve {
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+                field: Field::Reserve1,
+            },
+            StoreKey::Price {
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+                field: Field::Token0,
+            },
+            StoreKey::DerivedPrice {
+                token: TOKEN.to_string(),
+                denomination: Denomination::Usd,
+            },
+            StoreKey::NativeUsdPrice,
+            StoreKey::DerivedRoute { token: TOKEN.to_string() },
+            StoreKey::DerivedReserve {
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+                denomination: Denomination::Native,
+            },
+            StoreKey::DerivedReserves { pair: PAIR.to_string() },
+            StoreKey::PairTotal {
+                pair: PAIR.to_string(),
+                field: Field::TransactionCount,
+            },
+            StoreKey::TokenTotal {
+                token: TOKEN.to_string(),
+                field: Field::TradeUsd,
+            },
+            StoreKey::Global { field: Field::LiquidityUsd },
+            StoreKey::GlobalDay { day: 18_500, field: Field::Native },
+            StoreKey::PairDay {
+                day: 18_500,
+                pair: PAIR.to_string(),
+                field: Field::Reserve0,
+            },
+            StoreKey::PairHour {
+                hour: 444_000,
+                pair: PAIR.to_string(),
+                field: Field::Usd,
+            },
+            StoreKey::TokenDay {
+                day: 18_500,
+                token: TOKEN.to_string(),
+                field: Field::Usd,
+            },
+            StoreKey::PairDayDerivedReserve {
+                day: 18_500,
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+            },
+            StoreKey::PairHourDerivedReserve {
+                hour: 444_000,
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+            },
+            StoreKey::TokenDayDerivedPrice { day: 18_500, token: TOKEN.to_string() },
+            StoreKey::LpBalance {
+                pair: PAIR.to_string(),
+                account: TOKEN.to_string(),
+            },
+            StoreKey::LpTotalSupply { pair: PAIR.to_string() },
+        ];
+
+        for key in keys {
+            assert_eq!(StoreKey::parse(&key.encode()), Some(key.clone()), "{}", key);
+        }
+    }
+
+    #[test]
+    fn keys_keep_their_layout() {
+        assert_eq!(
+            StoreKey::Reserve {
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+                field: Field::Reserve0,
+            }
+            .encode(),
+            format!("reserve:{}:{}:reserve0", PAIR, TOKEN)
+        );
+        assert_eq!(
+            StoreKey::pair_tokens(PAIR, TOKEN).encode(),
+            format!("tokens:{}:{}", TOKEN, PAIR)
+        );
+        assert_eq!(StoreKey::NativeUsdPrice.encode(), "dprice:usd:native");
+        assert_eq!(
+            StoreKey::GlobalDay { day: 1, field: Field::Usd }.encode(),
+            "global_day:1:usd"
+        );
+        assert_eq!(
+            StoreKey::PairDayDerivedReserve {
+                day: 1,
+                pair: PAIR.to_string(),
+                token: TOKEN.to_string(),
+            }
+            .encode(),
+            format!("pair_day:1:{}:dreserve:{}:usd", PAIR, TOKEN)
+        );
+    }
+
+    #[test]
+    fn unknown_keys_do_not_parse() {
+        assert_eq!(StoreKey::parse("reserve:0xpair:0xtoken"), None);
+        assert_eq!(StoreKey::parse("pair:0xpair:unknown_field"), None);
+        assert_eq!(StoreKey::parse("pair_day:not_a_day:0xpair:usd"), None);
+    }
+}
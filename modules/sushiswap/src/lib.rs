@@ -1,51 +1,76 @@
 extern crate core;
 
+use std::collections::{BTreeMap, HashSet};
 use std::ops::{Mul, Neg};
 use std::str::FromStr;
 
 use bigdecimal::BigDecimal;
-use hex;
-use substreams::{log, proto, store};
+use substreams::{log, proto};
 use substreams::errors::Error;
+use substreams::pb::substreams::store_delta;
 
 use eth::{address_decode, address_pretty};
 
+use crate::errors::PcsError;
 use crate::event::pcs_event::Event;
-use crate::event::PcsEvent;
+use crate::keys::{Denomination, Field, StoreKey};
 use crate::pb::database::DatabaseChanges;
 use crate::pb::pcs;
 use crate::pb::tokens::Token;
 use crate::pcs::event::Type;
+use crate::profile::PROFILE;
+use crate::route::{Route, RouteFinder};
+use crate::sequence::PairAction;
 use crate::utils::zero_big_decimal;
 
+mod abi;
+mod buckets;
+mod candles;
 mod db;
+mod errors;
 mod eth;
 mod event;
+mod fees;
+mod keys;
 mod macros;
 mod pb;
+mod profile;
+mod route;
 mod rpc;
+mod sequence;
+mod store;
+#[cfg(test)]
+mod testing;
+mod twap;
 mod utils;
 
-#[substreams::handlers::map]
+#[cfg_attr(not(test), substreams::handlers::map)]
 pub fn map_pairs(blk: pb::eth::Block) -> Result<pcs::Pairs, Error> {
     let mut pairs = pcs::Pairs { pairs: vec![] };
 
     for trx in blk.transaction_traces {
-        if hex::encode(&trx.to) != "0xc35dadb65012ec5796536bd9864ed8773abc74c4" {
+        if !PROFILE.is_factory(&trx.to) {
             continue;
         }
 
-        for log in trx.receipt.unwrap().logs {
-            let sig = hex::encode(&log.topics[0]);
+        let receipt = match trx.receipt.as_ref() {
+            None => continue,
+            Some(receipt) => receipt,
+        };
 
-            if !event::is_pair_created_event(sig.as_str()) {
+        for log in &receipt.logs {
+            if !abi::factory::events::PairCreated::match_log(log) {
                 continue;
             }
 
+            let created = abi::factory::events::PairCreated::decode(log).map_err(|reason| {
+                Error::Unexpected(format!("invalid PairCreated log in {}: {}", address_pretty(&trx.hash), reason))
+            })?;
+
             pairs.pairs.push(pcs::Pair {
-                address: address_pretty(&log.data[12..32]),
-                token0_address: address_pretty(&log.topics[1][12..]),
-                token1_address: address_pretty(&log.topics[2][12..]),
+                address: address_pretty(&created.pair),
+                token0_address: address_pretty(&created.token0),
+                token1_address: address_pretty(&created.token1),
                 creation_transaction_id: address_pretty(&trx.hash),
                 block_num: blk.number,
                 log_ordinal: log.block_index as u64,
@@ -56,53 +81,99 @@ pub fn map_pairs(blk: pb::eth::Block) -> Result<pcs::Pairs, Error> {
     Ok(pairs)
 }
 
-#[substreams::handlers::store]
+#[cfg_attr(not(test), substreams::handlers::store)]
 pub fn store_pairs(pairs: pcs::Pairs, output: store::StoreSet) {
     log::info!("Building pair state");
     for pair in pairs.pairs {
         output.set(
             pair.log_ordinal,
-            format!("pair:{}", pair.address),
+            StoreKey::Pair {
+                pair: pair.address.clone(),
+            }
+            .encode(),
             &proto::encode(&pair).unwrap(),
         );
         output.set(
             pair.log_ordinal as u64,
-            format!(
-                "tokens:{}",
-                utils::generate_tokens_key(
-                    pair.token0_address.as_str(),
-                    pair.token1_address.as_str(),
-                )
-            ),
+            StoreKey::pair_tokens(&pair.token0_address, &pair.token1_address).encode(),
             &proto::encode(&pair).unwrap(),
         );
     }
 }
 
-#[substreams::handlers::map]
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_pair_graph(pairs: pcs::Pairs, output: store::StoreAppend) {
+    for pair in pairs.pairs {
+        output.append(
+            pair.log_ordinal,
+            StoreKey::Neighbors {
+                token: pair.token0_address.clone(),
+            }
+            .encode(),
+            &route::neighbor_entry(&pair.token1_address, &pair.address),
+        );
+        output.append(
+            pair.log_ordinal,
+            StoreKey::Neighbors {
+                token: pair.token1_address.clone(),
+            }
+            .encode(),
+            &route::neighbor_entry(&pair.token0_address, &pair.address),
+        );
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::map)]
 pub fn map_reserves(blk: pb::eth::Block, pairs: store::StoreGet, tokens: store::StoreGet) -> Result<pcs::Reserves, Error> {
     let mut reserves = pcs::Reserves { reserves: vec![] };
+    let block_num = blk.number;
 
     for trx in blk.transaction_traces {
-        for log in trx.receipt.unwrap().logs {
-            let addr = address_pretty(&log.address);
-            match pairs.get_last(&format!("pair:{}", addr)) {
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            let pair_key = StoreKey::Pair {
+                pair: address_pretty(&log.address),
+            }
+            .encode();
+            match pairs.get_last(&pair_key) {
                 None => continue,
                 Some(pair_bytes) => {
-                    let sig = hex::encode(&log.topics[0]);
-
-                    if !event::is_pair_sync_event(sig.as_str()) {
+                    if !abi::pair::events::Sync::match_log(&log) {
                         continue;
                     }
 
-                    let pair: pcs::Pair = proto::decode(&pair_bytes).unwrap();
-
-                    let token0: Token = utils::get_last_token(&tokens, &pair.token0_address);
-                    let reserve0 =
-                        utils::convert_token_to_decimal(&log.data[0..32], &token0.decimals);
-                    let token1: Token = utils::get_last_token(&tokens, &pair.token1_address);
-                    let reserve1 =
-                        utils::convert_token_to_decimal(&log.data[32..64], &token1.decimals);
+                    let pair: pcs::Pair = utils::decode_proto(&pair_key, &pair_bytes)?;
+
+                    let sync = match abi::pair::events::Sync::decode(&log) {
+                        Ok(sync) => sync,
+                        Err(reason) => {
+                            log::info!("skipping invalid sync of pair {}: {}", pair.address, reason);
+                            continue;
+                        }
+                    };
+
+                    let (token0, token1): (Token, Token) =
+                        match utils::get_last_pair_tokens(&tokens, &pair) {
+                            Ok(tokens) => tokens,
+                            Err(error) if error.is_recoverable() => {
+                                log::info!("skipping sync of pair {}: {}", pair.address, error);
+                                continue;
+                            }
+                            Err(error) => return Err(error.into()),
+                        };
+
+                    let reserve0 = utils::convert_token_to_decimal(
+                        &eth::uint_to_bytes(&sync.reserve0),
+                        &token0.decimals,
+                    );
+                    let reserve1 = utils::convert_token_to_decimal(
+                        &eth::uint_to_bytes(&sync.reserve1),
+                        &token1.decimals,
+                    );
 
                     let token0_price = utils::get_token_price(reserve0.clone(), reserve1.clone());
                     let token1_price = utils::get_token_price(reserve1.clone(), reserve0.clone());
@@ -114,6 +185,8 @@ pub fn map_reserves(blk: pb::eth::Block, pairs: store::StoreGet, tokens: store::
                         log_ordinal: log.block_index as u64,
                         token0_price: token0_price.to_string(),
                         token1_price: token1_price.to_string(),
+                        transaction_id: address_pretty(&trx.hash),
+                        block_num,
                     });
                 }
             }
@@ -123,87 +196,138 @@ pub fn map_reserves(blk: pb::eth::Block, pairs: store::StoreGet, tokens: store::
     Ok(reserves)
 }
 
-#[substreams::handlers::store]
-pub fn store_reserves(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, pairs: store::StoreGet, output: store::StoreSet) {
-    let timestamp_seconds = clock.timestamp.unwrap().seconds;
-    let day_id: i64 = timestamp_seconds / 86400;
-    let hour_id: i64 = timestamp_seconds / 3600;
+/// Current day, hour and TWAP window bucket ids, the deltas of this store tell
+/// the bucketed stores and `db_out` when a bucket closes.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_buckets(clock: substreams::pb::substreams::Clock, output: store::StoreSet) {
+    for bucket in buckets::BUCKETS {
+        output.set(0, bucket.key(), &Vec::from(bucket.id(&clock).to_string()));
+    }
+    for window in twap::WINDOWS {
+        output.set(0, window.key(), &Vec::from(window.bucket(&clock).to_string()));
+    }
+}
 
-    output.delete_prefix(0, &format!("pair_day:{}:", day_id - 1));
-    output.delete_prefix(0, &format!("pair_hour:{}:", hour_id - 1));
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_reserves(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, pairs: store::StoreGet, buckets_deltas: store::Deltas, output: store::StoreSet) {
+    let day_id: i64 = buckets::DAY.id(&clock);
+    let hour_id: i64 = buckets::HOUR.id(&clock);
+
+    for day in buckets::expired(&buckets_deltas, &buckets::DAY) {
+        output.delete_prefix(0, &StoreKey::pair_day_prefix(day));
+    }
+    for hour in buckets::expired(&buckets_deltas, &buckets::HOUR) {
+        output.delete_prefix(0, &StoreKey::pair_hour_prefix(hour));
+    }
 
     for reserve in reserves.reserves {
-        match pairs.get_last(&format!("pair:{}", reserve.pair_address)) {
+        let pair_key = StoreKey::Pair { pair: reserve.pair_address.clone() }.encode();
+        match pairs.get_last(&pair_key) {
             None => continue,
             Some(pair_bytes) => {
-                let pair: pcs::Pair = proto::decode(&pair_bytes).unwrap();
+                let pair: pcs::Pair = match utils::decode_proto(&pair_key, &pair_bytes) {
+                    Ok(pair) => pair,
+                    Err(error) => {
+                        log::info!("skipping reserve of pair {}: {}", reserve.pair_address, error);
+                        continue;
+                    }
+                };
 
                 output.set(
                     reserve.log_ordinal,
-                    format!("price:{}:{}:token0", pair.address, pair.token0_address),
+                    StoreKey::Price {
+                        pair: pair.address.clone(),
+                        token: pair.token0_address.clone(),
+                        field: Field::Token0,
+                    }
+                    .encode(),
                     &Vec::from(reserve.token0_price),
                 );
                 output.set(
                     reserve.log_ordinal,
-                    format!("price:{}:{}:token1", pair.address, pair.token1_address),
+                    StoreKey::Price {
+                        pair: pair.address.clone(),
+                        token: pair.token1_address.clone(),
+                        field: Field::Token1,
+                    }
+                    .encode(),
                     &Vec::from(reserve.token1_price),
                 );
 
-                output.set_many(
-                    reserve.log_ordinal,
-                    &vec![
-                        format!(
-                            "reserve:{}:{}:reserve0",
-                            reserve.pair_address, pair.token0_address
-                        ),
-                        format!("pair_day:{}:{}:reserve0", day_id, pair.token0_address),
-                        format!("pair_hour:{}:{}:reserve0", hour_id, pair.token0_address),
-                    ],
-                    &Vec::from(reserve.reserve0),
-                );
-
-                output.set_many(
-                    reserve.log_ordinal,
-                    &vec![
-                        format!(
-                            "reserve:{}:{}:reserve1",
-                            reserve.pair_address, pair.token1_address
-                        ),
-                        format!("pair_day:{}:{}:reserve1", day_id, pair.token1_address),
-                        format!("pair_hour:{}:{}:reserve1", hour_id, pair.token1_address),
-                    ],
-                    &Vec::from(reserve.reserve1),
-                )
+                for (token, field, amount) in [
+                    (&pair.token0_address, Field::Reserve0, reserve.reserve0),
+                    (&pair.token1_address, Field::Reserve1, reserve.reserve1),
+                ] {
+                    output.set_many(
+                        reserve.log_ordinal,
+                        &vec![
+                            StoreKey::Reserve {
+                                pair: reserve.pair_address.clone(),
+                                token: token.clone(),
+                                field,
+                            }
+                            .encode(),
+                            StoreKey::PairDay {
+                                day: day_id,
+                                pair: reserve.pair_address.clone(),
+                                field,
+                            }
+                            .encode(),
+                            StoreKey::PairHour {
+                                hour: hour_id,
+                                pair: reserve.pair_address.clone(),
+                                field,
+                            }
+                            .encode(),
+                        ],
+                        &Vec::from(amount),
+                    );
+                }
             }
         }
     }
 }
 
-#[substreams::handlers::store]
-pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, pairs: store::StoreGet, reserves_store: store::StoreGet, output: store::StoreSet) {
-    let timestamp_seconds = clock.timestamp.unwrap().seconds;
-    let day_id: i64 = timestamp_seconds / 86400;
-    let hour_id: i64 = timestamp_seconds / 3600;
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, pairs: store::StoreGet, reserves_store: store::StoreGet, pair_graph: store::StoreGet, buckets_deltas: store::Deltas, output: store::StoreSet) {
+    let day_id: i64 = buckets::DAY.id(&clock);
+    let hour_id: i64 = buckets::HOUR.id(&clock);
 
-    output.delete_prefix(0, &format!("pair_day:{}:", day_id - 1));
-    output.delete_prefix(0, &format!("pair_hour:{}:", hour_id - 1));
-    output.delete_prefix(0, &format!("token_day:{}:", day_id - 1));
+    for day in buckets::expired(&buckets_deltas, &buckets::DAY) {
+        output.delete_prefix(0, &StoreKey::pair_day_prefix(day));
+        output.delete_prefix(0, &StoreKey::token_day_prefix(day));
+    }
+    for hour in buckets::expired(&buckets_deltas, &buckets::HOUR) {
+        output.delete_prefix(0, &StoreKey::pair_hour_prefix(hour));
+    }
 
     for reserve in reserves.reserves {
-        match pairs.get_last(&format!("pair:{}", reserve.pair_address)) {
+        let pair_key = StoreKey::Pair { pair: reserve.pair_address.clone() }.encode();
+        match pairs.get_last(&pair_key) {
             None => continue,
             Some(pair_bytes) => {
-                let pair: pcs::Pair = proto::decode(&pair_bytes).unwrap();
+                let pair: pcs::Pair = match utils::decode_proto(&pair_key, &pair_bytes) {
+                    Ok(pair) => pair,
+                    Err(error) => {
+                        log::info!("skipping prices of pair {}: {}", reserve.pair_address, error);
+                        continue;
+                    }
+                };
 
+                // store handlers cannot return errors, the prices of a corrupted reserve are skipped
                 let latest_usd_price: BigDecimal =
-                    utils::compute_usd_price(&reserves_store, &reserve);
-
-                if reserve.pair_address.eq(&utils::WMATIC_USDT_PAIR)
-                    || reserve.pair_address.eq(&utils::WMATIC_USDC_PAIR)
-                {
+                    match utils::compute_usd_price(&pairs, &reserves_store, &reserve) {
+                        Ok(usd_price) => usd_price,
+                        Err(error) => {
+                            log::info!("skipping prices of pair {}: {}", reserve.pair_address, error);
+                            continue;
+                        }
+                    };
+
+                if PROFILE.is_stable_pair(&reserve.pair_address) {
                     output.set(
                         reserve.log_ordinal,
-                        format!("dprice:usd:native"),
+                        StoreKey::NativeUsdPrice.encode(),
                         &Vec::from(latest_usd_price.to_string()),
                     )
                 }
@@ -214,56 +338,73 @@ pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Res
                 // * dreserve:%s:%s:native (pair, token)
                 // * dreserve:%s:%s:usd (pair, token)
                 // * dreserves:%s:native (pair)  - sum of both token's reserves
+                // * droute:%s:native (tokenA)  - pairs the native price went through
                 // derived from:
-                // * price:%s:%s (tokenA, tokenB)
-                // * reserve:%s:%s (pair, tokenA)
+                // * tokens:%s:%s (tokenA, tokenB) and neighbors:%s (tokenA)
+                // * reserve:%s:%s:reserve0|1 (pair, tokenA)
                 let usd_price_valid: bool = latest_usd_price.ne(&zero_big_decimal());
 
-                let t0_derived_native_price = utils::find_native_price_per_token(
-                    &reserve.log_ordinal,
-                    pair.token0_address.as_str(),
-                    &pairs,
-                    &reserves_store,
-                );
+                let route_finder =
+                    RouteFinder::new(reserve.log_ordinal, &pairs, &reserves_store, &pair_graph);
+                let t0_route = route_finder.find(pair.token0_address.as_str());
+                let t1_route = route_finder.find(pair.token1_address.as_str());
 
-                let t1_derived_native_price = utils::find_native_price_per_token(
-                    &reserve.log_ordinal,
-                    pair.token1_address.as_str(),
-                    &pairs,
-                    &reserves_store,
-                );
-
-                let apply = |token_derived_native_price: Option<BigDecimal>,
+                let apply = |route: Option<Route>,
                              token_addr: String,
                              reserve_amount: String|
                  -> BigDecimal {
-                    if token_derived_native_price.is_none() {
-                        return zero_big_decimal();
-                    }
+                    let route = match route {
+                        None => return zero_big_decimal(),
+                        Some(route) => route,
+                    };
+                    let reserve_amount = match utils::event_decimal(&reserve.pair_address, "reserve", &reserve_amount) {
+                        None => return zero_big_decimal(),
+                        Some(reserve_amount) => reserve_amount,
+                    };
+                    let token_derived_native_price = route.native_price;
 
                     output.set(
                         reserve.log_ordinal,
-                        format!("dprice:{}:native", token_addr),
-                        &Vec::from(token_derived_native_price.clone().unwrap().to_string()),
+                        StoreKey::DerivedRoute { token: token_addr.clone() }.encode(),
+                        &Vec::from(route.pairs.join(",")),
                     );
-                    let reserve_in_native = BigDecimal::from_str(reserve_amount.as_str())
-                        .unwrap()
-                        .mul(token_derived_native_price.clone().unwrap());
                     output.set(
                         reserve.log_ordinal,
-                        format!("dreserve:{}:{}:native", reserve.pair_address, token_addr),
+                        StoreKey::DerivedPrice {
+                            token: token_addr.clone(),
+                            denomination: Denomination::Native,
+                        }
+                        .encode(),
+                        &Vec::from(token_derived_native_price.to_string()),
+                    );
+                    let reserve_in_native = reserve_amount.mul(token_derived_native_price.clone());
+                    output.set(
+                        reserve.log_ordinal,
+                        StoreKey::DerivedReserve {
+                            pair: reserve.pair_address.clone(),
+                            token: token_addr.clone(),
+                            denomination: Denomination::Native,
+                        }
+                        .encode(),
                         &Vec::from(reserve_in_native.clone().to_string()),
                     );
 
                     if usd_price_valid {
-                        let derived_usd_price = token_derived_native_price
-                            .unwrap()
-                            .mul(latest_usd_price.clone());
+                        let derived_usd_price =
+                            token_derived_native_price.mul(latest_usd_price.clone());
                         output.set_many(
                             reserve.log_ordinal,
                             &vec![
-                                format!("dprice:{}:usd", token_addr),
-                                format!("token_day:{}:dprice:{}:usd", day_id, token_addr),
+                                StoreKey::DerivedPrice {
+                                    token: token_addr.clone(),
+                                    denomination: Denomination::Usd,
+                                }
+                                .encode(),
+                                StoreKey::TokenDayDerivedPrice {
+                                    day: day_id,
+                                    token: token_addr.clone(),
+                                }
+                                .encode(),
                             ],
                             &Vec::from(derived_usd_price.to_string()),
                         );
@@ -273,17 +414,24 @@ pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Res
                         output.set_many(
                             reserve.log_ordinal,
                             &vec![
-                                format!("dreserve:{}:{}:usd", reserve.pair_address, token_addr),
-                                format!("pair_day:{}:dreserve:{}:usd", day_id, pair.token0_address),
-                                format!("pair_day:{}:dreserve:{}:usd", day_id, pair.token1_address),
-                                format!(
-                                    "pair_hour:{}:dreserve:{}:usd",
-                                    hour_id, pair.token0_address
-                                ),
-                                format!(
-                                    "pair_hour:{}:dreserve:{}:usd",
-                                    hour_id, pair.token1_address
-                                ),
+                                StoreKey::DerivedReserve {
+                                    pair: reserve.pair_address.clone(),
+                                    token: token_addr.clone(),
+                                    denomination: Denomination::Usd,
+                                }
+                                .encode(),
+                                StoreKey::PairDayDerivedReserve {
+                                    day: day_id,
+                                    pair: reserve.pair_address.clone(),
+                                    token: token_addr.clone(),
+                                }
+                                .encode(),
+                                StoreKey::PairHourDerivedReserve {
+                                    hour: hour_id,
+                                    pair: reserve.pair_address.clone(),
+                                    token: token_addr.clone(),
+                                }
+                                .encode(),
                             ],
                             &Vec::from(reserve_in_usd.to_string()),
                         );
@@ -293,12 +441,12 @@ pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Res
                 };
 
                 let reserve0_native = apply(
-                    t0_derived_native_price,
+                    t0_route,
                     pair.token0_address.clone(),
                     reserve.reserve0.clone(),
                 );
                 let reserve1_native = apply(
-                    t1_derived_native_price,
+                    t1_route,
                     pair.token1_address.clone(),
                     reserve.reserve1.clone(),
                 );
@@ -307,7 +455,7 @@ pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Res
                 if reserves_native_sum.ne(&zero_big_decimal()) {
                     output.set(
                         reserve.log_ordinal,
-                        format!("dreserves:{}:native", reserve.pair_address),
+                        StoreKey::DerivedReserves { pair: reserve.pair_address.clone() }.encode(),
                         &Vec::from(reserves_native_sum.to_string()),
                     );
                 }
@@ -316,52 +464,171 @@ pub fn store_prices(clock: substreams::pb::substreams::Clock, reserves: pcs::Res
     }
 }
 
-// pub extern "C" fn build_twap_transient_store(clock, prices_deltas) {
-//     let deltas: pcs::StoreDeltas;
-//     // TODO: flatten the deltas
-//     for delta in deltas.deltas {
-// 	if delta.key.starts_with("dprice:") {
-// 	    let key = delta.key.split(":");
-
-// 	    let token0 = delta.key.split(":")[1];
-// 	    // FLATTEN to per block
-// 	    output.set(0, "price:{:012}:{}", clock.number, token0);
-// 	    // but then, how to compute the twap? we can't read
-// 	    // the store here
-// 	}
-//     }
-
-//     let del_prev_block = (clock.number - 100) / 100.0;
-//     output.delete_prefix("price:{:010}", del_prev_block)
-// }
-
-// pub extern "C" fn build_twap_from_dprice(clock, twap_price_store, twap_prices_deltas) {
-//     let deltas: pcs::StoreDeltas;
-//     // Assumes its flattened
-//     for delta in deltas.deltas {
-// 	let key = delta.key.split(":");
-// 	let block_num = key[1];
-// 	let token = key[2];
-// 	// loop through previous keys?
-
-// 	let count = 0;
-// 	let price_sum = 0.0;
-// 	for (i = 0; i < 10 /* or whatever is parameterized for the number of blocks of twap */; i++) {
-// 	    let price, found = state::get_last(twap_price_store, format!("price:{}:{}", block_num - i, token));
-// 	    if found {
-// 		count++;
-// 		price_sum += price;
-// 	    }
-// 	}
-
-// 	// avg = price_sum / count;
-// 	output.set(0, format!("price:{}:{}", clock.number, token0), avg);
-//     }
-// }
-
-#[substreams::handlers::map]
+/// Cumulative prices of the tokens priced by `store_prices`, see
+/// `twap::offset_change`. Only the tokens whose price changed are written.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_twap_cumulative(clock: substreams::pb::substreams::Clock, prices_deltas: store::Deltas, output: store::StoreAddBigFloat) {
+    for delta in prices_deltas {
+        let change = match twap::price_change(&delta) {
+            Ok(Some(change)) => change,
+            Ok(None) => continue,
+            Err(error) => {
+                log::info!("skipping twap of {}: {}", delta.key, error);
+                continue;
+            }
+        };
+
+        for unit in twap::UNITS {
+            let time = unit.now(&clock);
+            let previous = match &change.previous {
+                Some(previous) => previous.clone(),
+                None => {
+                    output.add(delta.ordinal, unit.first_key(&change.token, change.denomination), &BigDecimal::from(time));
+                    utils::zero_big_decimal()
+                }
+            };
+            output.add(
+                delta.ordinal,
+                unit.cumulative_key(&change.token, change.denomination),
+                &twap::offset_change(time, &previous, &change.price),
+            );
+        }
+    }
+}
+
+/// When the price of each token last changed, the deltas of this store give
+/// `store_twap_observations` the previous change.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_twap_changes(clock: substreams::pb::substreams::Clock, prices_deltas: store::Deltas, output: store::StoreSet) {
+    for delta in prices_deltas {
+        let (token, denomination) = match twap::sampled_token(&delta.key) {
+            Some(sampled) if delta.operation != store_delta::Operation::Delete as i32 => sampled,
+            _ => continue,
+        };
+
+        for unit in twap::UNITS {
+            output.set(
+                delta.ordinal,
+                unit.changed_key(&token, denomination.as_str()),
+                &Vec::from(unit.now(&clock).to_string()),
+            );
+        }
+    }
+}
+
+/// Cumulative price of each token at the start of the buckets of the TWAP
+/// windows. A price holds until it changes, so the buckets started since the
+/// previous change are written when the price changes again, and only those
+/// still in a window.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_twap_observations(
+    clock: substreams::pb::substreams::Clock,
+    buckets_deltas: store::Deltas,
+    prices_deltas: store::Deltas,
+    changes: store::StoreGet,
+    cumulative: store::StoreGet,
+    output: store::StoreSet,
+) {
+    for window in twap::WINDOWS {
+        if let Some((previous, current)) = buckets::rolled(&buckets_deltas, &window.key()) {
+            for bucket in window.expired(previous, current) {
+                output.delete_prefix(0, &window.observation_prefix(bucket as u64));
+            }
+        }
+    }
+
+    // the price a token had before the block held since its previous change,
+    // only the first change of the block is needed
+    let mut changed: HashSet<String> = HashSet::new();
+    for delta in prices_deltas {
+        let change = match twap::price_change(&delta) {
+            Ok(Some(change)) => change,
+            Ok(None) => continue,
+            Err(error) => {
+                log::info!("skipping twap of {}: {}", delta.key, error);
+                continue;
+            }
+        };
+        let previous = match &change.previous {
+            Some(previous) if changed.insert(delta.key.clone()) => previous,
+            _ => continue,
+        };
+
+        for window in twap::WINDOWS {
+            let (changed_at, offset) = match twap::state_before(window.unit, &change, &changes, &cumulative) {
+                Ok(Some(state)) => state,
+                Ok(None) => continue,
+                Err(error) => {
+                    log::info!("skipping twap of {}: {}", delta.key, error);
+                    continue;
+                }
+            };
+
+            let bucket = window.bucket(&clock);
+            let since = window.bucket_of(changed_at) + 1;
+            for observed in since.max(window.start(bucket))..=bucket {
+                let observation = twap::cumulative_price(previous, window.bucket_start(observed), &offset);
+                output.set(
+                    delta.ordinal,
+                    window.observation_key(observed, &change.token, change.denomination),
+                    &Vec::from(observation.to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// TWAPs of the tokens priced in the block. The TWAP of a token is not moved by
+/// blocks it is not priced in, its cumulative price lets a reader extend it to
+/// any later time.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_twap(
+    clock: substreams::pb::substreams::Clock,
+    prices_deltas: store::Deltas,
+    cumulative: store::StoreGet,
+    observations: store::StoreGet,
+    output: store::StoreSet,
+) {
+    // a token can be priced many times in a block, only average its last price
+    let mut priced: BTreeMap<String, (twap::PriceChange, u64)> = BTreeMap::new();
+    for delta in prices_deltas {
+        match twap::price_change(&delta) {
+            Ok(Some(change)) => {
+                priced.insert(delta.key, (change, delta.ordinal));
+            }
+            Ok(None) => continue,
+            Err(error) => log::info!("skipping twap of {}: {}", delta.key, error),
+        }
+    }
+
+    for (price_key, (change, ordinal)) in priced {
+        for window in twap::WINDOWS {
+            match twap::window_average(window, &clock, &change, &cumulative, &observations) {
+                Ok(average) => output.set(ordinal, window.twap_key(&change.token, change.denomination), &Vec::from(average.to_string())),
+                Err(error) => log::info!("skipping {} twap of {}: {}", window.name, price_key, error),
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::map)]
 pub fn map_burn_swaps_events(blk: pb::eth::Block, pairs_store: store::StoreGet, prices_store: store::StoreGet, tokens_store: store::StoreGet) -> Result<pcs::Events, Error> {
-    let mut events: pcs::Events = pcs::Events { events: vec![] };
+    let mut events: pcs::Events = pcs::Events {
+        events: vec![],
+        diagnostics: vec![],
+        warnings: vec![],
+    };
+
+    let timestamp = match blk.header.as_ref().and_then(|header| header.timestamp.as_ref()) {
+        Some(timestamp) => timestamp.seconds as u64,
+        None => {
+            return Err(PcsError::Decode {
+                key: format!("block {}", blk.number),
+                reason: "no header timestamp".to_string(),
+            }
+            .into())
+        }
+    };
 
     let mut burn_count: i32 = 0;
     let mut mint_count: i32 = 0;
@@ -380,96 +647,92 @@ pub fn map_burn_swaps_events(blk: pb::eth::Block, pairs_store: store::StoreGet,
 
             let pair_addr = address_pretty(call.address.as_slice());
 
-            let pair: pcs::Pair;
-            match pairs_store.get_last(&format!("pair:{}", pair_addr)) {
-                None => continue,
-                Some(pair_bytes) => pair = proto::decode(&pair_bytes).unwrap(),
-            }
-
-            let mut pcs_events: Vec<PcsEvent> = Vec::new();
-
-            for log in call.logs {
-                pcs_events.push(event::decode_event(log));
-            }
-
-            let mut base_event = pcs::Event {
-                log_ordinal: 0,
-                pair_address: pair_addr,
-                token0: pair.token0_address.clone(),
-                token1: pair.token1_address.clone(),
-                transaction_id: trx_id.to_string(),
-                timestamp: blk
-                    .header
-                    .as_ref()
-                    .unwrap()
-                    .timestamp
-                    .as_ref()
-                    .unwrap()
-                    .seconds as u64,
-                r#type: None,
+            let pair: pcs::Pair = match utils::get_last_pair(&pairs_store, &pair_addr) {
+                Ok(pair) => pair,
+                Err(PcsError::MissingPair(_)) => continue, // not a pair
+                Err(error) => return Err(error.into()),
             };
 
-            if pcs_events.len() == 4 {
-                let ev_tr1 = match pcs_events[0].event.as_ref().unwrap() {
-                    Event::PairTransferEvent(pair_transfer_event) => Some(pair_transfer_event),
-                    _ => None,
-                };
+            let mut pair_events: Vec<Event> = Vec::new();
 
-                let ev_tr2 = match pcs_events[1].event.as_ref().unwrap() {
-                    Event::PairTransferEvent(pair_transfer_event) => Some(pair_transfer_event),
-                    _ => None,
+            for log in call.logs {
+                let log_ordinal = log.block_index as u64;
+                let reason = match event::decode_event(&log) {
+                    Ok(pcs_event) => match pcs_event.event {
+                        Some(event) => {
+                            pair_events.push(event);
+                            continue;
+                        }
+                        None => "unknown log emitted by pair".to_string(),
+                    },
+                    Err(reason) => format!("invalid log emitted by pair: {}", reason),
                 };
+                events.diagnostics.push(pcs::Diagnostic {
+                    transaction_id: trx_id.to_string(),
+                    pair_address: pair_addr.clone(),
+                    log_ordinal,
+                    reason,
+                    events: vec![],
+                });
+            }
 
-                match pcs_events[3].event.as_ref().unwrap() {
-                    Event::PairMintEvent(pair_mint_event) => {
-                        let mint_id = format!("{}-{}", trx_id, mint_count);
-                        mint_count += 1;
+            let (actions, anomalies) = sequence::classify(&pair_events);
 
-                        event::process_mint(
-                            mint_id.as_str(),
-                            &mut base_event,
-                            &prices_store,
-                            &pair,
-                            ev_tr1,
-                            ev_tr2,
-                            pair_mint_event,
-                            utils::get_last_token(&tokens_store, pair.token0_address.as_str())
-                                .decimals,
-                            utils::get_last_token(&tokens_store, pair.token1_address.as_str())
-                                .decimals,
-                        )
-                    }
-                    Event::PairBurnEvent(pair_burn_event) => {
-                        let burn_id = format!("{}-{}", trx_id, burn_count);
-                        burn_count = burn_count + 1;
+            for anomaly in anomalies {
+                log::info!(
+                    "unhandled event pattern {:?} in transaction {}: {}",
+                    anomaly.events,
+                    trx_id,
+                    anomaly.reason
+                );
+                events.diagnostics.push(pcs::Diagnostic {
+                    transaction_id: trx_id.to_string(),
+                    pair_address: pair_addr.clone(),
+                    log_ordinal: anomaly.log_ordinal,
+                    reason: anomaly.reason,
+                    events: anomaly.events,
+                });
+            }
 
-                        event::process_burn(
-                            burn_id.as_str(),
-                            &mut base_event,
-                            &prices_store,
-                            &pair,
-                            ev_tr1,
-                            ev_tr2,
-                            pair_burn_event,
-                            utils::get_last_token(&tokens_store, pair.token0_address.as_str())
-                                .decimals,
-                            utils::get_last_token(&tokens_store, pair.token1_address.as_str())
-                                .decimals,
-                        );
-                    }
-                    _ => {
-                        log::info!("Error?! Events len is 4"); // fixme: should we panic here or just continue?
-                        continue;
+            let (token0, token1) = match utils::get_last_pair_tokens(&tokens_store, &pair) {
+                Ok(tokens) => tokens,
+                Err(error) if error.is_recoverable() => {
+                    // amounts can't be scaled without the decimals, skip the pair's actions
+                    for action in actions {
+                        events.warnings.push(pcs::Warning {
+                            transaction_id: trx_id.to_string(),
+                            pair_address: pair_addr.clone(),
+                            log_ordinal: action.log_ordinal(),
+                            item: action.name().to_string(),
+                            skipped: true,
+                            reason: error.to_string(),
+                        });
                     }
+                    continue;
                 }
-            } else if pcs_events.len() == 3 {
-                let ev_tr2 = match pcs_events[0].event.as_ref().unwrap() {
-                    Event::PairTransferEvent(pair_transfer_event) => Some(pair_transfer_event),
-                    _ => None,
+                Err(error) => return Err(error.into()),
+            };
+            let token0_decimals = token0.decimals;
+            let token1_decimals = token1.decimals;
+
+            for action in actions {
+                let item = action.name();
+                let mut base_event = pcs::Event {
+                    log_ordinal: 0,
+                    pair_address: pair_addr.clone(),
+                    token0: pair.token0_address.clone(),
+                    token1: pair.token1_address.clone(),
+                    transaction_id: trx_id.to_string(),
+                    timestamp,
+                    r#type: None,
                 };
 
-                match pcs_events[2].event.as_ref().unwrap() {
-                    Event::PairMintEvent(pair_mint_event) => {
+                let result = match action {
+                    PairAction::Mint {
+                        fee_transfer,
+                        transfer,
+                        mint,
+                    } => {
                         let mint_id = format!("{}-{}", trx_id, mint_count);
                         mint_count += 1;
 
@@ -478,16 +741,18 @@ pub fn map_burn_swaps_events(blk: pb::eth::Block, pairs_store: store::StoreGet,
                             &mut base_event,
                             &prices_store,
                             &pair,
-                            None,
-                            ev_tr2,
-                            pair_mint_event,
-                            utils::get_last_token(&tokens_store, pair.token0_address.as_str())
-                                .decimals,
-                            utils::get_last_token(&tokens_store, pair.token1_address.as_str())
-                                .decimals,
+                            fee_transfer,
+                            transfer,
+                            mint,
+                            token0_decimals,
+                            token1_decimals,
                         )
                     }
-                    Event::PairBurnEvent(pair_burn_event) => {
+                    PairAction::Burn {
+                        fee_transfer,
+                        transfer,
+                        burn,
+                    } => {
                         let burn_id = format!("{}-{}", trx_id, burn_count);
                         burn_count += 1;
 
@@ -496,23 +761,14 @@ pub fn map_burn_swaps_events(blk: pb::eth::Block, pairs_store: store::StoreGet,
                             &mut base_event,
                             &prices_store,
                             &pair,
-                            None,
-                            ev_tr2,
-                            pair_burn_event,
-                            utils::get_last_token(&tokens_store, pair.token0_address.as_str())
-                                .decimals,
-                            utils::get_last_token(&tokens_store, pair.token1_address.as_str())
-                                .decimals,
-                        );
-                    }
-                    _ => {
-                        log::info!("Error?! Events len is 3"); // fixme: should we panic here or just continue?
-                        continue;
+                            fee_transfer,
+                            transfer,
+                            burn,
+                            token0_decimals,
+                            token1_decimals,
+                        )
                     }
-                }
-            } else if pcs_events.len() == 2 {
-                match pcs_events[1].event.as_ref().unwrap() {
-                    Event::PairSwapEvent(pair_swap_event) => {
+                    PairAction::Swap { swap } => {
                         let swap_id = format!("{}-{}", trx_id, swap_count);
                         swap_count += 1;
 
@@ -521,67 +777,178 @@ pub fn map_burn_swaps_events(blk: pb::eth::Block, pairs_store: store::StoreGet,
                             &mut base_event,
                             &prices_store,
                             &pair,
-                            Some(pair_swap_event),
+                            swap,
                             address_pretty(trx.from.as_slice()),
-                            utils::get_last_token(&tokens_store, &pair.token0_address).decimals,
-                            utils::get_last_token(&tokens_store, &pair.token1_address).decimals,
-                        );
+                            token0_decimals,
+                            token1_decimals,
+                        )
                     }
-                    _ => {
-                        log::info!("Error?! Events len is 2"); // fixme: should we panic here or just continue?
-                        continue;
+                };
+
+                if let Err(error) = result {
+                    if !error.is_recoverable() {
+                        return Err(error.into());
                     }
+                    // the event is still emitted, without its USD amounts
+                    events.warnings.push(pcs::Warning {
+                        transaction_id: trx_id.to_string(),
+                        pair_address: pair_addr.clone(),
+                        log_ordinal: base_event.log_ordinal,
+                        item: item.to_string(),
+                        skipped: false,
+                        reason: error.to_string(),
+                    });
                 }
-            } else if pcs_events.len() == 1 {
-                match pcs_events[0].event.as_ref().unwrap() {
-                    Event::PairTransferEvent(_) => {
-                        log::debug!("Events len 1, PairTransferEvent");
-                        continue;
-                    } // do nothing
-                    Event::PairApprovalEvent(_) => {
-                        log::debug!("Events len 1, PairApprovalEvent");
-                        continue;
-                    } // do nothing
-                    _ => panic!("unhandled event pattern, with 1 event"),
-                };
-            } else {
-                panic!("unhandled event pattern with {} events", pcs_events.len());
-            }
 
-            events.events.push(base_event);
+                events.events.push(base_event);
+            }
         }
     }
 
     Ok(events)
 }
 
-#[substreams::handlers::store]
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_lp_transfers(blk: pb::eth::Block, pairs_store: store::StoreGet) -> Result<pcs::LpTransfers, Error> {
+    let mut transfers = pcs::LpTransfers { transfers: vec![] };
+
+    for trx in blk.transaction_traces {
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            if !abi::pair::events::Transfer::match_log(&log) {
+                continue;
+            }
+
+            let pair_address = address_pretty(&log.address);
+            if pairs_store.get_last(&StoreKey::Pair { pair: pair_address.clone() }.encode()).is_none() {
+                continue; // transfer of another token
+            }
+
+            let transfer = match abi::pair::events::Transfer::decode(&log) {
+                Ok(transfer) => transfer,
+                Err(reason) => {
+                    log::info!("skipping invalid transfer of pair {}: {}", pair_address, reason);
+                    continue;
+                }
+            };
+
+            transfers.transfers.push(pcs::LpTransfer {
+                log_ordinal: log.block_index as u64,
+                transaction_id: address_pretty(&trx.hash),
+                pair_address,
+                from: address_pretty(&transfer.from),
+                to: address_pretty(&transfer.to),
+                value: utils::convert_token_to_decimal(&eth::uint_to_bytes(&transfer.value), &18).to_string(),
+            });
+        }
+    }
+
+    Ok(transfers)
+}
+
+/// Liquidity token balance of every account holding some, and the total supply
+/// of each pair: transfers from the zero address mint, transfers to it burn.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_lp_balances(transfers: pcs::LpTransfers, output: store::StoreAddBigFloat) {
+    for transfer in transfers.transfers {
+        let value = match utils::event_decimal(&transfer.pair_address, "transfer.value", &transfer.value) {
+            Some(value) => value,
+            None => continue,
+        };
+        let ordinal = transfer.log_ordinal;
+        let pair = transfer.pair_address;
+
+        if transfer.from == eth::ZERO_ADDRESS {
+            output.add(ordinal, StoreKey::LpTotalSupply { pair: pair.clone() }.encode(), &value);
+        } else {
+            output.add(
+                ordinal,
+                StoreKey::LpBalance {
+                    pair: pair.clone(),
+                    account: transfer.from,
+                }
+                .encode(),
+                &value.clone().neg(),
+            );
+        }
+
+        if transfer.to == eth::ZERO_ADDRESS {
+            output.add(ordinal, StoreKey::LpTotalSupply { pair }.encode(), &value.neg());
+        } else {
+            output.add(
+                ordinal,
+                StoreKey::LpBalance {
+                    pair,
+                    account: transfer.to,
+                }
+                .encode(),
+                &value,
+            );
+        }
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::store)]
 pub fn store_totals(
     clock: substreams::pb::substreams::Clock,
     pairs: pcs::Pairs,
     events: pcs::Events,
+    buckets_deltas: store::Deltas,
     output: store::StoreAddInt64,
 ) {
-    let timestamp_seconds = clock.timestamp.unwrap().seconds;
-    let day_id: i64 = timestamp_seconds / 86400;
+    let day_id: i64 = buckets::DAY.id(&clock);
+
+    for day in buckets::expired(&buckets_deltas, &buckets::DAY) {
+        output.delete_prefix(0, &StoreKey::global_day_prefix(day));
+    }
 
     if events.events.len() == 0 && pairs.pairs.len() == 0 {
         return;
     }
 
     for pair in pairs.pairs {
-        output.add(pair.log_ordinal, "global:pair_count".to_string(), 1);
+        output.add(
+            pair.log_ordinal,
+            StoreKey::Global {
+                field: Field::PairCount,
+            }
+            .encode(),
+            1,
+        );
     }
 
     for event in events.events {
         output.add_many(
             event.log_ordinal,
             &vec![
-                format!("token:{}:transaction_count", event.token0),
-                format!("token:{}:transaction_count", event.token1),
-                format!("pair:{}:transaction_count", event.pair_address),
-                format!("global_day:{}:transaction_count", day_id),
-                format!("global:transaction_count"),
+                StoreKey::TokenTotal {
+                    token: event.token0.clone(),
+                    field: Field::TransactionCount,
+                }
+                .encode(),
+                StoreKey::TokenTotal {
+                    token: event.token1.clone(),
+                    field: Field::TransactionCount,
+                }
+                .encode(),
+                StoreKey::PairTotal {
+                    pair: event.pair_address.clone(),
+                    field: Field::TransactionCount,
+                }
+                .encode(),
+                StoreKey::GlobalDay {
+                    day: day_id,
+                    field: Field::TransactionCount,
+                }
+                .encode(),
+                StoreKey::Global {
+                    field: Field::TransactionCount,
+                }
+                .encode(),
             ],
             1,
         );
@@ -594,7 +961,11 @@ pub fn store_totals(
 
                 output.add_many(
                     event.log_ordinal,
-                    &vec![format!("pair:{}:swap_count", event.pair_address)],
+                    &vec![StoreKey::PairTotal {
+                        pair: event.pair_address.clone(),
+                        field: Field::SwapCount,
+                    }
+                    .encode()],
                     1,
                 );
 
@@ -603,155 +974,395 @@ pub fn store_totals(
             }
             Type::Burn(_) => output.add(
                 event.log_ordinal,
-                format!("pair:{}:burn_count", event.pair_address),
+                StoreKey::PairTotal {
+                    pair: event.pair_address.clone(),
+                    field: Field::BurnCount,
+                }
+                .encode(),
                 1,
             ),
             Type::Mint(_) => output.add(
                 event.log_ordinal,
-                format!("pair:{}:mint_count", event.pair_address),
+                StoreKey::PairTotal {
+                    pair: event.pair_address.clone(),
+                    field: Field::MintCount,
+                }
+                .encode(),
                 1,
             ),
         }
     }
 }
 
-#[substreams::handlers::store]
+#[cfg_attr(not(test), substreams::handlers::store)]
 pub fn store_volumes(
     clock: substreams::pb::substreams::Clock,
     events: pcs::Events,
+    buckets_deltas: store::Deltas,
     output: store::StoreAddBigFloat,
 ) {
-    let timestamp_seconds = clock.timestamp.unwrap().seconds;
-    let day_id: i64 = timestamp_seconds / 86400;
-    let hour_id: i64 = timestamp_seconds / 3600;
+    let day_id: i64 = buckets::DAY.id(&clock);
+    let hour_id: i64 = buckets::HOUR.id(&clock);
 
-    if events.events.len() == 0 {
-        return;
+    for day in buckets::expired(&buckets_deltas, &buckets::DAY) {
+        output.delete_prefix(0, &StoreKey::pair_day_prefix(day));
+        output.delete_prefix(0, &StoreKey::token_day_prefix(day));
+        output.delete_prefix(0, &StoreKey::global_day_prefix(day));
+    }
+    for hour in buckets::expired(&buckets_deltas, &buckets::HOUR) {
+        output.delete_prefix(0, &StoreKey::pair_hour_prefix(hour));
     }
-
-    output.delete_prefix(0, &format!("pair_day:{}:", day_id - 1));
-    output.delete_prefix(0, &format!("token_day:{}:", day_id - 1));
-    output.delete_prefix(0, &format!("pair_hour:{}:", hour_id - 1));
-    output.delete_prefix(0, &format!("global_day:{}", day_id - 1));
 
     for event in events.events {
         if event.r#type.is_some() {
             match event.r#type.unwrap() {
                 Type::Mint(mint) => {
-                    let amount_usd = BigDecimal::from_str(mint.amount_usd.as_str()).unwrap();
+                    let amount_usd = match BigDecimal::from_str(mint.amount_usd.as_str()) {
+                        Ok(amount_usd) => amount_usd,
+                        Err(_) => continue, // not priced, see the events' warnings
+                    };
                     if amount_usd.eq(&zero_big_decimal()) {
                         continue;
                     }
                     output.add(
                         event.log_ordinal,
-                        format!("global:liquidity_usd"),
+                        StoreKey::Global {
+                            field: Field::LiquidityUsd,
+                        }
+                        .encode(),
                         &amount_usd,
                     );
 
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            format!("token:{}:liquidity", mint.to),
-                            format!("pair:{}:total_supply", event.pair_address),
-                        ],
-                        &BigDecimal::from_str(mint.liquidity.as_str()).unwrap(),
-                    );
+                    if let Some(liquidity) =
+                        utils::event_decimal(&event.pair_address, "mint.liquidity", &mint.liquidity)
+                    {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::PairTotal {
+                                pair: event.pair_address.clone(),
+                                field: Field::TotalSupply,
+                            }
+                            .encode(),
+                            &liquidity,
+                        );
+                    }
+
+                    if let Some(protocol_fees_usd) =
+                        fees::protocol_fees_usd(&mint.fee_liquidity, &mint.liquidity, &amount_usd)
+                    {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::PairTotal {
+                                    pair: event.pair_address.clone(),
+                                    field: Field::ProtocolFeesUsd,
+                                }
+                                .encode(),
+                                StoreKey::PairDay {
+                                    day: day_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::ProtocolFeesUsd,
+                                }
+                                .encode(),
+                            ],
+                            &protocol_fees_usd,
+                        );
+                    }
                 }
                 Type::Burn(burn) => {
-                    let amount_usd = BigDecimal::from_str(burn.amount_usd.as_str()).unwrap();
+                    let amount_usd = match BigDecimal::from_str(burn.amount_usd.as_str()) {
+                        Ok(amount_usd) => amount_usd,
+                        Err(_) => continue, // not priced, see the events' warnings
+                    };
                     if amount_usd.eq(&zero_big_decimal()) {
                         continue;
                     }
                     output.add(
                         event.log_ordinal,
-                        format!("global:liquidity_usd"),
-                        &amount_usd.neg(),
+                        StoreKey::Global {
+                            field: Field::LiquidityUsd,
+                        }
+                        .encode(),
+                        &amount_usd.clone().neg(),
                     );
 
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            format!("token:{}:liquidity", burn.to),
-                            format!("pair:{}:total_supply", event.pair_address),
-                        ],
-                        &BigDecimal::from_str(burn.liquidity.as_str()).unwrap().neg(),
-                    );
+                    if let Some(liquidity) =
+                        utils::event_decimal(&event.pair_address, "burn.liquidity", &burn.liquidity)
+                    {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::PairTotal {
+                                pair: event.pair_address.clone(),
+                                field: Field::TotalSupply,
+                            }
+                            .encode(),
+                            &liquidity.neg(),
+                        );
+                    }
+
+                    if let Some(protocol_fees_usd) =
+                        fees::protocol_fees_usd(&burn.fee_liquidity, &burn.liquidity, &amount_usd)
+                    {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::PairTotal {
+                                    pair: event.pair_address.clone(),
+                                    field: Field::ProtocolFeesUsd,
+                                }
+                                .encode(),
+                                StoreKey::PairDay {
+                                    day: day_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::ProtocolFeesUsd,
+                                }
+                                .encode(),
+                            ],
+                            &protocol_fees_usd,
+                        );
+                    }
                 }
                 Type::Swap(swap) => {
                     if swap.amount_usd.is_empty() {
                         continue;
                     }
-                    let amount_usd = BigDecimal::from_str(swap.amount_usd.as_str()).unwrap();
-                    if amount_usd.eq(&zero_big_decimal()) {
-                        continue;
+
+                    if let Some(amount_0_total) =
+                        utils::compute_amount_total(&event.pair_address, &swap.amount0_out, &swap.amount0_in)
+                    {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::PairTotal {
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token0,
+                                }
+                                .encode(),
+                                StoreKey::PairDay {
+                                    day: day_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token0,
+                                }
+                                .encode(),
+                                StoreKey::PairHour {
+                                    hour: hour_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token0,
+                                }
+                                .encode(),
+                            ],
+                            &amount_0_total,
+                        );
                     }
-                    let amount_native = BigDecimal::from_str(swap.amount_native.as_str()).unwrap();
 
-                    let amount_0_total: BigDecimal =
-                        utils::compute_amount_total(swap.amount0_out, swap.amount0_in);
-                    let amount_1_total: BigDecimal =
-                        utils::compute_amount_total(swap.amount1_out, swap.amount1_in);
+                    if let Some(amount_1_total) =
+                        utils::compute_amount_total(&event.pair_address, &swap.amount1_out, &swap.amount1_in)
+                    {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::PairTotal {
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token1,
+                                }
+                                .encode(),
+                                StoreKey::PairDay {
+                                    day: day_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token1,
+                                }
+                                .encode(),
+                                StoreKey::PairHour {
+                                    hour: hour_id,
+                                    pair: event.pair_address.clone(),
+                                    field: Field::Token1,
+                                }
+                                .encode(),
+                            ],
+                            &amount_1_total,
+                        );
+                    }
 
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            format!("pair:{}:usd", event.pair_address),
-                            format!("pair_day:{}:{}:usd", day_id, event.pair_address),
-                            format!("pair_hour:{}:{}:usd", hour_id, event.pair_address),
-                            format!("token_day:{}:{}:usd", day_id, event.token0),
-                            format!("token_day:{}:{}:usd", day_id, event.token1),
-                            format!("global:usd"),
-                            format!("global_day:{}:usd", day_id),
-                        ],
-                        &amount_usd,
-                    );
+                    if let Some(trade_volume) = utils::event_decimal(
+                        &event.pair_address,
+                        "swap.trade_volume0",
+                        &swap.trade_volume0,
+                    ) {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::TokenTotal {
+                                token: event.token0.clone(),
+                                field: Field::Trade,
+                            }
+                            .encode(),
+                            &trade_volume,
+                        );
+                    }
+                    if let Some(trade_volume) = utils::event_decimal(
+                        &event.pair_address,
+                        "swap.trade_volume1",
+                        &swap.trade_volume1,
+                    ) {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::TokenTotal {
+                                token: event.token1.clone(),
+                                field: Field::Trade,
+                            }
+                            .encode(),
+                            &trade_volume,
+                        );
+                    }
 
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![format!("global:native"), format!("global_day:{}:native", day_id)],
-                        &amount_native,
-                    );
+                    if let Some(untracked_volume_usd) = utils::event_decimal(
+                        &event.pair_address,
+                        "swap.untracked_volume_usd",
+                        &swap.untracked_volume_usd,
+                    ) {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::PairTotal {
+                                    pair: event.pair_address.clone(),
+                                    field: Field::UntrackedUsd,
+                                }
+                                .encode(),
+                                StoreKey::TokenTotal {
+                                    token: event.token0.clone(),
+                                    field: Field::UntrackedUsd,
+                                }
+                                .encode(),
+                                StoreKey::TokenTotal {
+                                    token: event.token1.clone(),
+                                    field: Field::UntrackedUsd,
+                                }
+                                .encode(),
+                                StoreKey::Global {
+                                    field: Field::UntrackedUsd,
+                                }
+                                .encode(),
+                            ],
+                            &untracked_volume_usd,
+                        );
+                    }
+
+                    // neither token is whitelisted, only the untracked volume counts
+                    let amount_usd =
+                        match utils::event_decimal(&event.pair_address, "swap.amount_usd", &swap.amount_usd) {
+                            Some(amount_usd) if amount_usd.ne(&zero_big_decimal()) => amount_usd,
+                            _ => continue,
+                        };
 
                     output.add_many(
                         event.log_ordinal,
                         &vec![
-                            format!("pair:{}:token0", event.pair_address),
-                            format!("pair_day:{}:{}:token0", day_id, event.pair_address),
-                            format!("pair_hour:{}:{}:token0", day_id, event.pair_address),
+                            StoreKey::PairTotal {
+                                pair: event.pair_address.clone(),
+                                field: Field::Usd,
+                            }
+                            .encode(),
+                            StoreKey::PairDay {
+                                day: day_id,
+                                pair: event.pair_address.clone(),
+                                field: Field::Usd,
+                            }
+                            .encode(),
+                            StoreKey::PairHour {
+                                hour: hour_id,
+                                pair: event.pair_address.clone(),
+                                field: Field::Usd,
+                            }
+                            .encode(),
+                            StoreKey::TokenDay {
+                                day: day_id,
+                                token: event.token0.clone(),
+                                field: Field::Usd,
+                            }
+                            .encode(),
+                            StoreKey::TokenDay {
+                                day: day_id,
+                                token: event.token1.clone(),
+                                field: Field::Usd,
+                            }
+                            .encode(),
+                            StoreKey::Global { field: Field::Usd }.encode(),
+                            StoreKey::GlobalDay {
+                                day: day_id,
+                                field: Field::Usd,
+                            }
+                            .encode(),
                         ],
-                        &amount_0_total,
+                        &amount_usd,
                     );
 
                     output.add_many(
                         event.log_ordinal,
                         &vec![
-                            format!("pair:{}:token1", event.pair_address),
-                            format!("pair_day:{}:{}:token1", day_id, event.pair_address),
-                            format!("pair_hour:{}:{}:token1", day_id, event.pair_address),
+                            StoreKey::PairTotal {
+                                pair: event.pair_address.clone(),
+                                field: Field::FeesUsd,
+                            }
+                            .encode(),
+                            StoreKey::PairDay {
+                                day: day_id,
+                                pair: event.pair_address.clone(),
+                                field: Field::FeesUsd,
+                            }
+                            .encode(),
                         ],
-                        &amount_1_total,
+                        &fees::swap_fees_usd(&amount_usd),
                     );
 
-                    output.add(
-                        event.log_ordinal,
-                        format!("token:{}:trade", event.token0),
-                        &BigDecimal::from_str(swap.trade_volume0.as_str()).unwrap(),
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        format!("token:{}:trade", event.token1),
-                        &BigDecimal::from_str(swap.trade_volume1.as_str()).unwrap(),
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        format!("token:{}:trade_usd", event.token0),
-                        &BigDecimal::from_str(swap.trade_volume_usd0.as_str()).unwrap(),
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        format!("token:{}:trade_usd", event.token1),
-                        &BigDecimal::from_str(swap.trade_volume_usd1.as_str()).unwrap(),
-                    );
+                    if let Some(amount_native) =
+                        utils::event_decimal(&event.pair_address, "swap.amount_native", &swap.amount_native)
+                    {
+                        output.add_many(
+                            event.log_ordinal,
+                            &vec![
+                                StoreKey::Global {
+                                    field: Field::Native,
+                                }
+                                .encode(),
+                                StoreKey::GlobalDay {
+                                    day: day_id,
+                                    field: Field::Native,
+                                }
+                                .encode(),
+                            ],
+                            &amount_native,
+                        );
+                    }
+
+                    if let Some(trade_volume_usd) = utils::event_decimal(
+                        &event.pair_address,
+                        "swap.trade_volume_usd0",
+                        &swap.trade_volume_usd0,
+                    ) {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::TokenTotal {
+                                token: event.token0.clone(),
+                                field: Field::TradeUsd,
+                            }
+                            .encode(),
+                            &trade_volume_usd,
+                        );
+                    }
+                    if let Some(trade_volume_usd) = utils::event_decimal(
+                        &event.pair_address,
+                        "swap.trade_volume_usd1",
+                        &swap.trade_volume_usd1,
+                    ) {
+                        output.add(
+                            event.log_ordinal,
+                            StoreKey::TokenTotal {
+                                token: event.token1.clone(),
+                                field: Field::TradeUsd,
+                            }
+                            .encode(),
+                            &trade_volume_usd,
+                        );
+                    }
 
                     //todo: token[0,1]Day.dailyVolumeToken, tokenDay[0,1].dailyVolumenative ? what about these
                 }
@@ -761,95 +1372,174 @@ pub fn store_volumes(
 }
 
 // todo: create pcs-token proto
-#[substreams::handlers::store]
+#[cfg_attr(not(test), substreams::handlers::store)]
 pub fn store_pcs_tokens(
     pairs: pcs::Pairs,
     tokens: store::StoreGet,
     output: store::StoreSetIfNotExists,
 ) {
-    let mut token0_retry: bool = false;
-    let mut token0: Token = Token {
-        address: "".to_string(),
-        name: "".to_string(),
-        symbol: "".to_string(),
-        decimals: 0,
-    };
-    let mut token1_retry: bool = false;
-    let mut token1: Token = Token {
-        address: "".to_string(),
-        name: "".to_string(),
-        symbol: "".to_string(),
-        decimals: 0,
-    };
-
     for pair in pairs.pairs {
-        let token0_option_from_store: Option<Vec<u8>> =
-            tokens.get_last(&format!("token:{}", pair.token0_address));
-        if token0_option_from_store.is_none() {
-            log::info!(
-                "token {} is not in the store, retrying rpc calls",
-                pair.token0_address,
-            );
-            let token0_res = rpc::retry_rpc_calls(&pair.token0_address);
-            if token0_res.is_err() {
-                continue; // skip to next execution, we don't have a valid token
-            }
-
-            token0 = token0_res.unwrap();
+        for token_address in [&pair.token0_address, &pair.token1_address] {
+            let token: Token = match utils::get_last_token(&tokens, token_address) {
+                Ok(token) => token,
+                Err(PcsError::MissingToken(_)) => {
+                    log::info!("token {} is not in the store, retrying rpc calls", token_address);
+                    match rpc::retry_rpc_calls(token_address) {
+                        Ok(token) => {
+                            log::info!("successfully found token {} after rpc calls", token_address);
+                            token
+                        }
+                        // skip to next execution, we don't have a valid token
+                        Err(error) => {
+                            log::info!("skipping token {}: {}", token_address, error);
+                            continue;
+                        }
+                    }
+                }
+                Err(error) => {
+                    log::info!("skipping token {}: {}", token_address, error);
+                    continue;
+                }
+            };
 
-            token0_retry = true;
-            log::info!(
-                "successfully found token {} after rpc calls",
-                pair.token0_address
+            output.set_if_not_exists(
+                pair.log_ordinal,
+                StoreKey::Token {
+                    token: token.address.clone(),
+                }
+                .encode(),
+                &proto::encode(&token).unwrap(),
             );
         }
+    }
+}
 
-        if !token0_retry {
-            // didn't need to retry as we have the token in the store
-            token0 = proto::decode(&token0_option_from_store.unwrap()).unwrap();
-        }
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_candle_open(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, prices_deltas: store::Deltas, buckets_deltas: store::Deltas, output: store::StoreSetIfNotExists) {
+    candles::delete_expired_buckets(&buckets_deltas, |prefix| output.delete_prefix(0, prefix));
 
-        output.set_if_not_exists(
-            pair.log_ordinal,
-            format!("token:{}", token0.address),
-            &proto::encode(&token0).unwrap(),
-        );
+    for (ordinal, key, price) in candles::price_samples(&clock, &reserves, &prices_deltas) {
+        output.set_if_not_exists(ordinal, key, &Vec::from(price.to_string()));
+    }
+}
 
-        let token1_option_from_store: Option<Vec<u8>> =
-            tokens.get_last(&format!("token:{}", pair.token1_address));
-        if token1_option_from_store.is_none() {
-            log::info!(
-                "token {} is not in the store, retrying rpc calls",
-                pair.token1_address
-            );
-            let token1_res = rpc::retry_rpc_calls(&pair.token1_address);
-            if token1_res.is_err() {
-                continue; // skip to next execution, we don't have a valid token
-            }
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_candle_high(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, prices_deltas: store::Deltas, buckets_deltas: store::Deltas, output: store::StoreMaxBigFloat) {
+    candles::delete_expired_buckets(&buckets_deltas, |prefix| output.delete_prefix(0, prefix));
 
-            token1 = token1_res.unwrap();
+    for (ordinal, key, price) in candles::price_samples(&clock, &reserves, &prices_deltas) {
+        output.max(ordinal, key, &price);
+    }
+}
 
-            token1_retry = true;
-            log::info!(
-                "successfully found token {} after rpc calls",
-                pair.token1_address
-            );
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_candle_low(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, prices_deltas: store::Deltas, buckets_deltas: store::Deltas, output: store::StoreMinBigFloat) {
+    candles::delete_expired_buckets(&buckets_deltas, |prefix| output.delete_prefix(0, prefix));
+
+    for (ordinal, key, price) in candles::price_samples(&clock, &reserves, &prices_deltas) {
+        output.min(ordinal, key, &price);
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_candle_close(clock: substreams::pb::substreams::Clock, reserves: pcs::Reserves, prices_deltas: store::Deltas, buckets_deltas: store::Deltas, output: store::StoreSet) {
+    candles::delete_expired_buckets(&buckets_deltas, |prefix| output.delete_prefix(0, prefix));
+
+    for (ordinal, key, price) in candles::price_samples(&clock, &reserves, &prices_deltas) {
+        output.set(ordinal, key, &Vec::from(price.to_string()));
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_candle_volume(clock: substreams::pb::substreams::Clock, events: pcs::Events, buckets_deltas: store::Deltas, output: store::StoreAddBigFloat) {
+    candles::delete_expired_buckets(&buckets_deltas, |prefix| output.delete_prefix(0, prefix));
+
+    for (ordinal, key, volume) in candles::volume_samples(&clock, &events) {
+        output.add(ordinal, key, &volume);
+    }
+}
+
+// one input per store of `substreams.yaml`
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_candles(
+    open_deltas: store::Deltas,
+    close_deltas: store::Deltas,
+    volume_deltas: store::Deltas,
+    open_store: store::StoreGet,
+    high_store: store::StoreGet,
+    low_store: store::StoreGet,
+    close_store: store::StoreGet,
+    volume_store: store::StoreGet,
+) -> Result<pcs::Candles, Error> {
+    let mut pcs_candles = pcs::Candles {
+        pair_candles: vec![],
+        token_candles: vec![],
+    };
+
+    let opened: HashSet<String> = open_deltas
+        .into_iter()
+        .filter(|delta| delta.operation == store_delta::Operation::Create as i32)
+        .map(|delta| delta.key)
+        .collect();
+
+    // every candle with a new price or volume in this block, at its last ordinal
+    let mut updated: BTreeMap<String, u64> = BTreeMap::new();
+    for delta in close_deltas.iter().chain(volume_deltas.iter()) {
+        if delta.operation == store_delta::Operation::Delete as i32 {
+            continue;
         }
 
-        if !token1_retry {
-            // didn't need to retry as we have the token in the store
-            token1 = proto::decode(&token1_option_from_store.unwrap()).unwrap();
+        if let Some((interval, bucket, kind, address)) = candles::parse_key(&delta.key) {
+            let ordinal = updated
+                .entry(interval.key(bucket, kind, address))
+                .or_insert(0);
+            *ordinal = delta.ordinal.max(*ordinal);
         }
+    }
 
-        output.set_if_not_exists(
-            pair.log_ordinal,
-            format!("token:{}", token1.address),
-            &proto::encode(&token1).unwrap(),
-        );
+    let get_string = |store: &store::StoreGet, key: &String| -> Option<String> {
+        store
+            .get_last(key)
+            .map(|value| String::from_utf8_lossy(value.as_slice()).to_string())
+    };
+
+    for (key, log_ordinal) in updated {
+        let (interval, bucket, kind, address) = candles::parse_key(&key).unwrap();
+
+        // a candle only exists once a price was seen in its bucket
+        let close = match get_string(&close_store, &key) {
+            None => continue,
+            Some(close) => close,
+        };
+
+        let candle = pcs::Candle {
+            address: address.to_string(),
+            interval: interval.name.to_string(),
+            timestamp: (bucket * interval.bucket.seconds) as u64,
+            open: get_string(&open_store, &key).unwrap_or_else(|| close.clone()),
+            high: get_string(&high_store, &key).unwrap_or_else(|| close.clone()),
+            low: get_string(&low_store, &key).unwrap_or_else(|| close.clone()),
+            close,
+            volume: get_string(&volume_store, &format!("{}:volume", key))
+                .unwrap_or_else(|| "0".to_string()),
+            volume_usd: get_string(&volume_store, &format!("{}:volume_usd", key))
+                .unwrap_or_else(|| "0".to_string()),
+            log_ordinal,
+            opened: opened.contains(&key),
+        };
+
+        match kind {
+            candles::PAIR => pcs_candles.pair_candles.push(candle),
+            candles::TOKEN => pcs_candles.token_candles.push(candle),
+            _ => continue,
+        }
     }
+
+    Ok(pcs_candles)
 }
 
-#[substreams::handlers::map]
+#[cfg_attr(not(test), substreams::handlers::map)]
 pub fn db_out(
     block: substreams::pb::substreams::Clock,
     pcs_tokens_deltas: store::Deltas,
@@ -858,10 +1548,15 @@ pub fn db_out(
     volumes_deltas: store::Deltas,
     reserves_deltas: store::Deltas,
     events: pcs::Events,
+    candles: pcs::Candles,
     pcs_tokens_store: store::StoreGet,
+    pairs_store: store::StoreGet,
+    prices_store: store::StoreGet,
+    volumes_store: store::StoreGet,
+    lp_balances_deltas: store::Deltas,
+    lp_balances_store: store::StoreGet,
+    reserves: pcs::Reserves,
 ) -> Result<DatabaseChanges, Error> {
-    substreams::register_panic_hook();
-
     log::info!(
         "map_to_database: pairs deltas:{} {}",
         pcs_tokens_deltas.len(),
@@ -876,8 +1571,15 @@ pub fn db_out(
         volumes_deltas,
         reserves_deltas,
         events,
+        candles,
         &pcs_tokens_store,
-    );
+        &pairs_store,
+        &prices_store,
+        &volumes_store,
+        lp_balances_deltas,
+        &lp_balances_store,
+        reserves,
+    )?;
 
     return Ok(changes);
 }
@@ -1,11 +1,9 @@
-#[path = "./dfuse.ethereum.r#type.v1.rs"]
-pub mod eth;
+pub use substreams_ethereum::pb::eth::v2 as eth;
 
 #[path = "./pcs.types.v1.rs"]
 pub mod pcs;
 
-#[path = "./sf.substreams.tokens.v1.rs"]
-pub mod tokens;
+pub use token_common::pb::tokens;
 
 #[path = "./pcs.database.v1.rs"]
 pub mod database;
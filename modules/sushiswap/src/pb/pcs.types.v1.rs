@@ -45,11 +45,112 @@ pub struct Reserve {
     pub token0_price: ::prost::alloc::string::String,
     #[prost(string, tag="6")]
     pub token1_price: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="8")]
+    pub block_num: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LpTransfers {
+    #[prost(message, repeated, tag="1")]
+    pub transfers: ::prost::alloc::vec::Vec<LpTransfer>,
+}
+/// Transfer of a pair's liquidity token, mints are transfers from the zero
+/// address and burns transfers to it
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LpTransfer {
+    #[prost(uint64, tag="1")]
+    pub log_ordinal: u64,
+    #[prost(string, tag="2")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub pair_address: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub to: ::prost::alloc::string::String,
+    /// in liquidity tokens (18 decimals)
+    #[prost(string, tag="6")]
+    pub value: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Events {
     #[prost(message, repeated, tag="1")]
     pub events: ::prost::alloc::vec::Vec<Event>,
+    #[prost(message, repeated, tag="2")]
+    pub diagnostics: ::prost::alloc::vec::Vec<Diagnostic>,
+    #[prost(message, repeated, tag="3")]
+    pub warnings: ::prost::alloc::vec::Vec<Warning>,
+}
+/// Logs emitted by a pair that do not fit a known Mint, Burn or Swap sequence
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Diagnostic {
+    #[prost(string, tag="1")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pair_address: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub log_ordinal: u64,
+    #[prost(string, tag="4")]
+    pub reason: ::prost::alloc::string::String,
+    /// event names of the unmatched sequence, in log order
+    #[prost(string, repeated, tag="5")]
+    pub events: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Mint, Burn or Swap emitted without some of its data, or skipped, because a
+/// token or a price was not in the stores yet
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Warning {
+    #[prost(string, tag="1")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pair_address: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub log_ordinal: u64,
+    /// mint, burn or swap
+    #[prost(string, tag="4")]
+    pub item: ::prost::alloc::string::String,
+    #[prost(bool, tag="5")]
+    pub skipped: bool,
+    #[prost(string, tag="6")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Candles {
+    #[prost(message, repeated, tag="1")]
+    pub pair_candles: ::prost::alloc::vec::Vec<Candle>,
+    #[prost(message, repeated, tag="2")]
+    pub token_candles: ::prost::alloc::vec::Vec<Candle>,
+}
+/// Pair candles follow the pair's token0_price and count the volume in token0,
+/// token candles follow the token's USD price and count the volume in the token
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Candle {
+    #[prost(string, tag="1")]
+    pub address: ::prost::alloc::string::String,
+    /// minute, hour or day
+    #[prost(string, tag="2")]
+    pub interval: ::prost::alloc::string::String,
+    /// start of the bucket, in seconds
+    #[prost(uint64, tag="3")]
+    pub timestamp: u64,
+    #[prost(string, tag="4")]
+    pub open: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub high: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub low: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub close: ::prost::alloc::string::String,
+    #[prost(string, tag="8")]
+    pub volume: ::prost::alloc::string::String,
+    #[prost(string, tag="9")]
+    pub volume_usd: ::prost::alloc::string::String,
+    #[prost(uint64, tag="10")]
+    pub log_ordinal: u64,
+    /// true in the block opening the candle
+    #[prost(bool, tag="11")]
+    pub opened: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Event {
@@ -110,10 +211,10 @@ pub struct Swap {
     pub trade_volume_usd0: ::prost::alloc::string::String,
     #[prost(string, tag="14")]
     pub trade_volume_usd1: ::prost::alloc::string::String,
-    // dropped for now...
-    //string untracked_volume_usd0 = 15;
-    //string untracked_volume_usd1 = 16;
-
+    /// average USD value of both sides, whitelisted or not; amount_usd only
+    /// counts the whitelisted sides
+    #[prost(string, tag="15")]
+    pub untracked_volume_usd: ::prost::alloc::string::String,
     #[prost(string, tag="17")]
     pub volume_usd: ::prost::alloc::string::String,
     #[prost(string, tag="18")]
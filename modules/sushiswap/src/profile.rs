@@ -0,0 +1,139 @@
+use substreams::hex;
+
+#[cfg(all(feature = "polygon", feature = "ethereum"))]
+compile_error!("only one chain profile feature can be enabled at a time");
+
+#[cfg(not(any(feature = "polygon", feature = "ethereum")))]
+compile_error!("a chain profile feature must be enabled, one of: polygon, ethereum");
+
+/// Everything that differs between the UniswapV2 forks we index: where the
+/// factory lives, which token is the chain's wrapped native currency and which
+/// pairs/tokens are trusted for pricing.
+pub struct ChainProfile {
+    pub name: &'static str,
+    pub factory_address: [u8; 20],
+    pub native_address: &'static str,
+    pub stable_pairs: &'static [StablePair],
+    pub whitelist_tokens: &'static [&'static str],
+    /// Liquidity, in native, every pair of a multi-hop pricing route must hold.
+    pub min_liquidity_native: &'static str,
+    pub max_route_hops: usize,
+    /// Share of the amount swapped in paid as a fee, `0.003` for 0.3%.
+    pub swap_fee: &'static str,
+    pub bucket_retention: BucketRetention,
+}
+
+/// Number of day, hour and minute buckets the stores keep, the current one
+/// included (see `buckets`).
+pub struct BucketRetention {
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+}
+
+/// A pair between the native wrapped token and a USD stablecoin, used to derive
+/// the USD price of the native token.
+pub struct StablePair {
+    pub address: &'static str,
+    pub stablecoin_address: &'static str,
+}
+
+impl ChainProfile {
+    pub fn is_factory(&self, address: &[u8]) -> bool {
+        address == &self.factory_address[..]
+    }
+
+    pub fn factory_address_hex(&self) -> String {
+        hex::encode(self.factory_address)
+    }
+
+    pub fn is_whitelisted(&self, token_address: &str) -> bool {
+        self.whitelist_tokens.contains(&token_address)
+    }
+
+    pub fn is_stable_pair(&self, pair_address: &str) -> bool {
+        self.stable_pairs
+            .iter()
+            .any(|stable_pair| stable_pair.address == pair_address)
+    }
+}
+
+#[cfg(feature = "polygon")]
+pub const PROFILE: ChainProfile = ChainProfile {
+    name: "polygon",
+    factory_address: hex!("c35dadb65012ec5796536bd9864ed8773abc74c4"),
+    // WMATIC
+    native_address: "0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270",
+    stable_pairs: &[
+        // WMATIC/USDC
+        StablePair {
+            address: "0xcd353f79d9fade311fc3119b841e1f456b54e858",
+            stablecoin_address: "0x2791bca1f2de4661ed88a30c99a7a9449aa84174",
+        },
+        // WMATIC/USDT
+        StablePair {
+            address: "0x55ff76bffc3cdd9d5fdbbc2ece4528ecce45047e",
+            stablecoin_address: "0xc2132d05d31c914a87c6611c10748aeb04b58e8f",
+        },
+    ],
+    whitelist_tokens: &[
+        "0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270", // WMATIC
+        "0x0b3f868e0be5597d5db7feb59e1cadbb0fdda50a", // SUSHI
+        "0x7ceb23fd6bc0add59e62ac25578270cff1b9f619", // WETH
+        "0x1bfd67037b42cf73acf2047067bd4f2c47d9bfd6", // WBTC
+        "0x2791bca1f2de4661ed88a30c99a7a9449aa84174", // USDC
+        "0xc2132d05d31c914a87c6611c10748aeb04b58e8f", // USDT
+        "0x8f3cf7ad23cd3cadbd9735aff958023239c6a063", // DAI
+        "0xd6df932a45c0f255f85145f286ea0b292b21c90b", // AAVE
+        "0x45c32fa6df82ead1e2ef74d17b76547eddfaff89", // FRAX
+        "0x2f800db0fdb5223b3c3f354886d907a671414a7f", // BCT
+        "0x34d4ab47bee066f361fa52d792e69ac7bd05ee23", // AURUM
+        "0xe8377a076adabb3f9838afb77bee96eac101ffb1", // MSU
+        "0x61daecab65ee2a1d5b6032df030f3faa3d116aa7", // DMAGIC
+        "0xd3f07ea86ddf7baebefd49731d7bbd207fedc53b", // NDEFI
+    ],
+    min_liquidity_native: "5",
+    max_route_hops: 3,
+    swap_fee: "0.003",
+    bucket_retention: BucketRetention {
+        day: 1,
+        hour: 1,
+        minute: 1,
+    },
+};
+
+#[cfg(feature = "ethereum")]
+pub const PROFILE: ChainProfile = ChainProfile {
+    name: "ethereum",
+    factory_address: hex!("c0aee478e3658e2610c5f7a4a2e1777ce9e4f2ac"),
+    // WETH
+    native_address: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+    stable_pairs: &[
+        // USDC/WETH
+        StablePair {
+            address: "0x397ff1542f962076d0bfe58ea045ffa2d347aca0",
+            stablecoin_address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+        },
+        // WETH/USDT
+        StablePair {
+            address: "0x06da0fd433c1a5d7a4faa01111c044910a184553",
+            stablecoin_address: "0xdac17f958d2ee523a2206206994597c13d831ec7",
+        },
+    ],
+    whitelist_tokens: &[
+        "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", // WETH
+        "0x6b3595068778dd592e39a122f4f5a5cf09c90fe2", // SUSHI
+        "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599", // WBTC
+        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", // USDC
+        "0xdac17f958d2ee523a2206206994597c13d831ec7", // USDT
+        "0x6b175474e89094c44da98b954eedeac495271d0f", // DAI
+    ],
+    min_liquidity_native: "5",
+    max_route_hops: 3,
+    swap_fee: "0.003",
+    bucket_retention: BucketRetention {
+        day: 1,
+        hour: 1,
+        minute: 1,
+    },
+};
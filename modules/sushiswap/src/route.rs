@@ -0,0 +1,440 @@
+use std::ops::{Div, Mul};
+use std::str;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use substreams::proto;
+
+use crate::keys::{Field, StoreKey};
+use crate::pb::pcs;
+use crate::profile::PROFILE;
+use crate::store;
+use crate::utils::{one_big_decimal, zero_big_decimal};
+
+/// Neighbors taken from `store_pair_graph` for a token, on top of the native
+/// token and the whitelisted ones which are looked up directly. This keeps the
+/// search bounded for tokens paired with thousands of others, the deepest
+/// pairs are the ones kept.
+const MAX_NEIGHBORS: usize = 8;
+
+/// How a token got its native price: the pairs traversed from the token to the
+/// native token, and the liquidity of the shallowest of them, in native.
+pub struct Route {
+    pub native_price: BigDecimal,
+    pub liquidity_native: BigDecimal,
+    pub pairs: Vec<String>,
+}
+
+struct Hop {
+    pair_address: String,
+    // amount of the next token one unit of the current token is worth
+    price: BigDecimal,
+    token_reserve: BigDecimal,
+    next_token_reserve: BigDecimal,
+}
+
+/// Depth-first search over the pair graph, bounded by the profile's
+/// `max_route_hops`, keeping the route to the native token with the deepest
+/// liquidity. Routes going through other tokens need at least the profile's
+/// `min_liquidity_native` on each hop, a direct pair with the native token is
+/// always good enough.
+pub struct RouteFinder<'a> {
+    log_ordinal: u64,
+    pairs_store: &'a store::StoreGet,
+    reserves_store: &'a store::StoreGet,
+    graph_store: &'a store::StoreGet,
+    min_liquidity: BigDecimal,
+}
+
+impl<'a> RouteFinder<'a> {
+    pub fn new(
+        log_ordinal: u64,
+        pairs_store: &'a store::StoreGet,
+        reserves_store: &'a store::StoreGet,
+        graph_store: &'a store::StoreGet,
+    ) -> RouteFinder<'a> {
+        RouteFinder {
+            log_ordinal,
+            pairs_store,
+            reserves_store,
+            graph_store,
+            min_liquidity: BigDecimal::from_str(PROFILE.min_liquidity_native).unwrap(),
+        }
+    }
+
+    pub fn find(&self, token_address: &str) -> Option<Route> {
+        if token_address.eq(PROFILE.native_address) {
+            return Some(Route {
+                native_price: one_big_decimal(), // native price of a native is always 1
+                liquidity_native: zero_big_decimal(),
+                pairs: vec![],
+            });
+        }
+
+        let mut best: Option<Route> = None;
+        self.search(
+            token_address,
+            &mut vec![token_address.to_string()],
+            &mut vec![],
+            &mut best,
+        );
+        best
+    }
+
+    fn search(
+        &self,
+        token_address: &str,
+        visited: &mut Vec<String>,
+        hops: &mut Vec<Hop>,
+        best: &mut Option<Route>,
+    ) {
+        if token_address.eq(PROFILE.native_address) {
+            let route = evaluate(hops);
+            if hops.len() > 1 && route.liquidity_native.le(&self.min_liquidity) {
+                return; // Not enough liquidity
+            }
+            let deeper = match best {
+                None => true,
+                Some(best) => route.liquidity_native.gt(&best.liquidity_native),
+            };
+            if deeper {
+                *best = Some(route);
+            }
+            return;
+        }
+
+        if hops.len() == PROFILE.max_route_hops {
+            return;
+        }
+
+        // the last hop can only go to the native token
+        let last_hop = hops.len() + 1 == PROFILE.max_route_hops;
+        for (next_token, pair_address) in self.neighbors(token_address, last_hop) {
+            if visited.contains(&next_token) {
+                continue;
+            }
+
+            let hop = match self.hop(&pair_address, token_address) {
+                None => continue,
+                Some(hop) => hop,
+            };
+
+            visited.push(next_token.clone());
+            hops.push(hop);
+            self.search(&next_token, visited, hops, best);
+            hops.pop();
+            visited.pop();
+        }
+    }
+
+    /// Tokens paired with `token_address`, with the pair address: the native
+    /// token first, then the whitelisted tokens and the deepest other neighbors.
+    fn neighbors(&self, token_address: &str, native_only: bool) -> Vec<(String, String)> {
+        let mut neighbors: Vec<(String, String)> = vec![];
+
+        let candidates: Vec<&str> = match native_only {
+            true => vec![PROFILE.native_address],
+            false => std::iter::once(PROFILE.native_address)
+                .chain(PROFILE.whitelist_tokens.iter().copied())
+                .collect(),
+        };
+        for candidate in candidates {
+            if candidate.eq(token_address) || neighbors.iter().any(|(token, _)| token == candidate) {
+                continue;
+            }
+            if let Some(pair_bytes) = self.pairs_store.get_at(
+                self.log_ordinal,
+                &StoreKey::pair_tokens(token_address, candidate).encode(),
+            ) {
+                if let Ok(pair) = proto::decode::<pcs::Pair>(&pair_bytes) {
+                    neighbors.push((candidate.to_string(), pair.address));
+                }
+            }
+        }
+
+        let whitelisted = PROFILE.is_whitelisted(token_address);
+        if native_only || whitelisted {
+            // whitelisted tokens are hubs, their other neighbors are not worth following
+            return neighbors;
+        }
+
+        let graph_key = StoreKey::Neighbors {
+            token: token_address.to_string(),
+        };
+        let graph_bytes = match self.graph_store.get_at(self.log_ordinal, &graph_key.encode()) {
+            Some(graph_bytes) => graph_bytes,
+            None => return neighbors,
+        };
+        let entries = match str::from_utf8(graph_bytes.as_slice()) {
+            Ok(entries) => entries,
+            Err(_) => return neighbors,
+        };
+
+        // every reserve is in `token_address`, ranking the pairs by it ranks
+        // them by native liquidity
+        let mut ranked: Vec<(BigDecimal, &str, &str)> = vec![];
+        for (next_token, pair_address) in parse_neighbors(entries) {
+            if neighbors.iter().any(|(token, _)| token == next_token)
+                || ranked.iter().any(|(_, token, _)| *token == next_token)
+            {
+                continue;
+            }
+            if let Some(hop) = self.hop(pair_address, token_address) {
+                ranked.push((hop.token_reserve, next_token, pair_address));
+            }
+        }
+        ranked.sort_by(|(left, _, _), (right, _, _)| right.cmp(left));
+
+        for (_, next_token, pair_address) in ranked.into_iter().take(MAX_NEIGHBORS) {
+            neighbors.push((next_token.to_string(), pair_address.to_string()));
+        }
+
+        neighbors
+    }
+
+    fn hop(&self, pair_address: &str, token_address: &str) -> Option<Hop> {
+        let pair_key = StoreKey::Pair {
+            pair: pair_address.to_string(),
+        };
+        let pair_bytes = self.pairs_store.get_at(self.log_ordinal, &pair_key.encode())?;
+        let pair: pcs::Pair = proto::decode(&pair_bytes).ok()?;
+
+        let reserve0 = self.reserve(StoreKey::Reserve {
+            pair: pair.address.clone(),
+            token: pair.token0_address.clone(),
+            field: Field::Reserve0,
+        })?;
+        let reserve1 = self.reserve(StoreKey::Reserve {
+            pair: pair.address.clone(),
+            token: pair.token1_address.clone(),
+            field: Field::Reserve1,
+        })?;
+
+        let (token_reserve, next_token_reserve) = match pair.token0_address == token_address {
+            true => (reserve0, reserve1),
+            false => (reserve1, reserve0),
+        };
+
+        Some(Hop {
+            pair_address: pair.address,
+            price: next_token_reserve.clone().div(token_reserve.clone()).with_prec(100),
+            token_reserve,
+            next_token_reserve,
+        })
+    }
+
+    fn reserve(&self, key: StoreKey) -> Option<BigDecimal> {
+        let reserve_bytes = self.reserves_store.get_at(self.log_ordinal, &key.encode())?;
+        let reserve = BigDecimal::from_str(str::from_utf8(reserve_bytes.as_slice()).ok()?)
+            .ok()?
+            .with_prec(100);
+        match reserve.eq(&zero_big_decimal()) {
+            true => None,
+            false => Some(reserve),
+        }
+    }
+}
+
+/// Walks the route back from the native token, pricing each token in native
+/// from the one after it.
+fn evaluate(hops: &[Hop]) -> Route {
+    let mut native_price = one_big_decimal();
+    let mut liquidity_native: Option<BigDecimal> = None;
+
+    for hop in hops.iter().rev() {
+        let hop_liquidity = hop.next_token_reserve.clone().mul(native_price.clone());
+        liquidity_native = match liquidity_native {
+            Some(liquidity) if liquidity.le(&hop_liquidity) => Some(liquidity),
+            _ => Some(hop_liquidity),
+        };
+        native_price = native_price.mul(hop.price.clone()).with_prec(100);
+    }
+
+    Route {
+        native_price,
+        liquidity_native: liquidity_native.unwrap_or_else(zero_big_decimal),
+        pairs: hops.iter().map(|hop| hop.pair_address.clone()).collect(),
+    }
+}
+
+/// Entries appended by `store_pair_graph`, `{token}:{pair};` each.
+pub fn parse_neighbors(entries: &str) -> impl Iterator<Item = (&str, &str)> {
+    entries
+        .split(';')
+        .filter_map(|entry| entry.split_once(':'))
+}
+
+pub fn neighbor_entry(token_address: &str, pair_address: &str) -> String {
+    format!("{}:{};", token_address, pair_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block::{pair_created_log, sync_log, BlockBuilder};
+    use crate::testing::store::{MemoryStore, MemoryStoreRef, StoreAppend, StoreGet, StoreSet};
+    use crate::testing::{decimal, Pipeline, ONE, PAIR, PAIR_AB, TOKEN_A, TOKEN_B, USER};
+
+    const TOKEN: &str = "0x00000000000000000000000000000000000000c0";
+
+    struct Graph {
+        pairs: MemoryStoreRef,
+        reserves: MemoryStoreRef,
+        graph: MemoryStoreRef,
+    }
+
+    impl Graph {
+        fn new() -> Graph {
+            Graph {
+                pairs: MemoryStore::new_ref(),
+                reserves: MemoryStore::new_ref(),
+                graph: MemoryStore::new_ref(),
+            }
+        }
+
+        fn pair(&self, address: &str, token_a: (&str, &str), token_b: (&str, &str)) {
+            let (token0, token1) = match token_a.0 > token_b.0 {
+                true => (token_b, token_a),
+                false => (token_a, token_b),
+            };
+            let pair = pcs::Pair {
+                address: address.to_string(),
+                token0_address: token0.0.to_string(),
+                token1_address: token1.0.to_string(),
+                ..Default::default()
+            };
+            let pair_bytes = proto::encode(&pair).unwrap();
+
+            let pairs = StoreSet::new(&self.pairs);
+            pairs.set(0, StoreKey::Pair { pair: address.to_string() }.encode(), &pair_bytes);
+            pairs.set(0, StoreKey::pair_tokens(token0.0, token1.0).encode(), &pair_bytes);
+
+            let reserves = StoreSet::new(&self.reserves);
+            for (token, field) in [(token0, Field::Reserve0), (token1, Field::Reserve1)] {
+                let key = StoreKey::Reserve {
+                    pair: address.to_string(),
+                    token: token.0.to_string(),
+                    field,
+                };
+                reserves.set(0, key.encode(), &Vec::from(token.1));
+            }
+
+            let graph = StoreAppend::new(&self.graph);
+            for (token, other) in [(token0.0, token1.0), (token1.0, token0.0)] {
+                let key = StoreKey::Neighbors { token: token.to_string() };
+                graph.append(0, key.encode(), &neighbor_entry(other, address));
+            }
+        }
+
+        fn find(&self, token_address: &str) -> Option<Route> {
+            let (pairs, reserves, graph) = (
+                StoreGet::new(&self.pairs),
+                StoreGet::new(&self.reserves),
+                StoreGet::new(&self.graph),
+            );
+            RouteFinder::new(1, &pairs, &reserves, &graph).find(token_address)
+        }
+    }
+
+    fn address(prefix: &str, index: usize) -> String {
+        format!("0x{:0>40}", format!("{}{}", prefix, index))
+    }
+
+    #[test]
+    fn neighbors_are_parsed_from_appended_entries() {
+        let entries = format!("{}{}garbage;", neighbor_entry("0xa", "0x1"), neighbor_entry("0xb", "0x2"));
+
+        let neighbors: Vec<(&str, &str)> = parse_neighbors(&entries).collect();
+        assert_eq!(neighbors, vec![("0xa", "0x1"), ("0xb", "0x2")]);
+        assert_eq!(parse_neighbors("").count(), 0);
+    }
+
+    #[test]
+    fn deepest_neighbors_are_followed() {
+        let graph = Graph::new();
+
+        // the deepest pair is appended last, after MAX_NEIGHBORS shallow ones
+        for index in 1..=MAX_NEIGHBORS + 1 {
+            let middle = address("d", index);
+            let depth = match index {
+                index if index == MAX_NEIGHBORS + 1 => "1000",
+                _ => "10",
+            };
+            graph.pair(&address("e", index), (TOKEN, depth), (&middle, depth));
+            graph.pair(&address("f", index), (&middle, "100"), (PROFILE.native_address, "100"));
+        }
+
+        let route = graph.find(TOKEN).expect("route");
+        let deepest = MAX_NEIGHBORS + 1;
+        assert_eq!(route.pairs, vec![address("e", deepest), address("f", deepest)]);
+        assert_eq!(route.native_price, BigDecimal::from(1));
+        assert_eq!(route.liquidity_native, BigDecimal::from(100));
+    }
+
+    #[test]
+    fn shallow_routes_are_rejected() {
+        let graph = Graph::new();
+        let middle = address("d", 1);
+        graph.pair(&address("e", 1), (TOKEN, "2"), (&middle, "1"));
+        graph.pair(&address("f", 1), (&middle, "100"), (PROFILE.native_address, "100"));
+
+        // 1 native worth of liquidity on the first hop, under the profile's minimum
+        assert!(graph.find(TOKEN).is_none());
+
+        // a direct pair is always good enough, 1 TOKEN is worth 0.5 native
+        graph.pair(&address("e", 2), (TOKEN, "2"), (PROFILE.native_address, "1"));
+        let route = graph.find(TOKEN).expect("route");
+        assert_eq!(route.pairs, vec![address("e", 2)]);
+        assert_eq!(route.native_price, BigDecimal::from_str("0.5").unwrap());
+    }
+
+    #[test]
+    fn corrupted_values_end_the_route() {
+        let graph = Graph::new();
+        graph.pair(&address("e", 1), (TOKEN, "2"), (PROFILE.native_address, "1"));
+        StoreSet::new(&graph.reserves).set(
+            0,
+            StoreKey::Reserve {
+                pair: address("e", 1),
+                token: TOKEN.to_string(),
+                field: Field::Reserve0,
+            }
+            .encode(),
+            &Vec::from("not a decimal"),
+        );
+
+        assert!(graph.find(TOKEN).is_none());
+    }
+
+    #[test]
+    fn long_tail_tokens_are_priced_through_other_pairs() {
+        let pipeline = Pipeline::with_pair();
+
+        let factory = format!("0x{}", PROFILE.factory_address_hex());
+        pipeline.process_block(
+            BlockBuilder::new(2, 1_600_000_002)
+                .transaction(
+                    USER,
+                    &factory,
+                    vec![(&factory, vec![pair_created_log(TOKEN_A, TOKEN_B, PAIR_AB, 2)])],
+                )
+                .build(),
+        );
+
+        // 1 TKA is worth 0.5 NATIVE, 1 TKB is worth 4 TKA
+        pipeline.process_block(
+            BlockBuilder::new(3, 1_600_000_004)
+                .transaction(USER, PAIR, vec![(PAIR, vec![sync_log(100 * ONE, 200 * ONE)])])
+                .transaction(USER, PAIR_AB, vec![(PAIR_AB, vec![sync_log(40 * ONE, 10 * ONE)])])
+                .build(),
+        );
+
+        let prices = StoreGet::new(&pipeline.prices);
+        let get = |key: String| String::from_utf8(prices.get_last(&key).expect(&key)).unwrap();
+        assert_eq!(decimal(get(format!("dprice:{}:native", TOKEN_B))), BigDecimal::from(2));
+        assert_eq!(
+            get(format!("droute:{}:native", TOKEN_B)),
+            format!("{},{}", PAIR_AB, PAIR)
+        );
+    }
+}
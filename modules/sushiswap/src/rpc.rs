@@ -1,67 +1,29 @@
-use hex;
-use substreams::{Hex};
 use substreams_ethereum::pb::eth;
 
-use crate::{address_decode, address_pretty, Token};
-use crate::eth::{read_string, read_uint32};
+#[cfg(not(test))]
+use substreams_ethereum::rpc::eth_call;
 
-pub fn create_rpc_calls(addr: &Vec<u8>) -> eth::rpc::RpcCalls {
-    let decimals = hex::decode("313ce567").unwrap();
-    let name = hex::decode("06fdde03").unwrap();
-    let symbol = hex::decode("95d89b41").unwrap();
+#[cfg(test)]
+use crate::testing::rpc::eth_call;
 
-    return eth::rpc::RpcCalls {
-        calls: vec![
-            eth::rpc::RpcCall {
-                to_addr: Vec::from(addr.clone()),
-                data: decimals,
-            },
-            eth::rpc::RpcCall {
-                to_addr: Vec::from(addr.clone()),
-                data: name,
-            },
-            eth::rpc::RpcCall {
-                to_addr: Vec::from(addr.clone()),
-                data: symbol,
-            },
-        ],
-    };
-}
-
-pub fn retry_rpc_calls(pair_token_address: &String) -> Result<Token, String> {
-    let rpc_calls = create_rpc_calls(&address_decode(pair_token_address));
-
-    let rpc_responses_unmarshalled: eth::rpc::RpcResponses =
-	substreams_ethereum::rpc::eth_call(&rpc_calls);
+use token_common::rpc::{create_rpc_calls, decode_token, TOKEN_METHODS};
 
-    if rpc_responses_unmarshalled.responses[0].failed
-        || rpc_responses_unmarshalled.responses[1].failed
-        || rpc_responses_unmarshalled.responses[2].failed
-    {
-        return Err(format!("not a ERC20 because of a failure: {}", address_pretty(pair_token_address.as_bytes())));
-    };
+use crate::{address_decode, Token};
 
-    let decoded_decimals = read_uint32(rpc_responses_unmarshalled.responses[0].raw.as_ref());
-    if decoded_decimals.is_err() {
-        return Err(format!("{} is not a an ERC20 token contract decimal `eth_call` failed: {}", Hex(&pair_token_address), decoded_decimals.err().unwrap()));
-    }
-
-
-    let decoded_name = read_string(rpc_responses_unmarshalled.responses[1].raw.as_ref());
-    if decoded_name.is_err() {
-        return Err(format!("{} is not a an ERC20 token contract name `eth_call` failed: {}", Hex(&pair_token_address),decoded_name.err().unwrap()));
-    }
+/// Fetches a token missing from `ethtokens_at_pcs:store_tokens`, a name or
+/// symbol that cannot be decoded is not a reason to skip a token that is in a
+/// pair.
+pub fn retry_rpc_calls(pair_token_address: &String) -> Result<Token, String> {
+    let address = address_decode(pair_token_address);
+    let request = create_rpc_calls(&address, TOKEN_METHODS);
 
+    let rpc_responses_unmarshalled: eth::rpc::RpcResponses = eth_call(request.calls());
+    let mut responses = request.responses(rpc_responses_unmarshalled.responses);
 
-    let decoded_symbol = read_string(rpc_responses_unmarshalled.responses[2].raw.as_ref());
-    if decoded_symbol.is_err() {
-        return Err(format!("{} is not a an ERC20 token contract symbol `eth_call` failed: {}", Hex(&pair_token_address),decoded_symbol.err().unwrap()));
-    }
+    let response = responses
+        .remove(&address)
+        .ok_or_else(|| format!("no rpc response for token {}", pair_token_address))?;
 
-    return Ok(Token {
-        address: pair_token_address.to_string(),
-        name: decoded_name.unwrap(),
-        symbol: decoded_symbol.unwrap(),
-        decimals: decoded_decimals.unwrap() as u64,
-    })
+    decode_token(pair_token_address.to_string(), &response)
+        .map_err(|error| format!("{} is not an ERC20 token: {}", pair_token_address, error))
 }
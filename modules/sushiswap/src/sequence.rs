@@ -0,0 +1,268 @@
+use crate::event::pcs_event::Event;
+use crate::event::{PairBurnEvent, PairMintEvent, PairSwapEvent, PairTransferEvent};
+
+/// A complete Mint, Burn or Swap recognized in the logs a pair emitted in one
+/// call, along with the LP token transfers that belong to it.
+pub enum PairAction<'a> {
+    Mint {
+        fee_transfer: Option<&'a PairTransferEvent>,
+        transfer: &'a PairTransferEvent,
+        mint: &'a PairMintEvent,
+    },
+    Burn {
+        fee_transfer: Option<&'a PairTransferEvent>,
+        transfer: &'a PairTransferEvent,
+        burn: &'a PairBurnEvent,
+    },
+    Swap {
+        swap: &'a PairSwapEvent,
+    },
+}
+
+impl<'a> PairAction<'a> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PairAction::Mint { .. } => "mint",
+            PairAction::Burn { .. } => "burn",
+            PairAction::Swap { .. } => "swap",
+        }
+    }
+
+    /// Ordinal of the Mint, Burn or Swap log closing the action.
+    pub fn log_ordinal(&self) -> u64 {
+        match self {
+            PairAction::Mint { mint, .. } => mint.log_index,
+            PairAction::Burn { burn, .. } => burn.log_index,
+            PairAction::Swap { swap } => swap.log_index,
+        }
+    }
+}
+
+/// A log that could not be fitted in any known sequence.
+pub struct Anomaly {
+    pub log_ordinal: u64,
+    pub reason: String,
+    pub events: Vec<String>,
+}
+
+/// What has been seen since the last complete action. A pair's `_mintFee`
+/// may emit a fee Transfer before the LP Transfer, the reserves update emits
+/// a Sync, and the Mint/Burn/Swap closes the sequence.
+#[derive(Default)]
+struct Pending<'a> {
+    transfers: Vec<&'a PairTransferEvent>,
+    synced: bool,
+    names: Vec<&'static str>,
+}
+
+impl<'a> Pending<'a> {
+    fn anomaly(&self, log_ordinal: u64, reason: &str, closing: &str) -> Anomaly {
+        let mut events: Vec<String> = self.names.iter().map(|name| name.to_string()).collect();
+        events.push(closing.to_string());
+
+        Anomaly {
+            log_ordinal,
+            reason: reason.to_string(),
+            events,
+        }
+    }
+
+    fn fee_and_lp_transfers(
+        &self,
+    ) -> Option<(Option<&'a PairTransferEvent>, &'a PairTransferEvent)> {
+        let count = self.transfers.len();
+        if count == 0 {
+            return None;
+        }
+        let fee_transfer = if count > 1 {
+            Some(self.transfers[count - 2])
+        } else {
+            None
+        };
+        Some((fee_transfer, self.transfers[count - 1]))
+    }
+}
+
+/// Walks the events a pair emitted in a single call and recognizes every
+/// Transfer(s)/Sync/Mint, Transfer(s)/Sync/Burn and Sync/Swap sequence in it,
+/// however many there are.
+pub fn classify(events: &[Event]) -> (Vec<PairAction>, Vec<Anomaly>) {
+    let mut actions = vec![];
+    let mut anomalies = vec![];
+    let mut pending = Pending::default();
+
+    for event in events {
+        match event {
+            Event::PairApprovalEvent(_) => {}
+            Event::PairTransferEvent(transfer) => {
+                if pending.synced {
+                    // a Sync not closed by a Mint, Burn or Swap comes from a `sync()` or
+                    // `skim()` call, it starts nothing
+                    pending = Pending::default();
+                }
+                pending.transfers.push(transfer);
+                pending.names.push("Transfer");
+            }
+            Event::PairSyncEvent(_) => {
+                if pending.synced {
+                    pending = Pending::default();
+                }
+                pending.synced = true;
+                pending.names.push("Sync");
+            }
+            Event::PairMintEvent(mint) => {
+                match (pending.synced, pending.fee_and_lp_transfers()) {
+                    (true, Some((fee_transfer, transfer))) => actions.push(PairAction::Mint {
+                        fee_transfer,
+                        transfer,
+                        mint,
+                    }),
+                    (false, _) => anomalies.push(pending.anomaly(
+                        mint.log_index,
+                        "mint without a preceding sync",
+                        "Mint",
+                    )),
+                    (true, None) => anomalies.push(pending.anomaly(
+                        mint.log_index,
+                        "mint without a liquidity transfer",
+                        "Mint",
+                    )),
+                }
+                pending = Pending::default();
+            }
+            Event::PairBurnEvent(burn) => {
+                match (pending.synced, pending.fee_and_lp_transfers()) {
+                    (true, Some((fee_transfer, transfer))) => actions.push(PairAction::Burn {
+                        fee_transfer,
+                        transfer,
+                        burn,
+                    }),
+                    (false, _) => anomalies.push(pending.anomaly(
+                        burn.log_index,
+                        "burn without a preceding sync",
+                        "Burn",
+                    )),
+                    (true, None) => anomalies.push(pending.anomaly(
+                        burn.log_index,
+                        "burn without a liquidity transfer",
+                        "Burn",
+                    )),
+                }
+                pending = Pending::default();
+            }
+            Event::PairSwapEvent(swap) => {
+                if pending.synced {
+                    actions.push(PairAction::Swap { swap });
+                } else {
+                    anomalies.push(pending.anomaly(
+                        swap.log_index,
+                        "swap without a preceding sync",
+                        "Swap",
+                    ));
+                }
+                pending = Pending::default();
+            }
+            Event::PairCreatedEvent(created) => {
+                anomalies.push(pending.anomaly(
+                    created.log_index,
+                    "pair created event emitted by a pair",
+                    "PairCreated",
+                ));
+                pending = Pending::default();
+            }
+        }
+    }
+
+    (actions, anomalies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::PairSyncEvent;
+
+    fn transfer(log_index: u64) -> Event {
+        Event::PairTransferEvent(PairTransferEvent {
+            log_address: vec![],
+            log_index,
+            from: vec![],
+            to: vec![],
+            value: vec![],
+        })
+    }
+
+    fn sync(log_index: u64) -> Event {
+        Event::PairSyncEvent(PairSyncEvent {
+            log_address: vec![],
+            log_index,
+            reserve0: vec![],
+            reserve1: vec![],
+        })
+    }
+
+    fn mint(log_index: u64) -> Event {
+        Event::PairMintEvent(PairMintEvent {
+            log_address: vec![],
+            log_index,
+            sender: vec![],
+            amount0: vec![],
+            amount1: vec![],
+        })
+    }
+
+    fn swap(log_index: u64) -> Event {
+        Event::PairSwapEvent(PairSwapEvent {
+            log_address: vec![],
+            log_index,
+            sender: vec![],
+            amount0_in: vec![],
+            amount1_in: vec![],
+            amount0_out: vec![],
+            amount1_out: vec![],
+            to: vec![],
+        })
+    }
+
+    #[test]
+    fn recognizes_every_sequence_of_a_call() {
+        let events = vec![
+            transfer(1),
+            transfer(2),
+            sync(3),
+            mint(4),
+            sync(5),
+            swap(6),
+            sync(7),
+            swap(8),
+        ];
+
+        let (actions, anomalies) = classify(&events);
+        assert!(anomalies.is_empty());
+        assert_eq!(actions.len(), 3);
+        match &actions[0] {
+            PairAction::Mint {
+                fee_transfer,
+                transfer,
+                mint,
+            } => {
+                assert_eq!(fee_transfer.unwrap().log_index, 1);
+                assert_eq!(transfer.log_index, 2);
+                assert_eq!(mint.log_index, 4);
+            }
+            _ => panic!("expected a mint"),
+        }
+        assert!(matches!(actions[2], PairAction::Swap { swap } if swap.log_index == 8));
+    }
+
+    #[test]
+    fn reports_unknown_patterns() {
+        let events = vec![transfer(1), swap(2), sync(3), mint(4)];
+
+        let (actions, anomalies) = classify(&events);
+        assert!(actions.is_empty());
+        assert_eq!(anomalies.len(), 2);
+        assert_eq!(anomalies[0].log_ordinal, 2);
+        assert_eq!(anomalies[0].events, vec!["Transfer", "Swap"]);
+        assert_eq!(anomalies[1].reason, "mint without a liquidity transfer");
+    }
+}
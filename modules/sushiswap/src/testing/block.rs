@@ -0,0 +1,147 @@
+use substreams::hex;
+
+use crate::pb::eth::{Block, BlockHeader, Call, Log, TransactionReceipt, TransactionTrace};
+
+const PAIR_CREATED: [u8; 32] =
+    hex!("0d3648bd0f6ba80134a33ba9275ac585d9d315f0ad8355cddefde31afa28d0e9");
+const TRANSFER: [u8; 32] =
+    hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+const SYNC: [u8; 32] = hex!("1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1");
+const MINT: [u8; 32] = hex!("4c209b5fc8ad50758f13e2e1088ba56a560dff690a1c6fef26394f4c03821c4f");
+const SWAP: [u8; 32] = hex!("d78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822");
+
+/// Builds synthetic blocks for scenarios no captured fixture covers. Log
+/// `block_index` values are assigned in insertion order, like on chain.
+pub struct BlockBuilder {
+    block: Block,
+    next_log_index: u32,
+}
+
+impl BlockBuilder {
+    pub fn new(number: u64, timestamp_seconds: i64) -> BlockBuilder {
+        BlockBuilder {
+            block: Block {
+                number,
+                hash: number.to_be_bytes().to_vec(),
+                header: Some(BlockHeader {
+                    number,
+                    timestamp: Some(prost_types::Timestamp {
+                        seconds: timestamp_seconds,
+                        nanos: 0,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            next_log_index: 0,
+        }
+    }
+
+    /// Adds a transaction from `from` to `to`, each entry of `calls` being the
+    /// address of a called contract and the logs it emitted.
+    pub fn transaction(mut self, from: &str, to: &str, calls: Vec<(&str, Vec<Log>)>) -> Self {
+        let trx_index = self.block.transaction_traces.len() as u32;
+        let mut trx = TransactionTrace {
+            hash: [self.block.number.to_be_bytes(), (trx_index as u64).to_be_bytes()].concat(),
+            from: address(from),
+            to: address(to),
+            index: trx_index,
+            receipt: Some(TransactionReceipt::default()),
+            ..Default::default()
+        };
+
+        for (call_index, (call_address, logs)) in calls.into_iter().enumerate() {
+            let mut call = Call {
+                index: call_index as u32,
+                address: address(call_address),
+                ..Default::default()
+            };
+
+            for mut log in logs {
+                log.address = address(call_address);
+                log.block_index = self.next_log_index;
+                self.next_log_index += 1;
+
+                trx.receipt.as_mut().unwrap().logs.push(log.clone());
+                call.logs.push(log);
+            }
+            trx.calls.push(call);
+        }
+
+        self.block.transaction_traces.push(trx);
+        self
+    }
+
+    pub fn build(self) -> Block {
+        self.block
+    }
+}
+
+pub fn address(address: &str) -> Vec<u8> {
+    hex::decode(address.trim_start_matches("0x")).unwrap()
+}
+
+pub fn uint(value: u128) -> Vec<u8> {
+    [[0u8; 16], value.to_be_bytes()].concat()
+}
+
+fn topic_address(value: &str) -> Vec<u8> {
+    [vec![0u8; 12], address(value)].concat()
+}
+
+pub fn pair_created_log(token0: &str, token1: &str, pair: &str, pair_count: u128) -> Log {
+    Log {
+        topics: vec![
+            PAIR_CREATED.to_vec(),
+            topic_address(token0),
+            topic_address(token1),
+        ],
+        data: [topic_address(pair), uint(pair_count)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn transfer_log(from: &str, to: &str, value: u128) -> Log {
+    Log {
+        topics: vec![TRANSFER.to_vec(), topic_address(from), topic_address(to)],
+        data: uint(value),
+        ..Default::default()
+    }
+}
+
+pub fn sync_log(reserve0: u128, reserve1: u128) -> Log {
+    Log {
+        topics: vec![SYNC.to_vec()],
+        data: [uint(reserve0), uint(reserve1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn mint_log(sender: &str, amount0: u128, amount1: u128) -> Log {
+    Log {
+        topics: vec![MINT.to_vec(), topic_address(sender)],
+        data: [uint(amount0), uint(amount1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn swap_log(
+    sender: &str,
+    to: &str,
+    amount0_in: u128,
+    amount1_in: u128,
+    amount0_out: u128,
+    amount1_out: u128,
+) -> Log {
+    Log {
+        topics: vec![SWAP.to_vec(), topic_address(sender), topic_address(to)],
+        data: [
+            uint(amount0_in),
+            uint(amount1_in),
+            uint(amount0_out),
+            uint(amount1_out),
+        ]
+        .concat(),
+        ..Default::default()
+    }
+}
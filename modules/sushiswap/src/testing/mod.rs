@@ -0,0 +1,462 @@
+//! Offline harness running the handlers natively, wired the same way as in
+//! `substreams.yaml`, with in-memory stores standing in for the runtime ones.
+//!
+//! Captured blocks live under `testdata/<case>/` as `blocks/*.binpb` files
+//! (encoded `pb::eth::Block`, processed in file name order), with an optional
+//! `tokens.binpb` (encoded `sf.ethereum.tokens.v1.Tokens`) seeding the imported
+//! tokens store. The `DatabaseChanges` produced for each block are compared to
+//! the case's `golden.txt`; run with `UPDATE_GOLDEN=1` to regenerate it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use prost::Message;
+use substreams::pb::substreams::Clock;
+use substreams::proto;
+
+use crate::keys::StoreKey;
+use crate::pb;
+use crate::pb::database::DatabaseChanges;
+use crate::pb::tokens::{Token, Tokens};
+use crate::profile::PROFILE;
+use crate::testing::block::*;
+use crate::testing::store::*;
+
+pub mod block;
+pub mod rpc;
+pub mod store;
+
+pub struct Pipeline {
+    pub eth_tokens: MemoryStoreRef,
+    pub pcs_tokens: MemoryStoreRef,
+    pub pairs: MemoryStoreRef,
+    pub pair_graph: MemoryStoreRef,
+    pub buckets: MemoryStoreRef,
+    pub reserves: MemoryStoreRef,
+    pub prices: MemoryStoreRef,
+    pub twap_cumulative: MemoryStoreRef,
+    pub twap_changes: MemoryStoreRef,
+    pub twap_observations: MemoryStoreRef,
+    pub twap: MemoryStoreRef,
+    pub totals: MemoryStoreRef,
+    pub volumes: MemoryStoreRef,
+    pub lp_balances: MemoryStoreRef,
+    pub candle_open: MemoryStoreRef,
+    pub candle_high: MemoryStoreRef,
+    pub candle_low: MemoryStoreRef,
+    pub candle_close: MemoryStoreRef,
+    pub candle_volume: MemoryStoreRef,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline {
+            eth_tokens: MemoryStore::new_ref(),
+            pcs_tokens: MemoryStore::new_ref(),
+            pairs: MemoryStore::new_ref(),
+            pair_graph: MemoryStore::new_ref(),
+            buckets: MemoryStore::new_ref(),
+            reserves: MemoryStore::new_ref(),
+            prices: MemoryStore::new_ref(),
+            twap_cumulative: MemoryStore::new_ref(),
+            twap_changes: MemoryStore::new_ref(),
+            twap_observations: MemoryStore::new_ref(),
+            twap: MemoryStore::new_ref(),
+            totals: MemoryStore::new_ref(),
+            volumes: MemoryStore::new_ref(),
+            lp_balances: MemoryStore::new_ref(),
+            candle_open: MemoryStore::new_ref(),
+            candle_high: MemoryStore::new_ref(),
+            candle_low: MemoryStore::new_ref(),
+            candle_close: MemoryStore::new_ref(),
+            candle_volume: MemoryStore::new_ref(),
+        }
+    }
+
+    /// A pipeline knowing the `synthetic_tokens`, with the NATIVE/TKA pair `PAIR`
+    /// created at block 1.
+    pub fn with_pair() -> Pipeline {
+        let pipeline = Pipeline::new();
+        pipeline.seed_tokens(synthetic_tokens());
+        create_pair(&pipeline);
+        pipeline
+    }
+
+    /// Seeds the store normally imported from `ethtokens_at_pcs:store_tokens`.
+    pub fn seed_tokens(&self, tokens: Tokens) {
+        let output = StoreSet::new(&self.eth_tokens);
+        for token in tokens.tokens {
+            output.set(
+                0,
+                StoreKey::Token {
+                    token: token.address.clone(),
+                }
+                .encode(),
+                &proto::encode(&token).unwrap(),
+            );
+        }
+        self.eth_tokens.borrow_mut().flush();
+    }
+
+    pub fn process_block(&self, blk: pb::eth::Block) -> DatabaseChanges {
+        for store in self.stores() {
+            store.borrow_mut().flush();
+        }
+
+        let clock = clock(&blk);
+
+        let pairs = crate::map_pairs(blk.clone()).unwrap();
+        crate::store_pcs_tokens(
+            pairs.clone(),
+            StoreGet::new(&self.eth_tokens),
+            StoreSetIfNotExists::new(&self.pcs_tokens),
+        );
+        crate::store_pairs(pairs.clone(), StoreSet::new(&self.pairs));
+        crate::store_pair_graph(pairs.clone(), StoreAppend::new(&self.pair_graph));
+
+        crate::store_buckets(clock.clone(), StoreSet::new(&self.buckets));
+
+        let reserves = crate::map_reserves(
+            blk.clone(),
+            StoreGet::new(&self.pairs),
+            StoreGet::new(&self.pcs_tokens),
+        )
+        .unwrap();
+        crate::store_reserves(
+            clock.clone(),
+            reserves.clone(),
+            StoreGet::new(&self.pairs),
+            self.buckets.borrow().deltas(),
+            StoreSet::new(&self.reserves),
+        );
+        crate::store_prices(
+            clock.clone(),
+            reserves.clone(),
+            StoreGet::new(&self.pairs),
+            StoreGet::new(&self.reserves),
+            StoreGet::new(&self.pair_graph),
+            self.buckets.borrow().deltas(),
+            StoreSet::new(&self.prices),
+        );
+        crate::store_twap_cumulative(
+            clock.clone(),
+            self.prices.borrow().deltas(),
+            StoreAddBigFloat::new(&self.twap_cumulative),
+        );
+        crate::store_twap_changes(
+            clock.clone(),
+            self.prices.borrow().deltas(),
+            StoreSet::new(&self.twap_changes),
+        );
+        crate::store_twap_observations(
+            clock.clone(),
+            self.buckets.borrow().deltas(),
+            self.prices.borrow().deltas(),
+            StoreGet::new(&self.twap_changes),
+            StoreGet::new(&self.twap_cumulative),
+            StoreSet::new(&self.twap_observations),
+        );
+        crate::store_twap(
+            clock.clone(),
+            self.prices.borrow().deltas(),
+            StoreGet::new(&self.twap_cumulative),
+            StoreGet::new(&self.twap_observations),
+            StoreSet::new(&self.twap),
+        );
+
+        let lp_transfers = crate::map_lp_transfers(blk.clone(), StoreGet::new(&self.pairs)).unwrap();
+        crate::store_lp_balances(lp_transfers, StoreAddBigFloat::new(&self.lp_balances));
+
+        let events = crate::map_burn_swaps_events(
+            blk,
+            StoreGet::new(&self.pairs),
+            StoreGet::new(&self.prices),
+            StoreGet::new(&self.pcs_tokens),
+        )
+        .unwrap();
+        crate::store_totals(
+            clock.clone(),
+            pairs,
+            events.clone(),
+            self.buckets.borrow().deltas(),
+            StoreAddInt64::new(&self.totals),
+        );
+        crate::store_volumes(
+            clock.clone(),
+            events.clone(),
+            self.buckets.borrow().deltas(),
+            StoreAddBigFloat::new(&self.volumes),
+        );
+
+        crate::store_candle_open(
+            clock.clone(),
+            reserves.clone(),
+            self.prices.borrow().deltas(),
+            self.buckets.borrow().deltas(),
+            StoreSetIfNotExists::new(&self.candle_open),
+        );
+        crate::store_candle_high(
+            clock.clone(),
+            reserves.clone(),
+            self.prices.borrow().deltas(),
+            self.buckets.borrow().deltas(),
+            StoreMaxBigFloat::new(&self.candle_high),
+        );
+        crate::store_candle_low(
+            clock.clone(),
+            reserves.clone(),
+            self.prices.borrow().deltas(),
+            self.buckets.borrow().deltas(),
+            StoreMinBigFloat::new(&self.candle_low),
+        );
+        crate::store_candle_close(
+            clock.clone(),
+            reserves.clone(),
+            self.prices.borrow().deltas(),
+            self.buckets.borrow().deltas(),
+            StoreSet::new(&self.candle_close),
+        );
+        crate::store_candle_volume(
+            clock.clone(),
+            events.clone(),
+            self.buckets.borrow().deltas(),
+            StoreAddBigFloat::new(&self.candle_volume),
+        );
+        let candles = crate::map_candles(
+            self.candle_open.borrow().deltas(),
+            self.candle_close.borrow().deltas(),
+            self.candle_volume.borrow().deltas(),
+            StoreGet::new(&self.candle_open),
+            StoreGet::new(&self.candle_high),
+            StoreGet::new(&self.candle_low),
+            StoreGet::new(&self.candle_close),
+            StoreGet::new(&self.candle_volume),
+        )
+        .unwrap();
+
+        crate::db_out(
+            clock,
+            self.pcs_tokens.borrow().deltas(),
+            self.pairs.borrow().deltas(),
+            self.totals.borrow().deltas(),
+            self.volumes.borrow().deltas(),
+            self.reserves.borrow().deltas(),
+            events,
+            candles,
+            StoreGet::new(&self.pcs_tokens),
+            StoreGet::new(&self.pairs),
+            StoreGet::new(&self.prices),
+            StoreGet::new(&self.volumes),
+            self.lp_balances.borrow().deltas(),
+            StoreGet::new(&self.lp_balances),
+            reserves,
+        )
+        .unwrap()
+    }
+
+    fn stores(&self) -> Vec<&MemoryStoreRef> {
+        vec![
+            &self.pcs_tokens,
+            &self.pairs,
+            &self.pair_graph,
+            &self.buckets,
+            &self.reserves,
+            &self.prices,
+            &self.twap_cumulative,
+            &self.twap_changes,
+            &self.twap_observations,
+            &self.twap,
+            &self.totals,
+            &self.volumes,
+            &self.lp_balances,
+            &self.candle_open,
+            &self.candle_high,
+            &self.candle_low,
+            &self.candle_close,
+            &self.candle_volume,
+        ]
+    }
+}
+
+pub fn clock(blk: &pb::eth::Block) -> Clock {
+    Clock {
+        id: hex::encode(&blk.hash),
+        number: blk.number,
+        timestamp: blk.header.as_ref().unwrap().timestamp.clone(),
+    }
+}
+
+fn testdata_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata")
+}
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn run_case(case_dir: &Path) -> String {
+    let pipeline = Pipeline::new();
+
+    let tokens_path = case_dir.join("tokens.binpb");
+    if tokens_path.exists() {
+        let tokens = Tokens::decode(fs::read(tokens_path).unwrap().as_slice()).unwrap();
+        pipeline.seed_tokens(tokens);
+    }
+
+    let mut output = String::new();
+    for block_path in sorted_entries(&case_dir.join("blocks")) {
+        let blk = pb::eth::Block::decode(fs::read(&block_path).unwrap().as_slice()).unwrap();
+        let block_num = blk.number;
+        let changes = pipeline.process_block(blk);
+        output.push_str(&format!("# block {}\n{:#?}\n", block_num, changes));
+    }
+    output
+}
+
+#[test]
+fn fixtures_match_golden_files() {
+    let testdata = testdata_dir();
+    assert!(testdata.is_dir(), "missing {}", testdata.display());
+
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let mut cases = 0;
+    for case_dir in sorted_entries(&testdata) {
+        if !case_dir.join("blocks").is_dir() {
+            continue;
+        }
+        cases += 1;
+
+        let actual = run_case(&case_dir);
+        let golden_path = case_dir.join("golden.txt");
+        if update {
+            fs::write(&golden_path, actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&golden_path).unwrap_or_else(|_| {
+            panic!(
+                "missing {}, run with UPDATE_GOLDEN=1 to create it",
+                golden_path.display()
+            )
+        });
+        assert!(
+            expected == actual,
+            "{} does not match the handlers output, run with UPDATE_GOLDEN=1 to update it",
+            golden_path.display()
+        );
+    }
+    assert!(cases > 0, "no fixture under {}", testdata.display());
+}
+
+pub const USER: &str = "0x00000000000000000000000000000000000000aa";
+pub const TOKEN_A: &str = "0x00000000000000000000000000000000000000a1";
+pub const TOKEN_B: &str = "0x00000000000000000000000000000000000000a2";
+pub const PAIR: &str = "0x00000000000000000000000000000000000000b1";
+pub const PAIR_AB: &str = "0x00000000000000000000000000000000000000b2";
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+
+pub fn synthetic_tokens() -> Tokens {
+    let token = |address: &str, symbol: &str| Token {
+        address: address.to_string(),
+        name: symbol.to_string(),
+        symbol: symbol.to_string(),
+        decimals: 18,
+        ..Default::default()
+    };
+
+    Tokens {
+        tokens: vec![
+            token(PROFILE.native_address, "NATIVE"),
+            token(TOKEN_A, "TKA"),
+            token(TOKEN_B, "TKB"),
+        ],
+    }
+}
+
+pub fn table_changes<'a>(
+    changes: &'a DatabaseChanges,
+    table: &'a str,
+) -> impl Iterator<Item = &'a pb::database::TableChange> {
+    changes
+        .table_changes
+        .iter()
+        .filter(move |change| change.table == table)
+}
+
+pub fn field_value(change: &pb::database::TableChange, name: &str) -> String {
+    change
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .unwrap_or_else(|| panic!("field {} not found on {}", name, change.table))
+        .new_value
+        .clone()
+}
+
+pub fn create_pair(pipeline: &Pipeline) -> DatabaseChanges {
+    let factory = format!("0x{}", PROFILE.factory_address_hex());
+    pipeline.process_block(
+        BlockBuilder::new(1, 1_600_000_000)
+            .transaction(
+                USER,
+                &factory,
+                vec![(
+                    &factory,
+                    vec![pair_created_log(PROFILE.native_address, TOKEN_A, PAIR, 1)],
+                )],
+            )
+            .build(),
+    )
+}
+
+pub fn decimal(value: String) -> BigDecimal {
+    BigDecimal::from_str(&value).unwrap()
+}
+
+#[test]
+fn pair_creation_and_mint() {
+    let pipeline = Pipeline::new();
+    pipeline.seed_tokens(synthetic_tokens());
+
+    let created = create_pair(&pipeline);
+
+    let pair = table_changes(&created, "pair").next().expect("pair row");
+    assert_eq!(pair.pk, PAIR);
+    assert_eq!(field_value(pair, "name"), "NATIVE-TKA");
+    assert_eq!(table_changes(&created, "token").count(), 2);
+
+    let minted = pipeline.process_block(
+        BlockBuilder::new(2, 1_600_000_002)
+            .transaction(
+                USER,
+                PAIR,
+                vec![(
+                    PAIR,
+                    vec![
+                        transfer_log("0x0000000000000000000000000000000000000000", USER, ONE),
+                        sync_log(2 * ONE, 4 * ONE),
+                        mint_log(USER, 2 * ONE, 4 * ONE),
+                    ],
+                )],
+            )
+            .build(),
+    );
+
+    let mint = table_changes(&minted, "mint").next().expect("mint row");
+    assert_eq!(field_value(mint, "pair"), PAIR);
+    assert_eq!(field_value(mint, "to"), USER);
+    assert_eq!(decimal(field_value(mint, "liquidity")), BigDecimal::from(1));
+    assert_eq!(decimal(field_value(mint, "amount_1")), BigDecimal::from(4));
+
+    let reserve1 = table_changes(&minted, "pair")
+        .find(|change| change.fields.iter().any(|field| field.name == "reserve_1"))
+        .expect("pair reserve update");
+    assert_eq!(decimal(field_value(reserve1, "reserve_1")), BigDecimal::from(4));
+}
@@ -0,0 +1,16 @@
+use substreams_ethereum::pb::eth;
+
+/// There is no node to talk to when running natively: every call fails, so
+/// the token metadata has to be part of the fixture's `tokens.binpb`.
+pub fn eth_call(calls: &eth::rpc::RpcCalls) -> eth::rpc::RpcResponses {
+    eth::rpc::RpcResponses {
+        responses: calls
+            .calls
+            .iter()
+            .map(|_| eth::rpc::RpcResponse {
+                raw: vec![],
+                failed: true,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,329 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use substreams::pb::substreams::{store_delta, StoreDelta};
+
+pub type Deltas = Vec<StoreDelta>;
+
+/// In-memory key/value store keeping the deltas of the block being processed,
+/// which gives `get_at`/`get_first` the same ordinal semantics as the runtime.
+#[derive(Default)]
+pub struct MemoryStore {
+    kv: BTreeMap<String, Vec<u8>>,
+    deltas: Vec<StoreDelta>,
+}
+
+pub type MemoryStoreRef = Rc<RefCell<MemoryStore>>;
+
+impl MemoryStore {
+    pub fn new_ref() -> MemoryStoreRef {
+        Rc::new(RefCell::new(MemoryStore::default()))
+    }
+
+    /// Starts a new block: the current values become the base state.
+    pub fn flush(&mut self) {
+        self.deltas.clear();
+    }
+
+    pub fn deltas(&self) -> Deltas {
+        self.deltas.clone()
+    }
+
+    fn get_at(&self, ord: u64, key: &str) -> Option<Vec<u8>> {
+        let mut value = self.kv.get(key).cloned();
+        for delta in self.deltas.iter().rev() {
+            if delta.ordinal <= ord || delta.key != key {
+                continue;
+            }
+            value = rollback(delta);
+        }
+        value
+    }
+
+    fn get_first(&self, key: &str) -> Option<Vec<u8>> {
+        match self.deltas.iter().find(|delta| delta.key == key) {
+            Some(delta) => rollback(delta),
+            None => self.kv.get(key).cloned(),
+        }
+    }
+
+    fn set(&mut self, ord: u64, key: String, value: Vec<u8>) {
+        let old_value = self.kv.insert(key.clone(), value.to_vec());
+        let operation = match old_value {
+            Some(_) => store_delta::Operation::Update,
+            None => store_delta::Operation::Create,
+        };
+
+        self.deltas.push(StoreDelta {
+            operation: operation as i32,
+            ordinal: ord,
+            key,
+            old_value: old_value.unwrap_or_default(),
+            new_value: value,
+        });
+    }
+
+    fn delete_prefix(&mut self, ord: u64, prefix: &str) {
+        let keys: Vec<String> = self
+            .kv
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        for key in keys {
+            let old_value = self.kv.remove(&key).unwrap();
+            self.deltas.push(StoreDelta {
+                operation: store_delta::Operation::Delete as i32,
+                ordinal: ord,
+                key,
+                old_value,
+                new_value: vec![],
+            });
+        }
+    }
+
+    fn get_last_string(&self, key: &str) -> Option<String> {
+        self.kv
+            .get(key)
+            .map(|value| String::from_utf8_lossy(value).to_string())
+    }
+}
+
+fn rollback(delta: &StoreDelta) -> Option<Vec<u8>> {
+    if delta.operation == store_delta::Operation::Create as i32 {
+        return None;
+    }
+    Some(delta.old_value.clone())
+}
+
+pub struct StoreGet {
+    store: MemoryStoreRef,
+}
+
+impl StoreGet {
+    pub fn new(store: &MemoryStoreRef) -> StoreGet {
+        StoreGet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn get_at(&self, ord: u64, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().get_at(ord, key)
+    }
+
+    pub fn get_last(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().kv.get(key).cloned()
+    }
+
+    pub fn get_first(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().get_first(key)
+    }
+}
+
+pub struct StoreSet {
+    store: MemoryStoreRef,
+}
+
+impl StoreSet {
+    pub fn new(store: &MemoryStoreRef) -> StoreSet {
+        StoreSet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn set(&self, ord: u64, key: String, value: &[u8]) {
+        self.store.borrow_mut().set(ord, key, value.to_vec());
+    }
+
+    pub fn set_many(&self, ord: u64, keys: &[String], value: &[u8]) {
+        for key in keys {
+            self.set(ord, key.to_string(), value);
+        }
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+pub struct StoreSetIfNotExists {
+    store: MemoryStoreRef,
+}
+
+impl StoreSetIfNotExists {
+    pub fn new(store: &MemoryStoreRef) -> StoreSetIfNotExists {
+        StoreSetIfNotExists {
+            store: store.clone(),
+        }
+    }
+
+    pub fn set_if_not_exists(&self, ord: u64, key: String, value: &[u8]) {
+        let mut store = self.store.borrow_mut();
+        if store.kv.contains_key(&key) {
+            return;
+        }
+        store.set(ord, key, value.to_vec());
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+pub struct StoreAddInt64 {
+    store: MemoryStoreRef,
+}
+
+impl StoreAddInt64 {
+    pub fn new(store: &MemoryStoreRef) -> StoreAddInt64 {
+        StoreAddInt64 {
+            store: store.clone(),
+        }
+    }
+
+    pub fn add(&self, ord: u64, key: String, value: i64) {
+        let mut store = self.store.borrow_mut();
+        let current = match store.get_last_string(&key) {
+            None => 0,
+            Some(current) => i64::from_str(&current).unwrap(),
+        };
+        store.set(ord, key, (current + value).to_string().into_bytes());
+    }
+
+    pub fn add_many(&self, ord: u64, keys: &[String], value: i64) {
+        for key in keys {
+            self.add(ord, key.to_string(), value);
+        }
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+pub struct StoreAddBigFloat {
+    store: MemoryStoreRef,
+}
+
+impl StoreAddBigFloat {
+    pub fn new(store: &MemoryStoreRef) -> StoreAddBigFloat {
+        StoreAddBigFloat {
+            store: store.clone(),
+        }
+    }
+
+    pub fn add(&self, ord: u64, key: String, value: &BigDecimal) {
+        let mut store = self.store.borrow_mut();
+        let current = match store.get_last_string(&key) {
+            None => BigDecimal::default(),
+            Some(current) => BigDecimal::from_str(&current).unwrap(),
+        };
+        store.set(ord, key, (current + value).to_string().into_bytes());
+    }
+
+    pub fn add_many(&self, ord: u64, keys: &[String], value: &BigDecimal) {
+        for key in keys {
+            self.add(ord, key.to_string(), value);
+        }
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+pub struct StoreAppend {
+    store: MemoryStoreRef,
+}
+
+impl StoreAppend {
+    pub fn new(store: &MemoryStoreRef) -> StoreAppend {
+        StoreAppend {
+            store: store.clone(),
+        }
+    }
+
+    pub fn append(&self, ord: u64, key: String, item: &str) {
+        let mut store = self.store.borrow_mut();
+        let mut value = store.kv.get(&key).cloned().unwrap_or_default();
+        value.extend_from_slice(item.as_bytes());
+        store.set(ord, key, value);
+    }
+}
+
+pub struct StoreMaxBigFloat {
+    store: MemoryStoreRef,
+}
+
+impl StoreMaxBigFloat {
+    pub fn new(store: &MemoryStoreRef) -> StoreMaxBigFloat {
+        StoreMaxBigFloat {
+            store: store.clone(),
+        }
+    }
+
+    pub fn max(&self, ord: u64, key: String, value: &BigDecimal) {
+        let mut store = self.store.borrow_mut();
+        if let Some(current) = store.get_last_string(&key) {
+            if &BigDecimal::from_str(&current).unwrap() >= value {
+                return;
+            }
+        }
+        store.set(ord, key, value.to_string().into_bytes());
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+pub struct StoreMinBigFloat {
+    store: MemoryStoreRef,
+}
+
+impl StoreMinBigFloat {
+    pub fn new(store: &MemoryStoreRef) -> StoreMinBigFloat {
+        StoreMinBigFloat {
+            store: store.clone(),
+        }
+    }
+
+    pub fn min(&self, ord: u64, key: String, value: &BigDecimal) {
+        let mut store = self.store.borrow_mut();
+        if let Some(current) = store.get_last_string(&key) {
+            if &BigDecimal::from_str(&current).unwrap() <= value {
+                return;
+            }
+        }
+        store.set(ord, key, value.to_string().into_bytes());
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+#[test]
+fn get_at_ignores_later_ordinals() {
+    let store = MemoryStore::new_ref();
+    let output = StoreSet::new(&store);
+    output.set(1, "key".to_string(), b"one");
+    store.borrow_mut().flush();
+
+    output.set(10, "key".to_string(), b"two");
+    output.set(20, "other".to_string(), b"three");
+
+    let input = StoreGet::new(&store);
+    let key = "key".to_string();
+    let other = "other".to_string();
+    assert_eq!(input.get_at(5, &key), Some(b"one".to_vec()));
+    assert_eq!(input.get_at(10, &key), Some(b"two".to_vec()));
+    assert_eq!(input.get_first(&key), Some(b"one".to_vec()));
+    assert_eq!(input.get_last(&key), Some(b"two".to_vec()));
+    assert_eq!(input.get_at(19, &other), None);
+    assert_eq!(input.get_at(20, &other), Some(b"three".to_vec()));
+}
@@ -0,0 +1,354 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use substreams::pb::substreams::{store_delta, Clock, StoreDelta};
+
+use crate::buckets;
+use crate::errors::PcsError;
+use crate::keys::{Denomination, StoreKey};
+use crate::{store, utils};
+
+/// Time a TWAP is weighted with, wall clock seconds or blocks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Seconds,
+    Blocks,
+}
+
+pub const UNITS: &[Unit] = &[Unit::Seconds, Unit::Blocks];
+
+impl Unit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Seconds => "seconds",
+            Unit::Blocks => "blocks",
+        }
+    }
+
+    pub fn now(&self, clock: &Clock) -> u64 {
+        match self {
+            Unit::Seconds => clock.timestamp.as_ref().unwrap().seconds as u64,
+            Unit::Blocks => clock.number,
+        }
+    }
+
+    /// Key of `store_twap_cumulative` holding the offset of the cumulative
+    /// price of a token, see `cumulative`.
+    pub fn cumulative_key(&self, token: &str, denomination: &str) -> String {
+        format!("twap_cum:{}:{}:{}", self.as_str(), token, denomination)
+    }
+
+    /// Key of `store_twap_cumulative` holding when a token was first priced.
+    pub fn first_key(&self, token: &str, denomination: &str) -> String {
+        format!("twap_first:{}:{}:{}", self.as_str(), token, denomination)
+    }
+
+    /// Key of `store_twap_changes` holding when the price of a token last
+    /// changed.
+    pub fn changed_key(&self, token: &str, denomination: &str) -> String {
+        format!("twap_changed:{}:{}:{}", self.as_str(), token, denomination)
+    }
+}
+
+/// A rolling TWAP window of `span` units, cut in `buckets` equal buckets. The
+/// average runs from the start of the bucket `buckets` buckets before the
+/// current one, so it covers the span plus the part of the current bucket
+/// already elapsed.
+pub struct Window {
+    pub name: &'static str,
+    pub unit: Unit,
+    pub span: u64,
+    pub buckets: u64,
+}
+
+pub const WINDOWS: &[Window] = &[
+    Window {
+        name: "30m",
+        unit: Unit::Seconds,
+        span: 30 * 60,
+        buckets: 30,
+    },
+    Window {
+        name: "1h",
+        unit: Unit::Seconds,
+        span: 60 * 60,
+        buckets: 60,
+    },
+    Window {
+        name: "24h",
+        unit: Unit::Seconds,
+        span: 24 * 60 * 60,
+        buckets: 96,
+    },
+    Window {
+        name: "1000b",
+        unit: Unit::Blocks,
+        span: 1000,
+        buckets: 50,
+    },
+];
+
+impl Window {
+    fn bucket_length(&self) -> u64 {
+        self.span / self.buckets
+    }
+
+    /// Id of the bucket `time` falls in, bucket ids are contiguous.
+    pub fn bucket_of(&self, time: u64) -> u64 {
+        time / self.bucket_length()
+    }
+
+    pub fn bucket(&self, clock: &Clock) -> u64 {
+        self.bucket_of(self.unit.now(clock))
+    }
+
+    pub fn bucket_start(&self, bucket: u64) -> u64 {
+        bucket * self.bucket_length()
+    }
+
+    /// First bucket of the window ending in `bucket`.
+    pub fn start(&self, bucket: u64) -> u64 {
+        bucket.saturating_sub(self.buckets)
+    }
+
+    /// Key of `store_buckets` holding the current bucket id of the window.
+    pub fn key(&self) -> String {
+        format!("bucket:twap_{}", self.name)
+    }
+
+    /// Buckets whose observations are deleted when the window moves from
+    /// `previous` to `current`.
+    pub fn expired(&self, previous: i64, current: i64) -> std::ops::RangeInclusive<i64> {
+        let expired = buckets::expired_range(previous, current, self.buckets as i64 + 1);
+        (*expired.start()).max(0)..=*expired.end()
+    }
+
+    pub fn observation_prefix(&self, bucket: u64) -> String {
+        format!("twap_obs:{}:{}:", self.name, bucket)
+    }
+
+    /// Key of `store_twap_observations` holding the cumulative price of a
+    /// token at the start of `bucket`.
+    pub fn observation_key(&self, bucket: u64, token: &str, denomination: &str) -> String {
+        format!("{}{}:{}", self.observation_prefix(bucket), token, denomination)
+    }
+
+    pub fn twap_key(&self, token: &str, denomination: &str) -> String {
+        format!("twap:{}:{}:{}", self.name, token, denomination)
+    }
+}
+
+/// Token and denomination a `store_prices` key is averaged as, the USD price
+/// of the native token is averaged as the `usd` token priced in native.
+pub fn sampled_token(price_key: &str) -> Option<(String, Denomination)> {
+    match StoreKey::parse(price_key) {
+        Some(StoreKey::DerivedPrice {
+            token,
+            denomination,
+        }) => Some((token, denomination)),
+        Some(StoreKey::NativeUsdPrice) => Some(("usd".to_string(), Denomination::Native)),
+        _ => None,
+    }
+}
+
+/// A change of a price averaged by the TWAP stores.
+pub struct PriceChange {
+    pub token: String,
+    pub denomination: &'static str,
+    /// `None` when the token is priced for the first time.
+    pub previous: Option<BigDecimal>,
+    pub price: BigDecimal,
+}
+
+/// Price change of a `store_prices` delta, `None` for the keys not averaged.
+pub fn price_change(delta: &StoreDelta) -> Result<Option<PriceChange>, PcsError> {
+    if delta.operation == store_delta::Operation::Delete as i32 {
+        return Ok(None);
+    }
+    let (token, denomination) = match sampled_token(&delta.key) {
+        Some(sampled) => sampled,
+        None => return Ok(None),
+    };
+
+    let previous = match delta.operation == store_delta::Operation::Create as i32 {
+        true => None,
+        false => Some(utils::decode_big_decimal(&delta.key, &delta.old_value)?),
+    };
+    Ok(Some(PriceChange {
+        token,
+        denomination: denomination.as_str(),
+        previous,
+        price: utils::decode_big_decimal(&delta.key, &delta.new_value)?,
+    }))
+}
+
+/// Amount `store_twap_cumulative` adds to the offset of a token when its price
+/// moves from `previous` to `price` at `time`.
+///
+/// Like the price accumulators of Uniswap's oracle, the cumulative price of a
+/// token is the integral of its price over time, from 0 when first priced.
+/// With prices only changing at `t1 < t2 < ...`, it is `price * time + offset`
+/// where the offset sums `t * (previous - price)` over the changes, which lets
+/// an add store maintain it without reading the time of the last change.
+pub fn offset_change(time: u64, previous: &BigDecimal, price: &BigDecimal) -> BigDecimal {
+    (previous - price) * BigDecimal::from(time)
+}
+
+/// Cumulative price at `time` of a token priced `price` since its last change.
+pub fn cumulative_price(price: &BigDecimal, time: u64, offset: &BigDecimal) -> BigDecimal {
+    price * BigDecimal::from(time) + offset
+}
+
+/// Average price over `elapsed` units, given the cumulative prices at both
+/// ends, the current price when no time elapsed.
+pub fn average(
+    cumulative: &BigDecimal,
+    since_cumulative: &BigDecimal,
+    elapsed: &BigDecimal,
+    price: &BigDecimal,
+) -> BigDecimal {
+    if elapsed <= &utils::zero_big_decimal() {
+        return price.clone();
+    }
+    ((cumulative - since_cumulative) / elapsed).with_prec(100)
+}
+
+/// Times are stored as their decimal representation.
+pub fn decode_time(key: &str, bytes: &[u8]) -> Result<u64, PcsError> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|value| u64::from_str(value).ok())
+        .ok_or_else(|| PcsError::Decode {
+            key: key.to_string(),
+            reason: format!("{:?} is not a time", String::from_utf8_lossy(bytes)),
+        })
+}
+
+/// When the price of a token last changed before the block and the offset of
+/// its cumulative price then, `None` for a token not priced before.
+pub fn state_before(
+    unit: Unit,
+    change: &PriceChange,
+    changes: &store::StoreGet,
+    cumulative: &store::StoreGet,
+) -> Result<Option<(u64, BigDecimal)>, PcsError> {
+    let changed_key = unit.changed_key(&change.token, change.denomination);
+    let cumulative_key = unit.cumulative_key(&change.token, change.denomination);
+    match (changes.get_first(&changed_key), cumulative.get_first(&cumulative_key)) {
+        (Some(changed_at), Some(offset)) => Ok(Some((
+            decode_time(&changed_key, &changed_at)?,
+            utils::decode_big_decimal(&cumulative_key, &offset)?,
+        ))),
+        _ => Ok(None),
+    }
+}
+
+/// Average price of a token priced in the block over `window`, from the
+/// cumulative price at the start of the window, or since the token was first
+/// priced when it is more recent.
+pub fn window_average(
+    window: &Window,
+    clock: &Clock,
+    change: &PriceChange,
+    cumulative: &store::StoreGet,
+    observations: &store::StoreGet,
+) -> Result<BigDecimal, PcsError> {
+    let time = window.unit.now(clock);
+    let cumulative_key = window.unit.cumulative_key(&change.token, change.denomination);
+    let offset = match cumulative.get_last(&cumulative_key) {
+        Some(offset) => utils::decode_big_decimal(&cumulative_key, &offset)?,
+        None => return Err(PcsError::MissingPrice(cumulative_key)),
+    };
+    let now = cumulative_price(&change.price, time, &offset);
+
+    let start = window.start(window.bucket_of(time));
+    let observation_key = window.observation_key(start, &change.token, change.denomination);
+    let (since, since_cumulative) = match observations.get_last(&observation_key) {
+        Some(observation) => (
+            BigDecimal::from(window.bucket_start(start)),
+            utils::decode_big_decimal(&observation_key, &observation)?,
+        ),
+        // the cumulative price is zero when the token is first priced
+        None => {
+            let first_key = window.unit.first_key(&change.token, change.denomination);
+            match cumulative.get_last(&first_key) {
+                Some(first) => (utils::decode_big_decimal(&first_key, &first)?, utils::zero_big_decimal()),
+                None => return Err(PcsError::MissingPrice(first_key)),
+            }
+        }
+    };
+
+    Ok(average(&now, &since_cumulative, &(BigDecimal::from(time) - since), &change.price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block::{sync_log, BlockBuilder};
+    use crate::testing::store::StoreGet;
+    use crate::testing::{decimal, Pipeline, ONE, PAIR, TOKEN_A, USER};
+
+    #[test]
+    fn cumulative_prices_integrate_price_changes() {
+        let price = |value: &str| BigDecimal::from_str(value).unwrap();
+
+        // 2 from 10, 6 from 40, 3 from 50
+        let mut offset = offset_change(10, &price("0"), &price("2"));
+        assert_eq!(cumulative_price(&price("2"), 10, &offset), price("0"));
+        offset += offset_change(40, &price("2"), &price("6"));
+        offset += offset_change(50, &price("6"), &price("3"));
+
+        let at_40 = cumulative_price(&price("2"), 40, &(offset_change(10, &price("0"), &price("2"))));
+        let at_60 = cumulative_price(&price("3"), 60, &offset);
+        assert_eq!(at_40, price("60"));
+        assert_eq!(at_60, price("150"));
+        assert_eq!(average(&at_60, &at_40, &price("20"), &price("3")), price("4.5"));
+        assert_eq!(average(&at_60, &at_60, &price("0"), &price("3")), price("3"));
+    }
+
+    #[test]
+    fn window_keeps_the_bucket_before_it() {
+        let window = &WINDOWS[0];
+
+        assert_eq!(window.start(100), 70);
+        assert_eq!(window.start(10), 0);
+        assert_eq!(window.expired(100, 101).collect::<Vec<_>>(), vec![70]);
+        assert_eq!(window.expired(100, 104).collect::<Vec<_>>(), vec![70, 71, 72, 73]);
+        assert_eq!(window.expired(100, 200).collect::<Vec<_>>(), (70..=100).collect::<Vec<_>>());
+        assert!(window.expired(10, 11).next().is_none());
+        assert_eq!(sampled_token("dprice:usd:native").map(|(token, _)| token), Some("usd".to_string()));
+        assert!(sampled_token("droute:0xabc:native").is_none());
+    }
+
+    #[test]
+    fn twap_windows_follow_price_changes() {
+        const START: i64 = 1_600_000_020;
+
+        let pipeline = Pipeline::with_pair();
+        let twap = |window: &str| {
+            let key = format!("twap:{}:{}:native", window, TOKEN_A);
+            let value = StoreGet::new(&pipeline.twap).get_last(&key).expect(&key);
+            decimal(String::from_utf8(value).unwrap())
+        };
+        let sync = |number: u64, timestamp: i64, reserve1: u128| {
+            BlockBuilder::new(number, timestamp)
+                .transaction(USER, PAIR, vec![(PAIR, vec![sync_log(2 * ONE, reserve1)])])
+                .build()
+        };
+
+        // 1 TKA is worth 0.5 NATIVE for an hour, then 1
+        pipeline.process_block(sync(2, START, 4 * ONE));
+        pipeline.process_block(BlockBuilder::new(3, START + 60).build());
+        assert!(pipeline.twap.borrow().deltas().is_empty());
+        assert!(pipeline.twap_observations.borrow().deltas().is_empty());
+
+        pipeline.process_block(sync(4, START + 3600, 2 * ONE));
+        assert_eq!(twap("30m"), decimal("0.5".to_string()));
+        assert_eq!(twap("24h"), decimal("0.5".to_string()));
+
+        // the new price weighs from its change on
+        pipeline.process_block(sync(5, START + 4500, ONE));
+        assert_eq!(twap("30m"), decimal("0.75".to_string()));
+        assert_eq!(twap("24h"), decimal("0.6".to_string()));
+    }
+}
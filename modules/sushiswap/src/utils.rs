@@ -5,49 +5,13 @@ use std::str::FromStr;
 use bigdecimal::{BigDecimal, One, Zero};
 use num_bigint::BigUint;
 use pad::PadStr;
-use substreams::{proto, store};
+use substreams::{log, proto};
 
+use crate::errors::PcsError;
+use crate::keys::{Denomination, Field, StoreKey};
 use crate::pb;
-
-pub const NATIVE_ADDRESS: &str = "0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270";
-pub const WMATIC_USDC_PAIR: &str = "0xcd353f79d9fade311fc3119b841e1f456b54e858";
-pub const WMATIC_USDT_PAIR: &str = "0x55ff76bffc3cdd9d5fdbbc2ece4528ecce45047e";
-pub const BUSD_PRICE_KEY: &str =
-    "price:0xe9e7cea3dedca5984780bafc599bd69add087d56:0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c";
-pub const USDT_PRICE_KEY: &str =
-    "price:0x55d398326f99059ff775485246999027b3197955:0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c";
-
-pub const SUSHI_ADDRESS: &str = "0x0b3f868e0be5597d5db7feb59e1cadbb0fdda50a";
-pub const WETH_ADDRESS: &str = "0x7ceb23fd6bc0add59e62ac25578270cff1b9f619";
-pub const WBTC_ADDRESS: &str = "0x1bfd67037b42cf73acf2047067bd4f2c47d9bfd6";
-pub const USDC_ADDRESS: &str = "0x2791bca1f2de4661ed88a30c99a7a9449aa84174";
-pub const USDT_ADDRESS: &str = "0xc2132d05d31c914a87c6611c10748aeb04b58e8f";
-pub const DAI_ADDRESS: &str = "0x8f3cf7ad23cd3cadbd9735aff958023239c6a063";
-pub const AAVE_ADDRESS: &str = "0xd6df932a45c0f255f85145f286ea0b292b21c90b";
-pub const FRAX_ADDRESS: &str = "0x45c32fa6df82ead1e2ef74d17b76547eddfaff89";
-pub const BCT_ADDRESS: &str = "0x2f800db0fdb5223b3c3f354886d907a671414a7f";
-pub const AURUM_ADDRESS: &str = "0x34d4ab47bee066f361fa52d792e69ac7bd05ee23";
-pub const MSU_ADDRESS: &str = "0xe8377a076adabb3f9838afb77bee96eac101ffb1";
-pub const DMAGIC_ADDRESS: &str = "0x61daecab65ee2a1d5b6032df030f3faa3d116aa7";
-pub const NDEFI_ADDRESS: &str = "0xd3f07ea86ddf7baebefd49731d7bbd207fedc53b";
-
-
-const WHITELIST_TOKENS: [&str; 14] = [
-    NATIVE_ADDRESS,
-    SUSHI_ADDRESS,
-    WETH_ADDRESS,
-    WBTC_ADDRESS,
-    USDC_ADDRESS,
-    USDT_ADDRESS,
-    DAI_ADDRESS,
-    AAVE_ADDRESS,
-    FRAX_ADDRESS,
-    BCT_ADDRESS,
-    AURUM_ADDRESS,
-    MSU_ADDRESS,
-    DMAGIC_ADDRESS,
-    NDEFI_ADDRESS,
-];
+use crate::profile::PROFILE;
+use crate::store;
 
 pub fn convert_token_to_decimal(amount: &[u8], decimals: &u64) -> BigDecimal {
     let big_uint_amount = BigUint::from_bytes_be(amount);
@@ -59,195 +23,196 @@ pub fn convert_token_to_decimal(amount: &[u8], decimals: &u64) -> BigDecimal {
 }
 
 pub fn get_token_price(bf0: BigDecimal, bf1: BigDecimal) -> BigDecimal {
+    if bf1.is_zero() {
+        return zero_big_decimal(); // an emptied pair has no price
+    }
     return bf0.div(bf1).with_prec(100);
 }
 
-pub fn generate_tokens_key(token0: &str, token1: &str) -> String {
-    if token0 > token1 {
-        return format!("{}:{}", token1, token0);
-    }
-    return format!("{}:{}", token0, token1);
-}
+/// USD price of the native token, from the stable pairs' reserves. Each stable
+/// pair's price is weighted by the share of native liquidity it holds. Stable
+/// pairs not created or synced yet are left out.
+pub fn compute_usd_price(
+    pairs_store: &store::StoreGet,
+    reserves_store: &store::StoreGet,
+    reserve: &pb::pcs::Reserve,
+) -> Result<BigDecimal, PcsError> {
+    let mut stable_reserves = vec![];
+    let mut total_liquidity_native = zero_big_decimal();
 
-// not sure about the & in front of reserve
-pub fn compute_usd_price(reserves_store: &store::StoreGet, reserve: &pb::pcs::Reserve) -> BigDecimal {
-    let wmatic_usdc_reserve_big_decimal;
-    let wmatic_usdt_reserve_big_decimal;
-
-    match reserves_store.get_at(
-        reserve.log_ordinal,
-        &format!("reserve:{}:{}", WMATIC_USDC_PAIR, NATIVE_ADDRESS),
-    ) {
-        None => wmatic_usdc_reserve_big_decimal = zero_big_decimal(),
-        Some(reserve_bytes) => {
-            wmatic_usdc_reserve_big_decimal = decode_reserve_bytes_to_big_decimal(reserve_bytes)
+    for stable_pair in PROFILE.stable_pairs {
+        let pair_key = StoreKey::Pair {
+            pair: stable_pair.address.to_string(),
         }
-    }
+        .encode();
+        let pair: pb::pcs::Pair = match pairs_store.get_at(reserve.log_ordinal, &pair_key) {
+            None => continue,
+            Some(pair_bytes) => decode_proto(&pair_key, &pair_bytes)?,
+        };
 
-    match reserves_store.get_at(
-        reserve.log_ordinal,
-        &format!("reserve:{}:{}", WMATIC_USDT_PAIR, NATIVE_ADDRESS),
-    ) {
-        None => wmatic_usdt_reserve_big_decimal = zero_big_decimal(),
-        Some(reserve_bytes) => {
-            wmatic_usdt_reserve_big_decimal = decode_reserve_bytes_to_big_decimal(reserve_bytes)
+        let native_reserve = get_reserve(
+            reserves_store,
+            reserve.log_ordinal,
+            &pair,
+            PROFILE.native_address,
+        )?;
+        let stablecoin_reserve = get_reserve(
+            reserves_store,
+            reserve.log_ordinal,
+            &pair,
+            stable_pair.stablecoin_address,
+        )?;
+        if native_reserve.eq(&zero_big_decimal()) {
+            continue;
         }
-    }
 
-    let mut total_liquidity_native = zero_big_decimal();
-    total_liquidity_native = total_liquidity_native
-        .clone()
-        .add(wmatic_usdc_reserve_big_decimal.clone());
-    total_liquidity_native = total_liquidity_native
-        .clone()
-        .add(wmatic_usdt_reserve_big_decimal.clone());
+        total_liquidity_native = total_liquidity_native.add(native_reserve.clone());
+        stable_reserves.push((native_reserve, stablecoin_reserve));
+    }
 
     let zero = zero_big_decimal();
 
     if total_liquidity_native.eq(&zero) {
-        return zero;
+        return Ok(zero);
     }
 
-    // if wmatic_usdc_reserve_big_decimal.eq(&zero) {
-    //     return match reserves_store.get_at(
-    //         reserve.log_ordinal,
-    //         &USDT_PRICE_KEY.to_string(),
-    //     ) {
-    //         None => zero,
-    //         Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-    //     };
-    // } else if wmatic_usdt_reserve_big_decimal.eq(&zero) {
-    //     return match reserves_store.get_at(
-    //         reserve.log_ordinal,
-    //         &BUSD_PRICE_KEY.to_string(),
-    //     ) {
-    //         None => zero,
-    //         Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-    //     };
-    // }
-
-    // both found and not equal to zero, average out
-    let usdc_weight = wmatic_usdc_reserve_big_decimal
-        .div(total_liquidity_native.clone())
-        .with_prec(100);
-    let usdt_weight = wmatic_usdt_reserve_big_decimal
-        .div(total_liquidity_native)
-        .with_prec(100);
-
-    let busd_price = match reserves_store.get_at(
-        reserve.log_ordinal,
-        &USDT_PRICE_KEY.to_string(),
-    ) {
-        None => zero_big_decimal(),
-        Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-    };
+    let mut usd_price = zero_big_decimal();
+    for (native_reserve, stablecoin_reserve) in stable_reserves {
+        let weight = native_reserve
+            .clone()
+            .div(total_liquidity_native.clone())
+            .with_prec(100);
+        let stablecoin_price = stablecoin_reserve.div(native_reserve).with_prec(100);
+
+        usd_price = usd_price.add(stablecoin_price.mul(weight).with_prec(100));
+    }
 
-    let usdt_price = match reserves_store.get_at(
-        reserve.log_ordinal,
-        &BUSD_PRICE_KEY.to_string(),
-    ) {
-        None => zero_big_decimal(),
-        Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-    };
+    Ok(usd_price)
+}
 
-    let usdc_price_over_weight = busd_price.mul(usdc_weight).with_prec(100);
-    let usdt_price_over_weight = usdt_price.mul(usdt_weight).with_prec(100);
+pub fn zero_big_decimal() -> BigDecimal {
+    BigDecimal::zero().with_prec(100)
+}
 
-    let mut usd_price = zero_big_decimal();
-    usd_price = usd_price.add(usdc_price_over_weight);
-    usd_price = usd_price.add(usdt_price_over_weight);
+/// Amount of a token a swap moved in and out, see `event_decimal`.
+pub fn compute_amount_total(pair_address: &str, amount_out: &str, amount_in: &str) -> Option<BigDecimal> {
+    let amount_out = event_decimal(pair_address, "swap.amount_out", amount_out)?;
+    let amount_in = event_decimal(pair_address, "swap.amount_in", amount_in)?;
 
-    usd_price
+    Some(amount_out.add(amount_in))
 }
 
-pub fn find_native_price_per_token(
-    log_ordinal: &u64,
-    erc20_token_address: &str,
-    pairs_store: &store::StoreGet,
-    reserves_store: &store::StoreGet,
-) -> Option<BigDecimal> {
-    if erc20_token_address.eq(NATIVE_ADDRESS) {
-        return Some(one_big_decimal()); // native price of a native is always 1
+pub fn get_last_token(
+    tokens: &store::StoreGet,
+    token_address: &str,
+) -> Result<pb::tokens::Token, PcsError> {
+    let key = StoreKey::Token {
+        token: token_address.to_string(),
     }
-
-    let direct_to_native_price = match reserves_store.get_last(
-        &format!("price:{}:{}", NATIVE_ADDRESS, erc20_token_address),
-    ) {
-        None => zero_big_decimal(),
-        Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-    };
-
-    if direct_to_native_price.ne(&zero_big_decimal()) {
-        return Some(direct_to_native_price);
+    .encode();
+    match tokens.get_last(&key) {
+        None => Err(PcsError::MissingToken(token_address.to_string())),
+        Some(token_bytes) => decode_proto(&key, &token_bytes),
     }
+}
 
-    // loop all whitelist for a matching pair
-    for major_token in WHITELIST_TOKENS {
-        let tiny_to_major_pair = match pairs_store.get_at(
-            *log_ordinal,
-            &format!(
-                "tokens:{}",
-                generate_tokens_key(erc20_token_address, major_token)
-            ),
-        ) {
-            None => continue,
-            Some(pair_bytes) => decode_pair_bytes(pair_bytes),
-        };
-
-        let major_to_native_price = match reserves_store.get_at(
-            *log_ordinal,
-            &format!("price:{}:{}", major_token, NATIVE_ADDRESS),
-        ) {
-            None => continue,
-            Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-        };
+/// Both tokens of `pair`, the first missing one failing.
+pub fn get_last_pair_tokens(
+    tokens: &store::StoreGet,
+    pair: &pb::pcs::Pair,
+) -> Result<(pb::tokens::Token, pb::tokens::Token), PcsError> {
+    Ok((
+        get_last_token(tokens, &pair.token0_address)?,
+        get_last_token(tokens, &pair.token1_address)?,
+    ))
+}
 
-        let tiny_to_major_price = match reserves_store.get_at(
-            *log_ordinal,
-            &format!("price:{}:{}", erc20_token_address, major_token),
-        ) {
-            None => continue,
-            Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes),
-        };
+pub fn get_last_pair(pairs: &store::StoreGet, pair_address: &str) -> Result<pb::pcs::Pair, PcsError> {
+    let key = StoreKey::Pair {
+        pair: pair_address.to_string(),
+    }
+    .encode();
+    match pairs.get_last(&key) {
+        None => Err(PcsError::MissingPair(pair_address.to_string())),
+        Some(pair_bytes) => decode_proto(&key, &pair_bytes),
+    }
+}
 
-        let major_reserve =
-            //todo: not sure about tiny_to_major_pair.erc20_token0.addr, maybe its the token1 ?
-            match reserves_store.get_at(*log_ordinal, &format!("reserve:{}:{}", tiny_to_major_pair, major_token)) {
-                None => continue,
-                Some(reserve_bytes) => decode_reserve_bytes_to_big_decimal(reserve_bytes)
-            };
-
-        let native_reserve_in_major_pair = major_to_native_price.clone().mul(major_reserve);
-        // We're checking for half of it, because `reserves_native` would have both sides in it.
-        // We could very well check the other reserve's native value, would be a bit more heavy, but we can do it.
-        if native_reserve_in_major_pair.le(&BigDecimal::from_str("5").unwrap()) {
-            // todo: little or big ?
-            continue; // Not enough liquidity
+/// USD value of both reserves of `pair_address` as last written by
+/// `store_prices`, `None` while a token has no USD price.
+pub fn get_last_reserve_usd(
+    pairs_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    pair_address: &str,
+) -> Option<BigDecimal> {
+    let pair: pb::pcs::Pair = get_last_pair(pairs_store, pair_address).ok()?;
+
+    let mut reserve_usd = zero_big_decimal();
+    for token in [&pair.token0_address, &pair.token1_address] {
+        let key = StoreKey::DerivedReserve {
+            pair: pair.address.clone(),
+            token: token.clone(),
+            denomination: Denomination::Usd,
         }
-
-        return Some(tiny_to_major_price.mul(major_to_native_price));
+        .encode();
+        let reserve_bytes = prices_store.get_last(&key)?;
+        reserve_usd = reserve_usd.add(decode_big_decimal(&key, &reserve_bytes).ok()?);
     }
 
-    return None;
+    Some(reserve_usd)
 }
 
-pub fn zero_big_decimal() -> BigDecimal {
-    BigDecimal::zero().with_prec(100)
+/// Price written by `store_prices` under `key`, as of `log_ordinal`.
+pub fn get_price_at(
+    prices_store: &store::StoreGet,
+    log_ordinal: u64,
+    key: &StoreKey,
+) -> Result<BigDecimal, PcsError> {
+    let key = key.encode();
+    match prices_store.get_at(log_ordinal, &key) {
+        None => Err(PcsError::MissingPrice(key)),
+        Some(price_bytes) => decode_big_decimal(&key, &price_bytes),
+    }
 }
 
-pub fn compute_amount_total(amount1: String, amount2: String) -> BigDecimal {
-    let amount1_bd: BigDecimal = BigDecimal::from_str(amount1.as_str()).unwrap();
-    let amount2_bd: BigDecimal = BigDecimal::from_str(amount2.as_str()).unwrap();
+pub fn decode_proto<T: Default + prost::Message>(key: &str, bytes: &Vec<u8>) -> Result<T, PcsError> {
+    proto::decode(bytes).map_err(|error| PcsError::Decode {
+        key: key.to_string(),
+        reason: error.to_string(),
+    })
+}
 
-    amount1_bd.add(amount2_bd)
+/// Decimals are stored as their string representation.
+pub fn decode_big_decimal(key: &str, bytes: &[u8]) -> Result<BigDecimal, PcsError> {
+    match str::from_utf8(bytes) {
+        Ok(value) => parse_big_decimal(key, value),
+        Err(_) => Err(PcsError::InvalidDecimal {
+            key: key.to_string(),
+            value: String::from_utf8_lossy(bytes).to_string(),
+        }),
+    }
 }
 
-pub fn get_last_token(tokens: &store::StoreGet, token_address: &str) -> pb::tokens::Token {
-    proto::decode(&tokens.get_last(&format!("token:{}", token_address)).unwrap())
-        .unwrap()
+pub fn parse_big_decimal(key: &str, value: &str) -> Result<BigDecimal, PcsError> {
+    BigDecimal::from_str(value).map_err(|_| PcsError::InvalidDecimal {
+        key: key.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Decimal `field` of an event of `pair_address`, `None` once logged when it is
+/// not a decimal: the keys written from it are skipped.
+pub fn event_decimal(pair_address: &str, field: &str, value: &str) -> Option<BigDecimal> {
+    match parse_big_decimal(field, value) {
+        Ok(decimal) => Some(decimal),
+        Err(error) => {
+            log::info!("skipping {} of pair {}: {}", field, pair_address, error);
+            None
+        }
+    }
 }
 
-fn one_big_decimal() -> BigDecimal {
+pub fn one_big_decimal() -> BigDecimal {
     BigDecimal::one().with_prec(100)
 }
 
@@ -261,14 +226,87 @@ fn divide_by_decimals(big_float_amount: BigDecimal, decimals: &u64) -> BigDecima
     return big_float_amount.div(bd).with_prec(100);
 }
 
-fn decode_pair_bytes(pair_bytes: Vec<u8>) -> String {
-    let pair_from_store_decoded = str::from_utf8(pair_bytes.as_slice()).unwrap();
-    return pair_from_store_decoded.to_string();
+/// Reserve of `token_address` in `pair` as written by `store_reserves`, zero
+/// when the pair had no sync yet.
+fn get_reserve(
+    reserves_store: &store::StoreGet,
+    log_ordinal: u64,
+    pair: &pb::pcs::Pair,
+    token_address: &str,
+) -> Result<BigDecimal, PcsError> {
+    let field = match pair.token0_address == token_address {
+        true => Field::Reserve0,
+        false => Field::Reserve1,
+    };
+    let key = StoreKey::Reserve {
+        pair: pair.address.clone(),
+        token: token_address.to_string(),
+        field,
+    };
+    let key = key.encode();
+    match reserves_store.get_at(log_ordinal, &key) {
+        None => Ok(zero_big_decimal()),
+        Some(reserve_bytes) => Ok(decode_big_decimal(&key, &reserve_bytes)?.with_prec(100)),
+    }
 }
 
-fn decode_reserve_bytes_to_big_decimal(reserve_bytes: Vec<u8>) -> BigDecimal {
-    let reserve_from_store_decoded = str::from_utf8(reserve_bytes.as_slice()).unwrap();
-    return BigDecimal::from_str(reserve_from_store_decoded)
-        .unwrap()
-        .with_prec(100);
+#[cfg(test)]
+mod tests {
+    use crate::keys::{Field, StoreKey};
+    use crate::pb;
+    use crate::testing::block::BlockBuilder;
+    use crate::testing::store::{MemoryStore, StoreAddBigFloat, StoreGet};
+    use crate::testing::{clock, PAIR, TOKEN_A, TOKEN_B};
+
+    #[test]
+    fn invalid_event_decimals_skip_their_keys() {
+        let volumes = MemoryStore::new_ref();
+        let swap = pb::pcs::Swap {
+            amount0_in: "1".to_string(),
+            amount0_out: "0".to_string(),
+            amount1_in: "0".to_string(),
+            amount1_out: "not a decimal".to_string(),
+            amount_native: "5".to_string(),
+            amount_usd: "10".to_string(),
+            trade_volume0: "1".to_string(),
+            trade_volume1: "".to_string(),
+            untracked_volume_usd: "0".to_string(),
+            trade_volume_usd0: "10".to_string(),
+            trade_volume_usd1: "10".to_string(),
+            ..Default::default()
+        };
+        let event = pb::pcs::Event {
+            r#type: Some(pb::pcs::event::Type::Swap(swap)),
+            log_ordinal: 1,
+            pair_address: PAIR.to_string(),
+            token0: TOKEN_A.to_string(),
+            token1: TOKEN_B.to_string(),
+            ..Default::default()
+        };
+
+        crate::store_volumes(
+            clock(&BlockBuilder::new(1, 1_600_000_000).build()),
+            pb::pcs::Events {
+                events: vec![event],
+                ..Default::default()
+            },
+            vec![],
+            StoreAddBigFloat::new(&volumes),
+        );
+
+        let volumes = StoreGet::new(&volumes);
+        let total = |key: StoreKey| volumes.get_last(&key.encode());
+        let trade = |token: &str| StoreKey::TokenTotal {
+            token: token.to_string(),
+            field: Field::Trade,
+        };
+        assert!(total(trade(TOKEN_A)).is_some());
+        assert!(total(trade(TOKEN_B)).is_none());
+        assert!(total(StoreKey::PairTotal {
+            pair: PAIR.to_string(),
+            field: Field::Token1,
+        })
+        .is_none());
+        assert!(total(StoreKey::Global { field: Field::Usd }).is_some());
+    }
 }
@@ -0,0 +1,123 @@
+//! Decoders of the values returned by the token `eth_call`s.
+
+use std::convert::TryInto;
+
+use num_bigint::BigUint;
+
+pub fn read_uint32(input: &[u8]) -> Result<u32, String> {
+    if input.len() != 32 {
+        return Err(format!("uint32 invalid length: {}", input.len()));
+    }
+    let as_array: [u8; 4] = input[28..32].try_into().unwrap();
+    Ok(u32::from_be_bytes(as_array))
+}
+
+/// An indexed address, the last 20 bytes of its 32 bytes topic.
+pub fn read_address(topic: &[u8]) -> Result<Vec<u8>, String> {
+    if topic.len() != 32 {
+        return Err(format!("address invalid length: {}", topic.len()));
+    }
+    Ok(topic[12..].to_vec())
+}
+
+pub fn read_uint256(input: &[u8]) -> Result<BigUint, String> {
+    if input.len() != 32 {
+        return Err(format!("uint256 invalid length: {}", input.len()));
+    }
+    Ok(BigUint::from_bytes_be(input))
+}
+
+/// Decodes an ABI encoded string, an empty one is only the offset and the
+/// length words.
+pub fn read_string(input: &[u8]) -> Result<String, String> {
+    if input.len() < 64 {
+        return Err(format!("string invalid length: {}", input.len()));
+    }
+
+    let next = read_uint32(&input[0..32])?;
+    if next != 32 {
+        return Err(format!("invalid string uint32 value: {}", next));
+    };
+
+    let size = read_uint32(&input[32..64])?;
+    let end: usize = (size as usize) + 64;
+
+    if end > input.len() {
+        return Err(format!(
+            "invalid input: end {:?}, length: {:?}, next: {:?}, size: {:?}, whole: {:?}",
+            end,
+            input.len(),
+            next,
+            size,
+            hex::encode(&input[32..64])
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&input[64..end]).to_string())
+}
+
+/// Decodes a string returned as a `bytes32`, like the `name` and `symbol` of
+/// MKR or SAI, its trailing null bytes trimmed.
+pub fn read_bytes32_string(input: &[u8]) -> Result<String, String> {
+    if input.len() != 32 {
+        return Err(format!("bytes32 invalid length: {}", input.len()));
+    }
+
+    let end = input
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last + 1);
+    Ok(String::from_utf8_lossy(&input[0..end]).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_decoded_from_abi_strings_and_bytes32() {
+        let abi_string = hex::decode(
+            "0000000000000000000000000000000000000000000000000000000000000020\
+             0000000000000000000000000000000000000000000000000000000000000004\
+             5553444300000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(read_string(&abi_string).unwrap(), "USDC");
+        assert!(read_string(&abi_string[0..64]).is_err());
+
+        let empty_string = hex::decode(
+            "0000000000000000000000000000000000000000000000000000000000000020\
+             0000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(read_string(&empty_string).unwrap(), "");
+
+        // MKR's symbol()
+        let bytes32 =
+            hex::decode("4d4b520000000000000000000000000000000000000000000000000000000000")
+                .unwrap();
+        assert_eq!(read_bytes32_string(&bytes32).unwrap(), "MKR");
+        assert_eq!(read_bytes32_string(&[0u8; 32]).unwrap(), "");
+        assert!(read_bytes32_string(&abi_string).is_err());
+    }
+
+    #[test]
+    fn uint32_is_read_from_the_last_bytes_of_a_word() {
+        let word = hex::decode("0000000000000000000000000000000000000000000000000000000000000012")
+            .unwrap();
+        assert_eq!(read_uint32(&word).unwrap(), 18);
+        assert!(read_uint32(&word[1..]).is_err());
+    }
+
+    #[test]
+    fn uint256_keeps_every_byte_of_the_word() {
+        // 2^128
+        let word = hex::decode("0000000000000000000000000000000100000000000000000000000000000000")
+            .unwrap();
+        assert_eq!(
+            read_uint256(&word).unwrap().to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert!(read_uint256(&word[1..]).is_err());
+    }
+}
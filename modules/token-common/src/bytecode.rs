@@ -0,0 +1,115 @@
+//! Tells the token standard of a contract from its runtime bytecode, without
+//! an `eth_call`. Solidity and Vyper dispatch on the 4 bytes selector of the
+//! call, each one a `PUSH4` followed by a comparison, so the selectors pushed
+//! by the code are the functions it implements.
+
+use std::collections::HashSet;
+
+const PUSH1: u8 = 0x60;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+
+type Selector = [u8; 4];
+
+// transfer(address,uint256), balanceOf(address), totalSupply(), decimals()
+const ERC20: &[Selector] = &[
+    [0xa9, 0x05, 0x9c, 0xbb],
+    [0x70, 0xa0, 0x82, 0x31],
+    [0x18, 0x16, 0x0d, 0xdd],
+    [0x31, 0x3c, 0xe5, 0x67],
+];
+
+// ownerOf(uint256), safeTransferFrom(address,address,uint256)
+const ERC721: &[Selector] = &[[0x63, 0x52, 0x21, 0x1e], [0x42, 0x84, 0x2e, 0x0e]];
+
+// safeTransferFrom(address,address,uint256,uint256,bytes),
+// safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)
+const ERC1155: &[Selector] = &[[0xf2, 0x42, 0x43, 0x2a], [0x2e, 0xb2, 0xc2, 0xd6]];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Standard {
+    Erc20,
+    Erc721,
+    Erc1155,
+    Unknown,
+}
+
+/// The NFT standards are checked first, an ERC721 often has `balanceOf` and
+/// `totalSupply` as well.
+pub fn classify(code: &[u8]) -> Standard {
+    let selectors = selectors(code);
+    let implements = |standard: &[Selector]| standard.iter().all(|s| selectors.contains(s));
+
+    if implements(ERC1155) {
+        Standard::Erc1155
+    } else if implements(ERC721) {
+        Standard::Erc721
+    } else if implements(ERC20) {
+        Standard::Erc20
+    } else {
+        Standard::Unknown
+    }
+}
+
+/// Every value pushed by a `PUSH4`. The data of the other pushes is skipped,
+/// so bytes of a constant are not read as opcodes.
+fn selectors(code: &[u8]) -> HashSet<Selector> {
+    let mut selectors = HashSet::new();
+
+    let mut i = 0;
+    while i < code.len() {
+        let opcode = code[i];
+        if !(PUSH1..=PUSH32).contains(&opcode) {
+            i += 1;
+            continue;
+        }
+
+        let size = (opcode - PUSH1 + 1) as usize;
+        if opcode == PUSH4 && i + 1 + size <= code.len() {
+            let mut selector = [0u8; 4];
+            selector.copy_from_slice(&code[i + 1..i + 1 + size]);
+            selectors.insert(selector);
+        }
+        i += 1 + size;
+    }
+
+    selectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dispatcher comparing the call's selector to each of `selectors`:
+    /// `DUP1 PUSH4 <selector> EQ PUSH2 <jump> JUMPI`.
+    fn dispatcher(selectors: &[Selector]) -> Vec<u8> {
+        let mut code = vec![];
+        for selector in selectors {
+            code.extend([0x80, PUSH4]);
+            code.extend(selector);
+            code.extend([0x14, 0x61, 0x00, 0x42, 0x57]);
+        }
+        code
+    }
+
+    #[test]
+    fn standards_are_told_from_the_dispatcher() {
+        assert_eq!(classify(&dispatcher(ERC20)), Standard::Erc20);
+        assert_eq!(classify(&dispatcher(&ERC20[0..3])), Standard::Unknown);
+
+        let erc721 = dispatcher(&[ERC721, &ERC20[1..3]].concat());
+        assert_eq!(classify(&erc721), Standard::Erc721);
+        assert_eq!(classify(&dispatcher(ERC1155)), Standard::Erc1155);
+    }
+
+    #[test]
+    fn push_data_is_not_read_as_opcodes() {
+        // a PUSH5 whose data holds a PUSH4 of transfer(address,uint256)
+        let mut code = vec![PUSH4 + 1, PUSH4];
+        code.extend(ERC20[0]);
+        code.extend(dispatcher(&ERC20[1..4]));
+        assert_eq!(classify(&code), Standard::Unknown);
+
+        assert!(selectors(&[PUSH4, 0xa9, 0x05]).is_empty());
+    }
+}
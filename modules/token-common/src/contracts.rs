@@ -0,0 +1,123 @@
+//! Contracts the token substreams look at: created in the block, or
+//! initialized through an OpenZeppelin proxy.
+
+use substreams::{hex, log, Hex};
+use substreams_ethereum::pb::eth as ethpb;
+
+use crate::bytecode::{self, Standard};
+
+const INITIALIZE_METHOD_HASH: [u8; 4] = hex!("1459457a");
+
+/// A contract that could be a token, and how it came to be.
+pub struct Candidate {
+    pub address: Vec<u8>,
+    pub creator: Vec<u8>,
+    pub transaction: Vec<u8>,
+    pub is_proxy: bool,
+    pub implementation: Vec<u8>,
+    /// told from the bytecode of a created contract, `Unknown` for a proxy
+    pub standard: Standard,
+}
+
+/// Contracts created in the block whose bytecode implements one of
+/// `standards`, and contracts initialized through a proxy. Each one is listed
+/// once, a proxy can be created and initialized in the same block.
+pub fn candidates(blk: ethpb::v2::Block, standards: &[Standard]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = vec![];
+
+    for trx in blk.transaction_traces {
+        for call in &trx.calls {
+            if call.state_reverted {
+                continue;
+            }
+            if call.call_type == ethpb::v2::CallType::Create as i32
+                || call.call_type == ethpb::v2::CallType::Call as i32
+            // proxy contract creation
+            {
+                let call_input_len = call.input.len();
+                if call.call_type == ethpb::v2::CallType::Call as i32
+                    && (call_input_len < 4 || call.input[0..4] != INITIALIZE_METHOD_HASH)
+                {
+                    // this will check if a proxy contract has been called to create a token contract.
+                    // if that is the case the Proxy contract will call the initialize function on the token contract
+                    // this is part of the OpenZeppelin Proxy contract standard
+                    continue;
+                }
+
+                let standard = if call.call_type == ethpb::v2::CallType::Create as i32 {
+                    let code = call
+                        .code_changes
+                        .iter()
+                        .rev()
+                        .find(|code_change| code_change.address == call.address)
+                        .map_or(&[][..], |code_change| code_change.new_code.as_ref());
+                    let standard = bytecode::classify(code);
+
+                    log::debug!(
+                        "found contract creation: {}, caller {}, code {}, standard {:?}",
+                        Hex(&call.address),
+                        Hex(&call.caller),
+                        code.len(),
+                        standard,
+                    );
+
+                    if !standards.contains(&standard) {
+                        continue;
+                    }
+                    standard
+                } else {
+                    // the proxy's code only forwards calls, there are no
+                    // selectors to scan
+                    log::debug!(
+                        "found proxy initialization: contract {}, caller {}",
+                        Hex(&call.address),
+                        Hex(&call.caller)
+                    );
+                    Standard::Unknown
+                };
+
+                let is_proxy = call.call_type == ethpb::v2::CallType::Call as i32;
+                let implementation = if is_proxy {
+                    proxy_implementation(&trx.calls, call)
+                } else {
+                    vec![]
+                };
+
+                match candidates
+                    .iter_mut()
+                    .find(|candidate| candidate.address == call.address)
+                {
+                    // created earlier in the block, initialized now
+                    Some(candidate) => {
+                        if is_proxy {
+                            candidate.is_proxy = true;
+                            candidate.implementation = implementation;
+                        }
+                    }
+                    None => candidates.push(Candidate {
+                        address: call.address.clone(),
+                        creator: call.caller.clone(),
+                        transaction: trx.hash.clone(),
+                        is_proxy,
+                        implementation,
+                        standard,
+                    }),
+                }
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Contract a proxy delegated its `initialize` call to, empty when the call
+/// was not delegated.
+fn proxy_implementation(calls: &[ethpb::v2::Call], initialize: &ethpb::v2::Call) -> Vec<u8> {
+    calls
+        .iter()
+        .find(|call| {
+            call.parent_index == initialize.index
+                && call.call_type == ethpb::v2::CallType::Delegate as i32
+        })
+        .map_or(vec![], |call| call.address.clone())
+}
@@ -0,0 +1,14 @@
+//! What the substreams discovering ERC20 tokens (`eth-token`) or NFT contracts
+//! (`eth-nft`) and the ones fetching the tokens of their pairs (`sushiswap`)
+//! share: the token `eth_call`s, the decoders of their responses, the
+//! detection of new contracts, the bytecode scan telling token standards apart
+//! and the `sf.ethereum.tokens.v1` types.
+
+pub mod abi;
+pub mod bytecode;
+pub mod contracts;
+pub mod pb;
+pub mod rpc;
+// never in a wasm build, where `println` is the runtime's import
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
@@ -0,0 +1,3 @@
+#[path = "./sf.ethereum.tokens.v1.rs"]
+#[allow(dead_code)] // prost generates a FILE_DESCRIPTOR_SET const that is not used
+pub mod tokens;
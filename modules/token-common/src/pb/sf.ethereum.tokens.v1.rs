@@ -14,6 +14,91 @@ pub struct Token {
     pub symbol: ::prost::alloc::string::String,
     #[prost(uint64, tag="4")]
     pub decimals: u64,
+    /// how the name and symbol were decoded
+    #[prost(message, optional, tag="5")]
+    pub metadata_source: ::core::option::Option<TokenMetadataSource>,
+    /// totalSupply() when the token was found, in its smallest unit, empty when
+    /// the call failed
+    #[prost(string, tag="6")]
+    pub total_supply: ::prost::alloc::string::String,
+    /// caller of the contract creation, or of initialize for a proxy
+    #[prost(string, tag="7")]
+    pub creator: ::prost::alloc::string::String,
+    #[prost(uint64, tag="8")]
+    pub created_at_block: u64,
+    #[prost(string, tag="9")]
+    pub created_at_tx: ::prost::alloc::string::String,
+    /// found through an OpenZeppelin proxy initialize call
+    #[prost(bool, tag="10")]
+    pub is_proxy: bool,
+    /// contract the proxy delegated initialize to, empty when not a proxy
+    #[prost(string, tag="11")]
+    pub implementation: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transfers {
+    #[prost(message, repeated, tag="1")]
+    pub transfers: ::prost::alloc::vec::Vec<Transfer>,
+}
+/// ERC20 Transfer of a token known to store_tokens, mints are transfers from
+/// the zero address and burns transfers to it
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transfer {
+    #[prost(string, tag="1")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub to: ::prost::alloc::string::String,
+    /// in the token's smallest unit
+    #[prost(string, tag="4")]
+    pub value: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="6")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Approvals {
+    #[prost(message, repeated, tag="1")]
+    pub approvals: ::prost::alloc::vec::Vec<Approval>,
+}
+/// ERC20 Approval of a token known to store_tokens
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Approval {
+    #[prost(string, tag="1")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub spender: ::prost::alloc::string::String,
+    /// in the token's smallest unit
+    #[prost(string, tag="4")]
+    pub value: ::prost::alloc::string::String,
+    /// value is 2^256-1
+    #[prost(bool, tag="5")]
+    pub unlimited: bool,
+    #[prost(string, tag="6")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="7")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenMetadataSource {
+    #[prost(enumeration="MetadataSource", tag="1")]
+    pub name: i32,
+    #[prost(enumeration="MetadataSource", tag="2")]
+    pub symbol: i32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum MetadataSource {
+    /// an ABI encoded string, what ERC20 specifies
+    String = 0,
+    /// a bytes32 with its trailing null bytes trimmed, returned by tokens like MKR or SAI
+    Bytes32 = 1,
+    /// the call reverted or returned nothing usable, the value is a placeholder
+    Fallback = 2,
 }
 /// Encoded file descriptor set for the `sf.ethereum.tokens.v1` package
 pub const FILE_DESCRIPTOR_SET: &[u8] = &[
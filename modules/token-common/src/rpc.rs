@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use substreams_ethereum::pb::eth::rpc::{RpcCall, RpcCalls, RpcResponse};
+
+use crate::abi::{read_bytes32_string, read_string, read_uint256, read_uint32};
+use crate::pb::tokens::{MetadataSource, Token, TokenMetadataSource};
+
+pub const DECIMALS: &str = "313ce567";
+pub const NAME: &str = "06fdde03";
+pub const SYMBOL: &str = "95d89b41";
+pub const TOTAL_SUPPLY: &str = "18160ddd";
+/// `supportsInterface(bytes4)` of the ERC721 and ERC1155 interface ids, ERC165
+/// passes the id left aligned in its word.
+pub const SUPPORTS_ERC721: &str =
+    "01ffc9a780ac58cd00000000000000000000000000000000000000000000000000000000";
+pub const SUPPORTS_ERC1155: &str =
+    "01ffc9a7d9b67a2600000000000000000000000000000000000000000000000000000000";
+
+/// Placeholder of a `name` or `symbol` that could not be decoded.
+pub const UNKNOWN: &str = "unknown";
+
+/// Methods called on token contracts, a call is tagged with its method so its
+/// response is looked up by method instead of by position in the batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Decimals,
+    Name,
+    Symbol,
+    TotalSupply,
+    SupportsErc721,
+    SupportsErc1155,
+}
+
+impl Method {
+    /// Data of the call, the selector followed by its arguments if any.
+    pub fn signature(&self) -> &'static str {
+        match self {
+            Method::Decimals => DECIMALS,
+            Method::Name => NAME,
+            Method::Symbol => SYMBOL,
+            Method::TotalSupply => TOTAL_SUPPLY,
+            Method::SupportsErc721 => SUPPORTS_ERC721,
+            Method::SupportsErc1155 => SUPPORTS_ERC1155,
+        }
+    }
+}
+
+/// The calls `decode_token` reads.
+pub const TOKEN_METHODS: &[Method] = &[Method::Decimals, Method::Name, Method::Symbol];
+
+/// A batch of `eth_call`s, each one tagged with the contract and the method it
+/// was made for.
+#[derive(Default)]
+pub struct RpcRequest {
+    calls: RpcCalls,
+    tags: Vec<(Vec<u8>, Method)>,
+}
+
+impl RpcRequest {
+    pub fn add(&mut self, addr: &[u8], methods: &[Method]) {
+        for method in methods {
+            self.calls.calls.push(RpcCall {
+                to_addr: addr.to_vec(),
+                data: hex::decode(method.signature()).unwrap(),
+            });
+            self.tags.push((addr.to_vec(), *method));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn calls(&self) -> &RpcCalls {
+        &self.calls
+    }
+
+    /// Matches the responses to `calls()` with their tags, grouped by contract.
+    pub fn responses(self, responses: Vec<RpcResponse>) -> HashMap<Vec<u8>, MethodResponses> {
+        assert_eq!(
+            self.tags.len(),
+            responses.len(),
+            "expected one response per call"
+        );
+
+        let mut by_contract: HashMap<Vec<u8>, MethodResponses> = HashMap::new();
+        for ((addr, method), response) in self.tags.into_iter().zip(responses) {
+            by_contract
+                .entry(addr)
+                .or_default()
+                .0
+                .insert(method, response);
+        }
+
+        by_contract
+    }
+}
+
+/// Responses to the calls made to a contract.
+#[derive(Default)]
+pub struct MethodResponses(HashMap<Method, RpcResponse>);
+
+impl MethodResponses {
+    /// Panics when `method` was not part of the request, which is a bug in
+    /// the caller rather than something a contract can do.
+    pub fn get(&self, method: Method) -> &RpcResponse {
+        self.0
+            .get(&method)
+            .unwrap_or_else(|| panic!("no {:?} call was made", method))
+    }
+}
+
+pub fn create_rpc_calls(addr: &[u8], methods: &[Method]) -> RpcRequest {
+    let mut request = RpcRequest::default();
+    request.add(addr, methods);
+    request
+}
+
+/// Token from the responses to the `TOKEN_METHODS` calls. Only `decimals` is
+/// required, a `name` or `symbol` that cannot be decoded is `UNKNOWN`.
+pub fn decode_token(address: String, responses: &MethodResponses) -> Result<Token, String> {
+    let decimals = decode_decimals(responses.get(Method::Decimals))?;
+    let (name, name_source) = decode_name_or_symbol(responses.get(Method::Name));
+    let (symbol, symbol_source) = decode_name_or_symbol(responses.get(Method::Symbol));
+
+    Ok(Token {
+        address,
+        name,
+        symbol,
+        decimals,
+        metadata_source: Some(TokenMetadataSource {
+            name: name_source as i32,
+            symbol: symbol_source as i32,
+        }),
+        ..Default::default()
+    })
+}
+
+pub fn decode_decimals(response: &RpcResponse) -> Result<u64, String> {
+    if response.failed {
+        return Err(format!(
+            "decimals `eth_call` failed: {}",
+            String::from_utf8_lossy(response.raw.as_ref())
+        ));
+    }
+
+    read_uint32(response.raw.as_ref()).map(|decimals| decimals as u64)
+}
+
+/// Raw `totalSupply()`, in the token's smallest unit.
+pub fn decode_total_supply(response: &RpcResponse) -> Result<String, String> {
+    if response.failed {
+        return Err(format!(
+            "totalSupply `eth_call` failed: {}",
+            String::from_utf8_lossy(response.raw.as_ref())
+        ));
+    }
+
+    read_uint256(response.raw.as_ref()).map(|total_supply| total_supply.to_string())
+}
+
+/// Whether a `supportsInterface` call returned true, a contract without
+/// ERC165 reverts or returns nothing.
+pub fn decode_supports_interface(response: &RpcResponse) -> bool {
+    !response.failed && read_uint32(&response.raw) == Ok(1)
+}
+
+/// Decodes the response of a `name()` or `symbol()` call, an ABI string or a
+/// `bytes32`, falling back to `UNKNOWN` when the call reverted or returned
+/// nothing usable.
+pub fn decode_name_or_symbol(response: &RpcResponse) -> (String, MetadataSource) {
+    if response.failed {
+        return (UNKNOWN.to_string(), MetadataSource::Fallback);
+    }
+
+    let decoded = match response.raw.len() {
+        32 => read_bytes32_string(&response.raw).map(|value| (value, MetadataSource::Bytes32)),
+        _ => read_string(&response.raw).map(|value| (value, MetadataSource::String)),
+    };
+
+    match decoded {
+        Ok((value, source)) if !value.is_empty() => (value, source),
+        _ => (UNKNOWN.to_string(), MetadataSource::Fallback),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(raw: &str) -> RpcResponse {
+        RpcResponse {
+            raw: hex::decode(raw).unwrap(),
+            failed: false,
+        }
+    }
+
+    fn reverted() -> RpcResponse {
+        RpcResponse {
+            raw: vec![],
+            failed: true,
+        }
+    }
+
+    /// Responses to the `TOKEN_METHODS` calls of a single contract.
+    fn token_responses(responses: Vec<RpcResponse>) -> MethodResponses {
+        create_rpc_calls(&[1u8; 20], TOKEN_METHODS)
+            .responses(responses)
+            .remove(&[1u8; 20][..])
+            .unwrap()
+    }
+
+    #[test]
+    fn standard_token_is_decoded() {
+        // USDC on Ethereum Mainnet
+        let responses = vec![
+            response("0000000000000000000000000000000000000000000000000000000000000006"),
+            response(
+                "0000000000000000000000000000000000000000000000000000000000000020\
+                 0000000000000000000000000000000000000000000000000000000000000008\
+                 55534420436f696e000000000000000000000000000000000000000000000000",
+            ),
+            response(
+                "0000000000000000000000000000000000000000000000000000000000000020\
+                 0000000000000000000000000000000000000000000000000000000000000004\
+                 5553444300000000000000000000000000000000000000000000000000000000",
+            ),
+        ];
+
+        let token = decode_token("usdc".to_string(), &token_responses(responses)).unwrap();
+        assert_eq!(token.name, "USD Coin");
+        assert_eq!(token.symbol, "USDC");
+        assert_eq!(token.decimals, 6);
+        assert_eq!(
+            token.metadata_source.unwrap().name,
+            MetadataSource::String as i32
+        );
+    }
+
+    #[test]
+    fn bytes32_and_reverted_metadata_fall_back() {
+        // MKR on Ethereum Mainnet, with its name() call reverted
+        let responses = vec![
+            response("0000000000000000000000000000000000000000000000000000000000000012"),
+            reverted(),
+            response("4d4b520000000000000000000000000000000000000000000000000000000000"),
+        ];
+
+        let token = decode_token("mkr".to_string(), &token_responses(responses)).unwrap();
+        assert_eq!(token.name, UNKNOWN);
+        assert_eq!(token.symbol, "MKR");
+        assert_eq!(token.decimals, 18);
+        let source = token.metadata_source.unwrap();
+        assert_eq!(source.name, MetadataSource::Fallback as i32);
+        assert_eq!(source.symbol, MetadataSource::Bytes32 as i32);
+
+        let responses = token_responses(vec![reverted(), reverted(), reverted()]);
+        assert!(decode_token("mkr".to_string(), &responses).is_err());
+    }
+
+    #[test]
+    fn responses_are_matched_by_contract_and_method() {
+        let mut request = create_rpc_calls(&[1u8; 20], &[Method::Name, Method::Decimals]);
+        request.add(&[2u8; 20], &[Method::Decimals]);
+
+        let mut responses = request.responses(vec![response("01"), response("02"), response("03")]);
+        let first = responses.remove(&[1u8; 20][..]).unwrap();
+        let second = responses.remove(&[2u8; 20][..]).unwrap();
+        assert_eq!(first.get(Method::Name).raw, vec![1]);
+        assert_eq!(first.get(Method::Decimals).raw, vec![2]);
+        assert_eq!(second.get(Method::Decimals).raw, vec![3]);
+    }
+}
@@ -0,0 +1,13 @@
+//! Support for running the handlers of the substreams natively in their tests.
+
+/// `substreams::log` writes through the runtime's `println` import, which a
+/// native test binary has to provide itself.
+///
+/// # Safety
+///
+/// `ptr` points to `len` bytes, like the runtime guarantees.
+#[no_mangle]
+pub unsafe extern "C" fn println(ptr: *const u8, len: usize) {
+    let message = std::slice::from_raw_parts(ptr, len);
+    eprintln!("{}", String::from_utf8_lossy(message));
+}
@@ -1,7 +1,17 @@
-use std::io::Result;
+use anyhow::{Ok, Result};
+use substreams_ethereum::Abigen;
 
-fn main() -> Result<()> {
-    let mut prost_build = prost_build::Config::new();
-    prost_build.out_dir("./src/pb");
-    prost_build.compile_protos(&["modules.proto"], &["./proto/"])
+fn main() -> Result<(), anyhow::Error> {
+    let out_dir = std::env::var("OUT_DIR")?;
+
+    for (contract, abi) in [("Factory", "factory"), ("Pool", "pool")] {
+        let abi_path = format!("abis/{}.json", abi);
+        println!("cargo:rerun-if-changed={}", abi_path);
+
+        Abigen::new(contract, &abi_path)?
+            .generate()?
+            .write_to_file(format!("{}/{}.rs", out_dir, abi))?;
+    }
+
+    Ok(())
 }
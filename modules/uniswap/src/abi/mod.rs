@@ -0,0 +1,12 @@
+//! Event structs with their `match_log` and `decode` functions, generated by
+//! `build.rs` from the JSON ABIs under `abis/`.
+
+#[allow(dead_code, clippy::all)]
+pub mod factory {
+    include!(concat!(env!("OUT_DIR"), "/factory.rs"));
+}
+
+#[allow(dead_code, clippy::all)]
+pub mod pool {
+    include!(concat!(env!("OUT_DIR"), "/pool.rs"));
+}
@@ -1,20 +1,31 @@
 use std::rc::Rc;
 
-use once_cell::sync::Lazy;
-
-use crate::pb::eth::{Block, Log, TransactionTrace};
-use crate::util::keccak256;
-
-static POOL_CREATED_SIGNATURE: Lazy<[u8; 32]> = Lazy::new(|| {
-    let signature = "PoolCreated(address,address,uint24,int24,address)";
-    keccak256(signature.as_bytes())
-});
-
-pub struct PoolCreatedEvent {}
+use substreams_ethereum::pb::eth::v2::{Block, Log, TransactionTrace};
+
+use crate::abi::factory::events::PoolCreated;
+use crate::util::{address, int24, uint24};
+
+pub struct PoolCreatedEvent {
+    pub token0: String,
+    pub token1: String,
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub pool: String,
+    pub log_ordinal: u64,
+}
 
 impl PoolCreatedEvent {
-    pub fn matches(log: &Log) -> bool {
-        &log.topics[0] == &*POOL_CREATED_SIGNATURE
+    pub fn decode(log: &Log) -> Result<Self, String> {
+        let event = PoolCreated::decode(log)?;
+
+        Ok(Self {
+            token0: address(&event.token0),
+            token1: address(&event.token1),
+            fee: uint24(&event.fee),
+            tick_spacing: int24(&event.tick_spacing),
+            pool: address(&event.pool),
+            log_ordinal: log.block_index as u64,
+        })
     }
 }
 
@@ -38,9 +49,11 @@ impl FactoryContract {
             .filter(|tx| hex::encode(&tx.to) == self.address)
     }
 
-    pub fn pool_created_events(&self) -> impl Iterator<Item = &Log> {
+    pub fn pool_created_events(&self) -> impl Iterator<Item = Result<PoolCreatedEvent, String>> + '_ {
         self.traces()
-            .flat_map(|tx| tx.receipt.as_ref().unwrap().logs.iter())
-            .filter(|log| PoolCreatedEvent::matches(log))
+            .filter_map(|tx| tx.receipt.as_ref())
+            .flat_map(|receipt| receipt.logs.iter())
+            .filter(|log| PoolCreated::match_log(log))
+            .map(PoolCreatedEvent::decode)
     }
 }
@@ -1 +1,2 @@
 pub mod factory;
+pub mod pool;
@@ -0,0 +1,215 @@
+use substreams_ethereum::pb::eth::v2::Log;
+
+use crate::abi::pool::events;
+use crate::pb::uniswap::pool_event::Type;
+use crate::pb::uniswap::{
+    Burn, Collect, CollectProtocol, Flash, Initialize, Mint, SetFeeProtocol, Swap,
+};
+use crate::util::{address, big_int, big_uint, int24, uint8};
+
+/// Decodes the logs of a pool contract changing its state, other logs (oracle
+/// cardinality) are ignored.
+pub fn decode_event(log: &Log) -> Result<Option<Type>, String> {
+    if events::Initialize::match_log(log) {
+        let initialize = events::Initialize::decode(log)?;
+        return Ok(Some(Type::Initialize(Initialize {
+            sqrt_price: big_uint(&initialize.sqrt_price_x96).to_string(),
+            tick: int24(&initialize.tick),
+        })));
+    }
+
+    if events::Swap::match_log(log) {
+        let swap = events::Swap::decode(log)?;
+        return Ok(Some(Type::Swap(Swap {
+            sender: address(&swap.sender),
+            recipient: address(&swap.recipient),
+            amount0: big_int(&swap.amount0).to_string(),
+            amount1: big_int(&swap.amount1).to_string(),
+            sqrt_price: big_uint(&swap.sqrt_price_x96).to_string(),
+            liquidity: big_uint(&swap.liquidity).to_string(),
+            tick: int24(&swap.tick),
+        })));
+    }
+
+    if events::Mint::match_log(log) {
+        let mint = events::Mint::decode(log)?;
+        return Ok(Some(Type::Mint(Mint {
+            sender: address(&mint.sender),
+            owner: address(&mint.owner),
+            tick_lower: int24(&mint.tick_lower),
+            tick_upper: int24(&mint.tick_upper),
+            amount: big_uint(&mint.amount).to_string(),
+            amount0: big_uint(&mint.amount0).to_string(),
+            amount1: big_uint(&mint.amount1).to_string(),
+        })));
+    }
+
+    if events::Burn::match_log(log) {
+        let burn = events::Burn::decode(log)?;
+        return Ok(Some(Type::Burn(Burn {
+            owner: address(&burn.owner),
+            tick_lower: int24(&burn.tick_lower),
+            tick_upper: int24(&burn.tick_upper),
+            amount: big_uint(&burn.amount).to_string(),
+            amount0: big_uint(&burn.amount0).to_string(),
+            amount1: big_uint(&burn.amount1).to_string(),
+        })));
+    }
+
+    if events::Collect::match_log(log) {
+        let collect = events::Collect::decode(log)?;
+        return Ok(Some(Type::Collect(Collect {
+            owner: address(&collect.owner),
+            recipient: address(&collect.recipient),
+            tick_lower: int24(&collect.tick_lower),
+            tick_upper: int24(&collect.tick_upper),
+            amount0: big_uint(&collect.amount0).to_string(),
+            amount1: big_uint(&collect.amount1).to_string(),
+        })));
+    }
+
+    if events::CollectProtocol::match_log(log) {
+        let collect = events::CollectProtocol::decode(log)?;
+        return Ok(Some(Type::CollectProtocol(CollectProtocol {
+            sender: address(&collect.sender),
+            recipient: address(&collect.recipient),
+            amount0: big_uint(&collect.amount0).to_string(),
+            amount1: big_uint(&collect.amount1).to_string(),
+        })));
+    }
+
+    if events::Flash::match_log(log) {
+        let flash = events::Flash::decode(log)?;
+        return Ok(Some(Type::Flash(Flash {
+            sender: address(&flash.sender),
+            recipient: address(&flash.recipient),
+            amount0: big_uint(&flash.amount0).to_string(),
+            amount1: big_uint(&flash.amount1).to_string(),
+            paid0: big_uint(&flash.paid0).to_string(),
+            paid1: big_uint(&flash.paid1).to_string(),
+        })));
+    }
+
+    if events::SetFeeProtocol::match_log(log) {
+        let set_fee_protocol = events::SetFeeProtocol::decode(log)?;
+        return Ok(Some(Type::SetFeeProtocol(SetFeeProtocol {
+            fee_protocol0: uint8(&set_fee_protocol.fee_protocol0_new),
+            fee_protocol1: uint8(&set_fee_protocol.fee_protocol1_new),
+        })));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block::*;
+    use crate::testing::{Q96, USER};
+
+    fn decode(log: Log) -> Type {
+        decode_event(&log).unwrap().unwrap()
+    }
+
+    #[test]
+    fn swaps_keep_the_sign_of_amounts_and_ticks() {
+        let swap = match decode(swap_log(1_000_000, -990_000, Q96, 5_000, -5)) {
+            Type::Swap(swap) => swap,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        assert_eq!(swap.sender, USER);
+        assert_eq!(swap.amount0, "1000000");
+        assert_eq!(swap.amount1, "-990000");
+        assert_eq!(swap.sqrt_price, Q96.to_string());
+        assert_eq!(swap.liquidity, "5000");
+        assert_eq!(swap.tick, -5);
+    }
+
+    #[test]
+    fn positions_keep_their_tick_range() {
+        assert_eq!(
+            decode(mint_log(-120, 60, 1_000, 10, 20)),
+            Type::Mint(Mint {
+                sender: USER.to_string(),
+                owner: USER.to_string(),
+                tick_lower: -120,
+                tick_upper: 60,
+                amount: "1000".to_string(),
+                amount0: "10".to_string(),
+                amount1: "20".to_string(),
+            })
+        );
+        assert_eq!(
+            decode(burn_log(-120, 60, 400, 4, 8)),
+            Type::Burn(Burn {
+                owner: USER.to_string(),
+                tick_lower: -120,
+                tick_upper: 60,
+                amount: "400".to_string(),
+                amount0: "4".to_string(),
+                amount1: "8".to_string(),
+            })
+        );
+        assert_eq!(
+            decode(collect_log(-120, 60, 5, 9)),
+            Type::Collect(Collect {
+                owner: USER.to_string(),
+                recipient: USER.to_string(),
+                tick_lower: -120,
+                tick_upper: 60,
+                amount0: "5".to_string(),
+                amount1: "9".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn protocol_fee_events_are_decoded() {
+        assert_eq!(
+            decode(initialize_log(Q96, -1)),
+            Type::Initialize(Initialize {
+                sqrt_price: Q96.to_string(),
+                tick: -1,
+            })
+        );
+        assert_eq!(
+            decode(set_fee_protocol_log(4, 10)),
+            Type::SetFeeProtocol(SetFeeProtocol {
+                fee_protocol0: 4,
+                fee_protocol1: 10,
+            })
+        );
+        assert_eq!(
+            decode(collect_protocol_log(3, 7)),
+            Type::CollectProtocol(CollectProtocol {
+                sender: USER.to_string(),
+                recipient: USER.to_string(),
+                amount0: "3".to_string(),
+                amount1: "7".to_string(),
+            })
+        );
+        assert_eq!(
+            decode(flash_log(1_000, 0, 3, 0)),
+            Type::Flash(Flash {
+                sender: USER.to_string(),
+                recipient: USER.to_string(),
+                amount0: "1000".to_string(),
+                amount1: "0".to_string(),
+                paid0: "3".to_string(),
+                paid1: "0".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn other_logs_are_ignored() {
+        let mut log = swap_log(1, -1, Q96, 1, 0);
+        log.topics[0] = vec![0u8; 32];
+        assert_eq!(decode_event(&log), Ok(None));
+
+        let mut log = swap_log(1, -1, Q96, 1, 0);
+        log.data.pop();
+        assert_eq!(decode_event(&log), Ok(None));
+    }
+}
@@ -0,0 +1,26 @@
+//! Keys of the pool stores. Every store is keyed by pool, the state stores add
+//! the name of the value.
+
+/// `pool:{address}`, the `Pool` as created by the factory.
+pub fn pool(address: &str) -> String {
+    format!("pool:{}", address)
+}
+
+/// `pool:{address}:{field}`
+pub fn pool_field(address: &str, field: &str) -> String {
+    format!("pool:{}:{}", address, field)
+}
+
+pub const SQRT_PRICE: &str = "sqrt_price";
+pub const TICK: &str = "tick";
+/// Liquidity reported by the last swap.
+pub const SWAP_LIQUIDITY: &str = "swap_liquidity";
+/// In range liquidity minted minus burned since the last swap.
+pub const LIQUIDITY_SINCE_SWAP: &str = "liquidity_since_swap";
+/// Denominator of the protocol's share of the fees of token0, none when 0.
+pub const FEE_PROTOCOL_0: &str = "fee_protocol0";
+pub const FEE_PROTOCOL_1: &str = "fee_protocol1";
+pub const FEE_GROWTH_GLOBAL_0: &str = "fee_growth_global0_x128";
+pub const FEE_GROWTH_GLOBAL_1: &str = "fee_growth_global1_x128";
+pub const TOTAL_VALUE_LOCKED_0: &str = "total_value_locked_token0";
+pub const TOTAL_VALUE_LOCKED_1: &str = "total_value_locked_token1";
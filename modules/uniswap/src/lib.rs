@@ -1,42 +1,598 @@
 use std::rc::Rc;
+use std::str::FromStr;
 
+mod abi;
 mod contracts;
+mod keys;
 mod pb;
+mod store;
+#[cfg(test)]
+mod testing;
 pub mod util;
 
+use num_bigint::BigInt;
+use substreams::errors::Error;
 use substreams::{log, proto};
+use substreams_ethereum::pb::eth::v2 as eth;
 
 use contracts::factory::FactoryContract;
-use pb::uniswap::{Pool, Pools};
+use contracts::pool;
+use pb::uniswap::pool_event::Type;
+use pb::uniswap::{Pool, PoolEvent, PoolEvents, Pools};
+use util::{decode, parse};
 
-#[no_mangle]
-pub extern "C" fn pools(block_ptr: *mut u8, block_len: usize) {
-    substreams::register_panic_hook();
+const FACTORY_ADDRESS: &str = "1f98431c8ad98523631ae4a59f267346ea31f984";
 
+/// Fee tiers are in hundredths of a bip.
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_pools(blk: eth::Block) -> Result<Pools, Error> {
     let mut pools = Pools { pools: vec![] };
 
-    let block: Rc<pb::eth::Block> = Rc::new(proto::decode_ptr(block_ptr, block_len).unwrap());
+    let block = Rc::new(blk);
+    let header = block.header.as_ref().unwrap();
+    let timestamp = header.timestamp.as_ref().unwrap();
 
-    let factory = FactoryContract::bind(block.clone(), "1f98431c8ad98523631ae4a59f267346ea31f984");
+    let factory = FactoryContract::bind(block.clone(), FACTORY_ADDRESS);
 
     for event in factory.pool_created_events() {
+        let event = event.map_err(|reason| Error::Unexpected(format!("invalid PoolCreated log: {}", reason)))?;
         log::info!("Pool created at block #{}", block.number);
 
-        let mut pool = Pool::default();
-        let header = block.header.as_ref().expect("header");
+        pools.pools.push(Pool {
+            address: event.pool,
+            token0: event.token0,
+            token1: event.token1,
+            fee_tier: event.fee,
+            tick_spacing: event.tick_spacing,
+            log_ordinal: event.log_ordinal,
+            created_at_timestamp: timestamp.seconds as u64,
+            created_at_block_number: block.number,
+            ..Default::default()
+        });
+    }
+
+    Ok(pools)
+}
+
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_pools(pools: Pools, output: store::StoreSet) {
+    for pool in pools.pools {
+        output.set(
+            pool.log_ordinal,
+            keys::pool(&pool.address),
+            &proto::encode(&pool).unwrap(),
+        );
+    }
+}
+
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_pool_events(blk: eth::Block, pools_store: store::StoreGet) -> Result<PoolEvents, Error> {
+    let mut events = PoolEvents { events: vec![] };
+
+    let timestamp = blk.header.as_ref().unwrap().timestamp.as_ref().unwrap().seconds as u64;
+
+    for trx in blk.transaction_traces {
+        let receipt = match trx.receipt {
+            None => continue,
+            Some(receipt) => receipt,
+        };
+
+        for log in receipt.logs {
+            let pool_address = hex::encode(&log.address);
+            if pools_store.get_last(&keys::pool(&pool_address)).is_none() {
+                continue;
+            }
+
+            let decoded = match pool::decode_event(&log) {
+                Ok(decoded) => decoded,
+                Err(reason) => {
+                    log::info!("skipping invalid log emitted by pool {}: {}", pool_address, reason);
+                    continue;
+                }
+            };
+
+            if let Some(r#type) = decoded {
+                events.events.push(PoolEvent {
+                    pool_address,
+                    log_ordinal: log.block_index as u64,
+                    transaction_id: hex::encode(&trx.hash),
+                    timestamp,
+                    r#type: Some(r#type),
+                });
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Sets the `sqrt_price` and `tick` of pools, and the liquidity reported by
+/// swaps, from `Initialize` and `Swap`, and the protocol's share of the fees
+/// from `SetFeeProtocol`.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_prices(events: PoolEvents, output: store::StoreSet) {
+    for event in events.events {
+        let ordinal = event.log_ordinal;
+        let address = &event.pool_address;
+
+        let (sqrt_price, tick) = match event.r#type.unwrap() {
+            Type::Initialize(initialize) => (initialize.sqrt_price, initialize.tick),
+            Type::Swap(swap) => {
+                output.set(
+                    ordinal,
+                    keys::pool_field(address, keys::SWAP_LIQUIDITY),
+                    &Vec::from(swap.liquidity),
+                );
+                (swap.sqrt_price, swap.tick)
+            }
+            Type::SetFeeProtocol(set_fee_protocol) => {
+                output.set(
+                    ordinal,
+                    keys::pool_field(address, keys::FEE_PROTOCOL_0),
+                    &Vec::from(set_fee_protocol.fee_protocol0.to_string()),
+                );
+                output.set(
+                    ordinal,
+                    keys::pool_field(address, keys::FEE_PROTOCOL_1),
+                    &Vec::from(set_fee_protocol.fee_protocol1.to_string()),
+                );
+                continue;
+            }
+            _ => continue,
+        };
+
+        output.set(
+            ordinal,
+            keys::pool_field(address, keys::SQRT_PRICE),
+            &Vec::from(sqrt_price),
+        );
+        output.set(
+            ordinal,
+            keys::pool_field(address, keys::TICK),
+            &Vec::from(tick.to_string()),
+        );
+    }
+}
+
+/// Sums the liquidity minted and burned in range since the last swap. A swap
+/// reports the pool's in range liquidity, which already accounts for the
+/// positions entering or leaving the range as ticks are crossed, so it resets
+/// the sum: the pool's liquidity is the last swap's plus this sum.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_liquidity(events: PoolEvents, prices_store: store::StoreGet, output: store::StoreAddBigInt) {
+    for event in events.events {
+        let ordinal = event.log_ordinal;
+        let address = &event.pool_address;
+        let key = keys::pool_field(address, keys::LIQUIDITY_SINCE_SWAP);
 
-        pool.token0 = hex::encode(&event.topics[1][12..]);
-        pool.token1 = hex::encode(&event.topics[2][12..]);
+        let (tick_lower, tick_upper, amount) = match event.r#type.unwrap() {
+            Type::Swap(_) => {
+                output.delete_prefix(ordinal as i64, &key);
+                continue;
+            }
+            Type::Mint(mint) => (
+                mint.tick_lower,
+                mint.tick_upper,
+                parse::<BigInt>(&mint.amount),
+            ),
+            Type::Burn(burn) => (
+                burn.tick_lower,
+                burn.tick_upper,
+                parse::<BigInt>(&burn.amount).map(|amount| -amount),
+            ),
+            _ => continue,
+        };
 
-        let timestamp = header.timestamp.as_ref().expect("timestamp");
+        let tick = get_at::<i32>(
+            &prices_store,
+            ordinal,
+            &keys::pool_field(address, keys::TICK),
+        );
+        let (tick, amount) = match (tick, amount) {
+            (Ok(None), _) => continue, // not initialized
+            (Ok(Some(tick)), Ok(amount)) => (tick, amount),
+            (Err(reason), _) | (_, Err(reason)) => {
+                log::info!("skipping liquidity of pool {}: {}", address, reason);
+                continue;
+            }
+        };
 
-        pool.created_at_timestamp = timestamp.seconds as u64;
-        pool.created_at_block_number = header.number;
+        if tick_lower <= tick && tick < tick_upper {
+            output.add(ordinal, key, &amount);
+        }
+    }
+}
+
+/// Sums the fee growth per unit of liquidity of swaps and flash loans, once
+/// the protocol took its share. A swap's fee is taken on the input token and
+/// spread over the in range liquidity the swap reports, which is exact unless
+/// the swap crossed initialized ticks.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_fee_growth(
+    events: PoolEvents,
+    pools_store: store::StoreGet,
+    prices_store: store::StoreGet,
+    liquidity_store: store::StoreGet,
+    output: store::StoreAddBigInt,
+) {
+    for event in events.events {
+        let growth = match fee_growth(&event, &pools_store, &prices_store, &liquidity_store) {
+            Ok(growth) => growth,
+            Err(reason) => {
+                log::info!("skipping fees of pool {}: {}", event.pool_address, reason);
+                continue;
+            }
+        };
+
+        let fields = [keys::FEE_GROWTH_GLOBAL_0, keys::FEE_GROWTH_GLOBAL_1];
+        for (field, growth) in fields.into_iter().zip(growth) {
+            if growth != BigInt::from(0) {
+                output.add(
+                    event.log_ordinal,
+                    keys::pool_field(&event.pool_address, field),
+                    &growth,
+                );
+            }
+        }
+    }
+}
+
+/// Fee growth of token0 and token1, as Q128.128, of a swap or a flash loan.
+fn fee_growth(
+    event: &PoolEvent,
+    pools_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    liquidity_store: &store::StoreGet,
+) -> Result<[BigInt; 2], String> {
+    let ordinal = event.log_ordinal;
+    let address = &event.pool_address;
+
+    let (mut fees, liquidity) = match &event.r#type {
+        Some(Type::Swap(swap)) => {
+            let pool = get_pool(pools_store, address)?;
+            let amount0 = parse::<BigInt>(&swap.amount0)?;
+            let amount1 = parse::<BigInt>(&swap.amount1)?;
+            let fee = |amount_in: BigInt| {
+                amount_in * BigInt::from(pool.fee_tier) / BigInt::from(FEE_DENOMINATOR)
+            };
+
+            let fees = match amount0 > BigInt::from(0) {
+                true => [fee(amount0), BigInt::from(0)],
+                false => [BigInt::from(0), fee(amount1)],
+            };
+            (fees, parse::<BigInt>(&swap.liquidity)?)
+        }
+        Some(Type::Flash(flash)) => {
+            let fees = [
+                parse::<BigInt>(&flash.paid0)?,
+                parse::<BigInt>(&flash.paid1)?,
+            ];
+            let swap_liquidity = get_at::<BigInt>(
+                prices_store,
+                ordinal,
+                &keys::pool_field(address, keys::SWAP_LIQUIDITY),
+            )?;
+            let liquidity_since_swap = get_at::<BigInt>(
+                liquidity_store,
+                ordinal,
+                &keys::pool_field(address, keys::LIQUIDITY_SINCE_SWAP),
+            )?;
+            (
+                fees,
+                swap_liquidity.unwrap_or_default() + liquidity_since_swap.unwrap_or_default(),
+            )
+        }
+        _ => return Ok([BigInt::from(0), BigInt::from(0)]),
+    };
+
+    if liquidity == BigInt::from(0) {
+        return Ok([BigInt::from(0), BigInt::from(0)]);
+    }
+
+    let fee_protocols = [keys::FEE_PROTOCOL_0, keys::FEE_PROTOCOL_1];
+    for (fee, field) in fees.iter_mut().zip(fee_protocols) {
+        let fee_protocol = get_at::<u32>(prices_store, ordinal, &keys::pool_field(address, field))?;
+        if let Some(fee_protocol @ 1..) = fee_protocol {
+            *fee -= &*fee / BigInt::from(fee_protocol);
+        }
+    }
+
+    Ok(fees.map(|fee| (fee << 128usize) / &liquidity))
+}
+
+/// Sums the tokens held by pools: minted, swapped in and paid as flash loan
+/// fees in, collected by positions or the protocol out. Burns only credit the
+/// position, the tokens leave the pool when collected.
+#[cfg_attr(not(test), substreams::handlers::store)]
+pub fn store_tvl(events: PoolEvents, output: store::StoreAddBigInt) {
+    for event in events.events {
+        let amounts = match event.r#type.unwrap() {
+            Type::Mint(mint) => (parse::<BigInt>(&mint.amount0), parse(&mint.amount1)),
+            Type::Swap(swap) => (parse(&swap.amount0), parse(&swap.amount1)),
+            Type::Flash(flash) => (parse(&flash.paid0), parse(&flash.paid1)),
+            Type::Collect(collect) => (
+                parse(&collect.amount0).map(|amount: BigInt| -amount),
+                parse(&collect.amount1).map(|amount: BigInt| -amount),
+            ),
+            Type::CollectProtocol(collect) => (
+                parse(&collect.amount0).map(|amount: BigInt| -amount),
+                parse(&collect.amount1).map(|amount: BigInt| -amount),
+            ),
+            _ => continue,
+        };
+
+        let (amount0, amount1) = match amounts {
+            (Ok(amount0), Ok(amount1)) => (amount0, amount1),
+            (Err(reason), _) | (_, Err(reason)) => {
+                log::info!("skipping tokens of pool {}: {}", event.pool_address, reason);
+                continue;
+            }
+        };
+
+        output.add(
+            event.log_ordinal,
+            keys::pool_field(&event.pool_address, keys::TOTAL_VALUE_LOCKED_0),
+            &amount0,
+        );
+        output.add(
+            event.log_ordinal,
+            keys::pool_field(&event.pool_address, keys::TOTAL_VALUE_LOCKED_1),
+            &amount1,
+        );
+    }
+}
+
+/// Emits the state of every pool with an event in the block.
+#[cfg_attr(not(test), substreams::handlers::map)]
+pub fn map_pool_states(
+    events: PoolEvents,
+    pools_store: store::StoreGet,
+    prices_store: store::StoreGet,
+    liquidity_store: store::StoreGet,
+    fee_growth_store: store::StoreGet,
+    tvl_store: store::StoreGet,
+) -> Result<Pools, Error> {
+    let mut pools = Pools { pools: vec![] };
 
-        pools.pools.push(pool);
+    let mut addresses: Vec<String> = vec![];
+    for event in events.events {
+        if !addresses.contains(&event.pool_address) {
+            addresses.push(event.pool_address);
+        }
     }
 
-    if !pools.pools.is_empty() {
-        substreams::output(pools);
+    for address in addresses {
+        pools.pools.push(
+            pool_state(
+                &address,
+                &pools_store,
+                &prices_store,
+                &liquidity_store,
+                &fee_growth_store,
+                &tvl_store,
+            )
+            .map_err(Error::Unexpected)?,
+        );
+    }
+
+    Ok(pools)
+}
+
+fn pool_state(
+    address: &str,
+    pools_store: &store::StoreGet,
+    prices_store: &store::StoreGet,
+    liquidity_store: &store::StoreGet,
+    fee_growth_store: &store::StoreGet,
+    tvl_store: &store::StoreGet,
+) -> Result<Pool, String> {
+    let mut pool = get_pool(pools_store, address)?;
+
+    let get_bigint = |store: &store::StoreGet, field: &str| -> Result<BigInt, String> {
+        get_last(store, &keys::pool_field(address, field)).map(Option::unwrap_or_default)
+    };
+
+    pool.sqrt_price =
+        get_last::<String>(prices_store, &keys::pool_field(address, keys::SQRT_PRICE))?
+            .unwrap_or_default();
+    pool.tick = get_last(prices_store, &keys::pool_field(address, keys::TICK))?.unwrap_or_default();
+    pool.liquidity = (get_bigint(prices_store, keys::SWAP_LIQUIDITY)?
+        + get_bigint(liquidity_store, keys::LIQUIDITY_SINCE_SWAP)?)
+    .to_string();
+    pool.fee_growth_global0_x128 =
+        get_bigint(fee_growth_store, keys::FEE_GROWTH_GLOBAL_0)?.to_string();
+    pool.fee_growth_global1_x128 =
+        get_bigint(fee_growth_store, keys::FEE_GROWTH_GLOBAL_1)?.to_string();
+    pool.total_value_locked_token0 = get_bigint(tvl_store, keys::TOTAL_VALUE_LOCKED_0)?.to_string();
+    pool.total_value_locked_token1 = get_bigint(tvl_store, keys::TOTAL_VALUE_LOCKED_1)?.to_string();
+
+    Ok(pool)
+}
+
+fn get_pool(pools_store: &store::StoreGet, address: &str) -> Result<Pool, String> {
+    let key = keys::pool(address);
+    match pools_store.get_last(&key) {
+        None => Err(format!("unknown pool {}", address)),
+        Some(value) => {
+            proto::decode(&value).map_err(|error| format!("invalid pool under {}: {}", key, error))
+        }
+    }
+}
+
+fn get_at<T: FromStr>(
+    store: &store::StoreGet,
+    ordinal: u64,
+    key: &str,
+) -> Result<Option<T>, String> {
+    store
+        .get_at(ordinal, key)
+        .map(|value| decode(key, &value))
+        .transpose()
+}
+
+fn get_last<T: FromStr>(store: &store::StoreGet, key: &str) -> Result<Option<T>, String> {
+    store
+        .get_last(key)
+        .map(|value| decode(key, &value))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block::*;
+    use crate::testing::store::*;
+    use crate::testing::*;
+
+    fn pool_block(number: u64, logs: Vec<eth::Log>) -> eth::Block {
+        BlockBuilder::new(number, 1_600_000_000 + number as i64 * 12)
+            .transaction(USER, logs.into_iter().map(|log| (POOL, log)).collect())
+            .build()
+    }
+
+    fn state(pools: Pools) -> Pool {
+        assert_eq!(pools.pools.len(), 1);
+        pools.pools.into_iter().next().unwrap()
+    }
+
+    fn growth(fee: u64, liquidity: u64) -> String {
+        ((BigInt::from(fee) << 128usize) / BigInt::from(liquidity)).to_string()
+    }
+
+    #[test]
+    fn liquidity_counts_in_range_positions_until_the_next_swap() {
+        let pipeline = Pipeline::with_pool();
+
+        let pool = state(pipeline.process_block(pool_block(
+            2,
+            vec![
+                mint_log(-60, 60, 1_000, 10, 10),
+                mint_log(60, 120, 500, 0, 5),
+                burn_log(-60, 60, 400, 4, 4),
+            ],
+        )));
+        assert_eq!(pool.tick, 0);
+        assert_eq!(pool.liquidity, "600");
+
+        // The swap reports the liquidity in range once it moved the price.
+        let pool = state(pipeline.process_block(pool_block(
+            3,
+            vec![
+                swap_log(1_000, -990, Q96, 5_000, -10),
+                mint_log(-60, 60, 100, 1, 1),
+                mint_log(0, 60, 100, 0, 1),
+            ],
+        )));
+        assert_eq!(pool.tick, -10);
+        assert_eq!(pool.liquidity, "5100");
+    }
+
+    #[test]
+    fn fee_growth_leaves_out_the_protocol_fee() {
+        let pipeline = Pipeline::with_pool();
+
+        let pool = state(pipeline.process_block(pool_block(
+            2,
+            vec![swap_log(1_000_000, -990_000, Q96, 1_000_000, 0)],
+        )));
+        assert_eq!(pool.fee_growth_global0_x128, growth(3_000, 1_000_000));
+        assert_eq!(pool.fee_growth_global1_x128, "0");
+
+        // A fourth of the fees of token0 and a fifth of those of token1 go to
+        // the protocol.
+        let pool = state(pipeline.process_block(pool_block(
+            3,
+            vec![
+                set_fee_protocol_log(4, 5),
+                swap_log(1_000_000, -990_000, Q96, 1_000_000, 0),
+                swap_log(-990_000, 2_000_000, Q96, 2_000_000, 0),
+            ],
+        )));
+        let fee_growth0 = BigInt::from_str(&growth(3_000, 1_000_000)).unwrap()
+            + BigInt::from_str(&growth(2_250, 1_000_000)).unwrap();
+        assert_eq!(pool.fee_growth_global0_x128, fee_growth0.to_string());
+        assert_eq!(pool.fee_growth_global1_x128, growth(4_800, 2_000_000));
+    }
+
+    #[test]
+    fn flash_fees_grow_fees_and_tokens_locked() {
+        let pipeline = Pipeline::with_pool();
+
+        let pool = state(pipeline.process_block(pool_block(
+            2,
+            vec![
+                mint_log(-60, 60, 1_000, 100, 200),
+                flash_log(50, 0, 30, 0),
+                collect_protocol_log(5, 0),
+                collect_log(-60, 60, 10, 20),
+            ],
+        )));
+        assert_eq!(pool.fee_growth_global0_x128, growth(30, 1_000));
+        assert_eq!(pool.fee_growth_global1_x128, "0");
+        assert_eq!(pool.total_value_locked_token0, "115");
+        assert_eq!(pool.total_value_locked_token1, "180");
+    }
+
+    #[test]
+    fn tokens_locked_follow_swaps_and_burns_once_collected() {
+        let pipeline = Pipeline::with_pool();
+
+        let pool = state(pipeline.process_block(pool_block(
+            2,
+            vec![
+                mint_log(-60, 60, 1_000, 100, 200),
+                swap_log(50, -40, Q96, 1_000, 0),
+                burn_log(-60, 60, 500, 75, 80),
+            ],
+        )));
+        assert_eq!(pool.total_value_locked_token0, "150");
+        assert_eq!(pool.total_value_locked_token1, "160");
+
+        let pool = state(pipeline.process_block(pool_block(3, vec![collect_log(-60, 60, 75, 80)])));
+        assert_eq!(pool.total_value_locked_token0, "75");
+        assert_eq!(pool.total_value_locked_token1, "80");
+    }
+
+    #[test]
+    fn unknown_pools_are_skipped_by_the_fee_growth() {
+        let pipeline = Pipeline::new();
+        let events = PoolEvents {
+            events: vec![PoolEvent {
+                pool_address: POOL.to_string(),
+                log_ordinal: 1,
+                r#type: Some(Type::Swap(pb::uniswap::Swap {
+                    amount0: "1000".to_string(),
+                    amount1: "-990".to_string(),
+                    liquidity: "1000".to_string(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }],
+        };
+
+        store_fee_growth(
+            events,
+            StoreGet::new(&pipeline.pools),
+            StoreGet::new(&pipeline.prices),
+            StoreGet::new(&pipeline.liquidity),
+            StoreAddBigInt::new(&pipeline.fee_growth),
+        );
+
+        let fee_growth = StoreGet::new(&pipeline.fee_growth);
+        assert_eq!(
+            fee_growth.get_last(&keys::pool_field(POOL, keys::FEE_GROWTH_GLOBAL_0)),
+            None
+        );
+    }
+
+    #[test]
+    fn corrupted_states_are_errors() {
+        let pipeline = Pipeline::with_pool();
+        StoreSet::new(&pipeline.prices).set(0, keys::pool_field(POOL, keys::TICK), b"not a tick");
+
+        let result = pipeline.try_process_block(pool_block(2, vec![collect_log(-60, 60, 1, 1)]));
+        assert!(result.is_err());
     }
 }
@@ -1,5 +1,2 @@
-#[path = "./dfuse.ethereum.r#type.v1.rs"]
-pub mod eth;
-
 #[path = "./uniswap.types.v1.rs"]
 pub mod uniswap;
@@ -13,4 +13,176 @@ pub struct Pool {
     pub token0: ::prost::alloc::string::String,
     #[prost(string, tag="4")]
     pub token1: ::prost::alloc::string::String,
+    #[prost(uint32, tag="5")]
+    pub fee_tier: u32,
+    #[prost(string, tag="6")]
+    pub liquidity: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub sqrt_price: ::prost::alloc::string::String,
+    #[prost(string, tag="8")]
+    pub fee_growth_global0_x128: ::prost::alloc::string::String,
+    #[prost(string, tag="9")]
+    pub fee_growth_global1_x128: ::prost::alloc::string::String,
+    #[prost(int32, tag="12")]
+    pub tick: i32,
+    /// in the tokens' smallest unit
+    #[prost(string, tag="23")]
+    pub total_value_locked_token0: ::prost::alloc::string::String,
+    #[prost(string, tag="24")]
+    pub total_value_locked_token1: ::prost::alloc::string::String,
+    #[prost(string, tag="29")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(int32, tag="30")]
+    pub tick_spacing: i32,
+    #[prost(uint64, tag="31")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolEvents {
+    #[prost(message, repeated, tag="1")]
+    pub events: ::prost::alloc::vec::Vec<PoolEvent>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolEvent {
+    #[prost(string, tag="1")]
+    pub pool_address: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub log_ordinal: u64,
+    #[prost(string, tag="3")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="4")]
+    pub timestamp: u64,
+    #[prost(oneof="pool_event::Type", tags="10, 11, 12, 13, 14, 15, 16, 17")]
+    pub r#type: ::core::option::Option<pool_event::Type>,
+}
+/// Nested message and enum types in `PoolEvent`.
+pub mod pool_event {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Type {
+        #[prost(message, tag="10")]
+        Initialize(super::Initialize),
+        #[prost(message, tag="11")]
+        Swap(super::Swap),
+        #[prost(message, tag="12")]
+        Mint(super::Mint),
+        #[prost(message, tag="13")]
+        Burn(super::Burn),
+        #[prost(message, tag="14")]
+        Collect(super::Collect),
+        #[prost(message, tag="15")]
+        CollectProtocol(super::CollectProtocol),
+        #[prost(message, tag="16")]
+        Flash(super::Flash),
+        #[prost(message, tag="17")]
+        SetFeeProtocol(super::SetFeeProtocol),
+    }
+}
+/// Amounts are integers in the tokens' smallest unit, prices are Q64.96
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Initialize {
+    #[prost(string, tag="1")]
+    pub sqrt_price: ::prost::alloc::string::String,
+    #[prost(int32, tag="2")]
+    pub tick: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Swap {
+    #[prost(string, tag="1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub recipient: ::prost::alloc::string::String,
+    /// signed, positive when going into the pool
+    #[prost(string, tag="3")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub amount1: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub sqrt_price: ::prost::alloc::string::String,
+    /// in range liquidity after the swap
+    #[prost(string, tag="6")]
+    pub liquidity: ::prost::alloc::string::String,
+    #[prost(int32, tag="7")]
+    pub tick: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Mint {
+    #[prost(string, tag="1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(int32, tag="3")]
+    pub tick_lower: i32,
+    #[prost(int32, tag="4")]
+    pub tick_upper: i32,
+    #[prost(string, tag="5")]
+    pub amount: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="7")]
+    pub amount1: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Burn {
+    #[prost(string, tag="1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(int32, tag="2")]
+    pub tick_lower: i32,
+    #[prost(int32, tag="3")]
+    pub tick_upper: i32,
+    #[prost(string, tag="4")]
+    pub amount: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub amount1: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Collect {
+    #[prost(string, tag="1")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub recipient: ::prost::alloc::string::String,
+    #[prost(int32, tag="3")]
+    pub tick_lower: i32,
+    #[prost(int32, tag="4")]
+    pub tick_upper: i32,
+    #[prost(string, tag="5")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub amount1: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CollectProtocol {
+    #[prost(string, tag="1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub recipient: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub amount1: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Flash {
+    #[prost(string, tag="1")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub recipient: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub amount0: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub amount1: ::prost::alloc::string::String,
+    /// fees paid back on top of the borrowed amounts
+    #[prost(string, tag="5")]
+    pub paid0: ::prost::alloc::string::String,
+    #[prost(string, tag="6")]
+    pub paid1: ::prost::alloc::string::String,
+}
+/// The protocol takes 1/n of the swap and flash fees, none when 0
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetFeeProtocol {
+    #[prost(uint32, tag="1")]
+    pub fee_protocol0: u32,
+    #[prost(uint32, tag="2")]
+    pub fee_protocol1: u32,
 }
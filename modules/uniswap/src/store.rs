@@ -0,0 +1,9 @@
+// Store handles given to the handlers. When running the tests natively, they are
+// swapped for the in-memory implementations of `testing::store` so the handlers
+// can be exercised without the substreams runtime.
+
+#[cfg(not(test))]
+pub use substreams::store::*;
+
+#[cfg(test)]
+pub use crate::testing::store::*;
@@ -0,0 +1,234 @@
+use substreams::hex;
+use substreams_ethereum::pb::eth::v2::{
+    Block, BlockHeader, Log, TransactionReceipt, TransactionTrace,
+};
+
+use crate::testing::USER;
+
+const POOL_CREATED: [u8; 32] =
+    hex!("783cca1c0412dd0d695e784568c96da2e9c22ff989357a2e8b1d9b2b4e6b7118");
+const INITIALIZE: [u8; 32] =
+    hex!("98636036cb66a9c19a37435efc1e90142190214e8abeb821bdba3f2990dd4c95");
+const SWAP: [u8; 32] = hex!("c42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67");
+const MINT: [u8; 32] = hex!("7a53080ba414158be7ec69b987b5fb7d07dee101fe85488f0853ae16239d0bde");
+const BURN: [u8; 32] = hex!("0c396cd989a39f4459b5fa1aed6a9a8dcdbc45908acfd67e028cd568da98982c");
+const COLLECT: [u8; 32] = hex!("70935338e69775456a85ddef226c395fb668b63fa0115f5f20610b388e6ca9c0");
+const COLLECT_PROTOCOL: [u8; 32] =
+    hex!("596b573906218d3411850b26a6b437d6c4522fdb43d2d2386263f86d50b8b151");
+const FLASH: [u8; 32] = hex!("bdbdb71d7860376ba52b25a5028beea23581364a40522f6bcfb86bb1f2dca633");
+const SET_FEE_PROTOCOL: [u8; 32] =
+    hex!("973d8d92bb299f4af6ce49b52a8adb85ae46b9f214c4c4fc06ac77401237b133");
+
+/// Builds synthetic blocks. Log `block_index` values are assigned in insertion
+/// order, like on chain.
+pub struct BlockBuilder {
+    block: Block,
+    next_log_index: u32,
+}
+
+impl BlockBuilder {
+    pub fn new(number: u64, timestamp_seconds: i64) -> BlockBuilder {
+        BlockBuilder {
+            block: Block {
+                number,
+                hash: number.to_be_bytes().to_vec(),
+                header: Some(BlockHeader {
+                    number,
+                    timestamp: Some(prost_types::Timestamp {
+                        seconds: timestamp_seconds,
+                        nanos: 0,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            next_log_index: 0,
+        }
+    }
+
+    /// Adds a transaction to `to`, each entry of `logs` being the address of the
+    /// emitting contract and the log.
+    pub fn transaction(mut self, to: &str, logs: Vec<(&str, Log)>) -> Self {
+        let trx_index = self.block.transaction_traces.len() as u32;
+        let mut trx = TransactionTrace {
+            hash: [
+                self.block.number.to_be_bytes(),
+                (trx_index as u64).to_be_bytes(),
+            ]
+            .concat(),
+            to: address(to),
+            index: trx_index,
+            receipt: Some(TransactionReceipt::default()),
+            ..Default::default()
+        };
+
+        for (emitter, mut log) in logs {
+            log.address = address(emitter);
+            log.block_index = self.next_log_index;
+            self.next_log_index += 1;
+            trx.receipt.as_mut().unwrap().logs.push(log);
+        }
+
+        self.block.transaction_traces.push(trx);
+        self
+    }
+
+    pub fn build(self) -> Block {
+        self.block
+    }
+}
+
+pub fn address(address: &str) -> Vec<u8> {
+    hex::decode(address).unwrap()
+}
+
+pub fn uint(value: u128) -> Vec<u8> {
+    [[0u8; 16], value.to_be_bytes()].concat()
+}
+
+/// Two's complement, sign extended to 32 bytes.
+pub fn int(value: i128) -> Vec<u8> {
+    let extension = match value < 0 {
+        true => [0xffu8; 16],
+        false => [0u8; 16],
+    };
+    [extension, value.to_be_bytes()].concat()
+}
+
+fn topic_address(value: &str) -> Vec<u8> {
+    [vec![0u8; 12], address(value)].concat()
+}
+
+pub fn pool_created_log(
+    token0: &str,
+    token1: &str,
+    fee: u32,
+    tick_spacing: i32,
+    pool: &str,
+) -> Log {
+    Log {
+        topics: vec![
+            POOL_CREATED.to_vec(),
+            topic_address(token0),
+            topic_address(token1),
+            uint(fee as u128),
+        ],
+        data: [int(tick_spacing as i128), topic_address(pool)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn initialize_log(sqrt_price: u128, tick: i32) -> Log {
+    Log {
+        topics: vec![INITIALIZE.to_vec()],
+        data: [uint(sqrt_price), int(tick as i128)].concat(),
+        ..Default::default()
+    }
+}
+
+/// Amounts are positive when going into the pool.
+pub fn swap_log(amount0: i128, amount1: i128, sqrt_price: u128, liquidity: u128, tick: i32) -> Log {
+    Log {
+        topics: vec![SWAP.to_vec(), topic_address(USER), topic_address(USER)],
+        data: [
+            int(amount0),
+            int(amount1),
+            uint(sqrt_price),
+            uint(liquidity),
+            int(tick as i128),
+        ]
+        .concat(),
+        ..Default::default()
+    }
+}
+
+pub fn mint_log(
+    tick_lower: i32,
+    tick_upper: i32,
+    amount: u128,
+    amount0: u128,
+    amount1: u128,
+) -> Log {
+    Log {
+        topics: vec![
+            MINT.to_vec(),
+            topic_address(USER),
+            int(tick_lower as i128),
+            int(tick_upper as i128),
+        ],
+        data: [
+            topic_address(USER),
+            uint(amount),
+            uint(amount0),
+            uint(amount1),
+        ]
+        .concat(),
+        ..Default::default()
+    }
+}
+
+pub fn burn_log(
+    tick_lower: i32,
+    tick_upper: i32,
+    amount: u128,
+    amount0: u128,
+    amount1: u128,
+) -> Log {
+    Log {
+        topics: vec![
+            BURN.to_vec(),
+            topic_address(USER),
+            int(tick_lower as i128),
+            int(tick_upper as i128),
+        ],
+        data: [uint(amount), uint(amount0), uint(amount1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn collect_log(tick_lower: i32, tick_upper: i32, amount0: u128, amount1: u128) -> Log {
+    Log {
+        topics: vec![
+            COLLECT.to_vec(),
+            topic_address(USER),
+            int(tick_lower as i128),
+            int(tick_upper as i128),
+        ],
+        data: [topic_address(USER), uint(amount0), uint(amount1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn collect_protocol_log(amount0: u128, amount1: u128) -> Log {
+    Log {
+        topics: vec![
+            COLLECT_PROTOCOL.to_vec(),
+            topic_address(USER),
+            topic_address(USER),
+        ],
+        data: [uint(amount0), uint(amount1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn flash_log(amount0: u128, amount1: u128, paid0: u128, paid1: u128) -> Log {
+    Log {
+        topics: vec![FLASH.to_vec(), topic_address(USER), topic_address(USER)],
+        data: [uint(amount0), uint(amount1), uint(paid0), uint(paid1)].concat(),
+        ..Default::default()
+    }
+}
+
+pub fn set_fee_protocol_log(fee_protocol0: u8, fee_protocol1: u8) -> Log {
+    Log {
+        topics: vec![SET_FEE_PROTOCOL.to_vec()],
+        data: [
+            uint(0),
+            uint(0),
+            uint(fee_protocol0 as u128),
+            uint(fee_protocol1 as u128),
+        ]
+        .concat(),
+        ..Default::default()
+    }
+}
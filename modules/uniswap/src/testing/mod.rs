@@ -0,0 +1,111 @@
+//! Harness running the handlers natively, wired the same way as in
+//! `substreams.yaml`, with in-memory stores standing in for the runtime ones.
+
+// nothing else of token-common is used, but its `println` has to be linked in
+extern crate token_common;
+
+use substreams_ethereum::pb::eth::v2::Block;
+
+use crate::pb::uniswap::Pools;
+use crate::testing::block::*;
+use crate::testing::store::*;
+
+pub mod block;
+pub mod store;
+
+pub struct Pipeline {
+    pub pools: MemoryStoreRef,
+    pub prices: MemoryStoreRef,
+    pub liquidity: MemoryStoreRef,
+    pub fee_growth: MemoryStoreRef,
+    pub tvl: MemoryStoreRef,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline {
+            pools: MemoryStore::new_ref(),
+            prices: MemoryStore::new_ref(),
+            liquidity: MemoryStore::new_ref(),
+            fee_growth: MemoryStore::new_ref(),
+            tvl: MemoryStore::new_ref(),
+        }
+    }
+
+    /// A pipeline with the 0.3% `POOL` created at block 1 and initialized at
+    /// tick 0.
+    pub fn with_pool() -> Pipeline {
+        let pipeline = Pipeline::new();
+        pipeline.process_block(
+            BlockBuilder::new(1, 1_600_000_000)
+                .transaction(
+                    crate::FACTORY_ADDRESS,
+                    vec![
+                        (
+                            crate::FACTORY_ADDRESS,
+                            pool_created_log(TOKEN0, TOKEN1, 3000, 60, POOL),
+                        ),
+                        (POOL, initialize_log(Q96, 0)),
+                    ],
+                )
+                .build(),
+        );
+        pipeline
+    }
+
+    /// Runs `blk` through every module and returns the `map_pool_states` output.
+    pub fn process_block(&self, blk: Block) -> Pools {
+        self.try_process_block(blk).unwrap()
+    }
+
+    pub fn try_process_block(&self, blk: Block) -> Result<Pools, substreams::errors::Error> {
+        for store in self.stores() {
+            store.borrow_mut().flush();
+        }
+
+        let pools = crate::map_pools(blk.clone())?;
+        crate::store_pools(pools, StoreSet::new(&self.pools));
+
+        let events = crate::map_pool_events(blk, StoreGet::new(&self.pools))?;
+        crate::store_prices(events.clone(), StoreSet::new(&self.prices));
+        crate::store_liquidity(
+            events.clone(),
+            StoreGet::new(&self.prices),
+            StoreAddBigInt::new(&self.liquidity),
+        );
+        crate::store_fee_growth(
+            events.clone(),
+            StoreGet::new(&self.pools),
+            StoreGet::new(&self.prices),
+            StoreGet::new(&self.liquidity),
+            StoreAddBigInt::new(&self.fee_growth),
+        );
+        crate::store_tvl(events.clone(), StoreAddBigInt::new(&self.tvl));
+
+        crate::map_pool_states(
+            events,
+            StoreGet::new(&self.pools),
+            StoreGet::new(&self.prices),
+            StoreGet::new(&self.liquidity),
+            StoreGet::new(&self.fee_growth),
+            StoreGet::new(&self.tvl),
+        )
+    }
+
+    fn stores(&self) -> Vec<&MemoryStoreRef> {
+        vec![
+            &self.pools,
+            &self.prices,
+            &self.liquidity,
+            &self.fee_growth,
+            &self.tvl,
+        ]
+    }
+}
+
+pub const USER: &str = "00000000000000000000000000000000000000aa";
+pub const TOKEN0: &str = "00000000000000000000000000000000000000a1";
+pub const TOKEN1: &str = "00000000000000000000000000000000000000a2";
+pub const POOL: &str = "00000000000000000000000000000000000000b1";
+/// Square root price of 1, as Q64.96.
+pub const Q96: u128 = 1 << 96;
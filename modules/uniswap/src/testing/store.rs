@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use substreams::pb::substreams::{store_delta, StoreDelta};
+
+/// In-memory key/value store keeping the deltas of the block being processed,
+/// which gives `get_at` the same ordinal semantics as the runtime.
+#[derive(Default)]
+pub struct MemoryStore {
+    kv: BTreeMap<String, Vec<u8>>,
+    deltas: Vec<StoreDelta>,
+}
+
+pub type MemoryStoreRef = Rc<RefCell<MemoryStore>>;
+
+impl MemoryStore {
+    pub fn new_ref() -> MemoryStoreRef {
+        Rc::new(RefCell::new(MemoryStore::default()))
+    }
+
+    /// Starts a new block: the current values become the base state.
+    pub fn flush(&mut self) {
+        self.deltas.clear();
+    }
+
+    fn get_at(&self, ord: u64, key: &str) -> Option<Vec<u8>> {
+        let mut value = self.kv.get(key).cloned();
+        for delta in self.deltas.iter().rev() {
+            if delta.ordinal <= ord || delta.key != key {
+                continue;
+            }
+            value = match delta.operation == store_delta::Operation::Create as i32 {
+                true => None,
+                false => Some(delta.old_value.clone()),
+            };
+        }
+        value
+    }
+
+    fn set(&mut self, ord: u64, key: String, value: Vec<u8>) {
+        let old_value = self.kv.insert(key.clone(), value.clone());
+        let operation = match old_value {
+            Some(_) => store_delta::Operation::Update,
+            None => store_delta::Operation::Create,
+        };
+
+        self.deltas.push(StoreDelta {
+            operation: operation as i32,
+            ordinal: ord,
+            key,
+            old_value: old_value.unwrap_or_default(),
+            new_value: value,
+        });
+    }
+
+    fn delete_prefix(&mut self, ord: u64, prefix: &str) {
+        let keys: Vec<String> = self
+            .kv
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        for key in keys {
+            let old_value = self.kv.remove(&key).unwrap();
+            self.deltas.push(StoreDelta {
+                operation: store_delta::Operation::Delete as i32,
+                ordinal: ord,
+                key,
+                old_value,
+                new_value: vec![],
+            });
+        }
+    }
+}
+
+pub struct StoreGet {
+    store: MemoryStoreRef,
+}
+
+impl StoreGet {
+    pub fn new(store: &MemoryStoreRef) -> StoreGet {
+        StoreGet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn get_at(&self, ord: u64, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().get_at(ord, key)
+    }
+
+    pub fn get_last(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.borrow().kv.get(key).cloned()
+    }
+}
+
+pub struct StoreSet {
+    store: MemoryStoreRef,
+}
+
+impl StoreSet {
+    pub fn new(store: &MemoryStoreRef) -> StoreSet {
+        StoreSet {
+            store: store.clone(),
+        }
+    }
+
+    pub fn set(&self, ord: u64, key: String, value: &[u8]) {
+        self.store.borrow_mut().set(ord, key, value.to_vec());
+    }
+}
+
+pub struct StoreAddBigInt {
+    store: MemoryStoreRef,
+}
+
+impl StoreAddBigInt {
+    pub fn new(store: &MemoryStoreRef) -> StoreAddBigInt {
+        StoreAddBigInt {
+            store: store.clone(),
+        }
+    }
+
+    pub fn add(&self, ord: u64, key: String, value: &BigInt) {
+        let mut store = self.store.borrow_mut();
+        let current = match store.kv.get(&key) {
+            None => BigInt::default(),
+            Some(current) => BigInt::from_str(&String::from_utf8_lossy(current)).unwrap(),
+        };
+        store.set(ord, key, (current + value).to_string().into_bytes());
+    }
+
+    pub fn delete_prefix(&self, ord: i64, prefix: &str) {
+        self.store.borrow_mut().delete_prefix(ord as u64, prefix);
+    }
+}
+
+#[test]
+fn get_at_sees_deleted_values_before_their_ordinal() {
+    let store = MemoryStore::new_ref();
+    let output = StoreAddBigInt::new(&store);
+    output.add(1, "key".to_string(), &BigInt::from(5));
+    store.borrow_mut().flush();
+
+    output.delete_prefix(10, "key");
+    output.add(20, "key".to_string(), &BigInt::from(2));
+
+    let input = StoreGet::new(&store);
+    assert_eq!(input.get_at(5, "key"), Some(b"5".to_vec()));
+    assert_eq!(input.get_at(10, "key"), None);
+    assert_eq!(input.get_at(20, "key"), Some(b"2".to_vec()));
+    assert_eq!(input.get_last("key"), Some(b"2".to_vec()));
+}
@@ -1,9 +1,42 @@
-use tiny_keccak::{Hasher, Keccak};
-
-pub fn keccak256(data: &[u8]) -> [u8; 32] {
-    let mut hasher = Keccak::v256();
-    let mut out: [u8; 32] = [0; 32];
-    hasher.update(data);
-    hasher.finalize(&mut out);
-    out
+use std::str::FromStr;
+
+use num_bigint::{BigInt, BigUint};
+
+/// Addresses as lowercase hex, without `0x`.
+pub fn address(value: &[u8]) -> String {
+    hex::encode(value)
+}
+
+/// Signed values are decoded as `BigInt`s, an `int24` always fits an `i32`.
+pub fn int24(value: &BigInt) -> i32 {
+    i32::try_from(value).expect("int24 out of the i32 range")
+}
+
+pub fn uint8(value: &ethabi::Uint) -> u32 {
+    value.low_u32()
+}
+
+pub fn uint24(value: &ethabi::Uint) -> u32 {
+    value.low_u32()
+}
+
+pub fn big_uint(value: &ethabi::Uint) -> BigUint {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    BigUint::from_bytes_be(&bytes)
+}
+
+pub fn big_int(value: &BigInt) -> BigInt {
+    value.clone()
+}
+
+/// Parses a number kept as a string in an event.
+pub fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    T::from_str(value).map_err(|_| format!("invalid number {:?}", value))
+}
+
+/// Decodes a number kept as a string in a store.
+pub fn decode<T: FromStr>(key: &str, value: &[u8]) -> Result<T, String> {
+    let value = std::str::from_utf8(value).map_err(|_| format!("invalid utf-8 under {}", key))?;
+    T::from_str(value).map_err(|_| format!("invalid number {:?} under {}", value, key))
 }